@@ -18,6 +18,8 @@ pub async fn mint_nft(
             token_owner: Account { owner, subaccount },
             memo: memo.map(|m| serde_bytes::ByteBuf::from(m.as_bytes())),
             metadata,
+            token_id: None,
+            created_at_time: None,
         }],
     };
 
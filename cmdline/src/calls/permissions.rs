@@ -17,8 +17,11 @@ pub fn parse_permission_from_str(input: &str) -> Result<Permission> {
         | "update-collection-metadata" => Ok(Permission::UpdateCollectionMetadata),
         "readuploads" | "read_uploads" | "read-uploads" => Ok(Permission::ReadUploads),
         "updateuploads" | "update_uploads" | "update-uploads" => Ok(Permission::UpdateUploads),
+        "burning" => Ok(Permission::Burning),
+        "updateroyalties" | "update_royalties" | "update-royalties" => Ok(Permission::UpdateRoyalties),
+        "pause" => Ok(Permission::Pause),
         other => Err(anyhow!(
-            "Unknown permission: {}. Valid values: minting, manage_authorities, update_metadata, update_collection_metadata, read_uploads, update_uploads",
+            "Unknown permission: {}. Valid values: minting, manage_authorities, update_metadata, update_collection_metadata, read_uploads, update_uploads, burning, update_royalties, pause",
             other
         )),
     }
@@ -30,7 +33,11 @@ pub async fn grant(
     principal: Principal,
     permission: Permission,
 ) -> Result<()> {
-    let args = grant_permission::Args { principal, permission };
+    let args = grant_permission::Args {
+        principal,
+        permission,
+        expires_at: None,
+    };
     let bytes = Encode!(&args)?;
     let response = agent
         .update(canister_id, "grant_permission")
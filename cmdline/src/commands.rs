@@ -260,6 +260,9 @@ pub async fn handle_permissions(
                         Permission::UpdateCollectionMetadata => "update_collection_metadata",
                         Permission::ReadUploads => "read_uploads",
                         Permission::UpdateUploads => "update_uploads",
+                        Permission::Burning => "burning",
+                        Permission::UpdateRoyalties => "update_royalties",
+                        Permission::Pause => "pause",
                     };
                     println!("- {}", label);
                 }
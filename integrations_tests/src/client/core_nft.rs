@@ -18,10 +18,37 @@ use core_nft::types::icrc7::{
     icrc7_tokens, icrc7_tokens_of, icrc7_total_supply, icrc7_transfer, icrc7_tx_window,
 };
 use core_nft::types::management::{
-    cancel_upload, finalize_upload, get_all_uploads, get_upload_status, get_user_permissions,
-    grant_permission, has_permission, init_upload, mint, revoke_permission, store_chunk,
-    update_collection_metadata, update_nft_metadata,
+    admin_burn_nfts, burn_nft, burn_nfts, cancel_upload, delete_role, finalize_upload,
+    freeze_metadata, get_all_uploads, get_roles, get_upload_status, get_user_permissions,
+    grant_permission, grant_role, has_permission, init_upload, list_permission_holders, mint,
+    pause_collection, revoke_permission, revoke_role, set_role, set_royalties,
+    set_royalty_enforcement, set_transferable, store_chunk, unpause_collection,
+    update_collection_metadata, update_nft_metadata, update_nft_metadata_patch,
 };
+use core_nft::types::marketplace::{
+    buy_token, cancel_listing, get_listing, get_marketplace_config, list_token, sale_transfer_from,
+    set_marketplace_config, settle_marketplace_payouts,
+};
+use core_nft::types::multisig::{
+    approve_proposal, create_proposal, get_multisig_config, get_proposal, list_proposals,
+    set_multisig_config,
+};
+use core_nft::types::nest::{
+    attach_child, detach_child, get_children, get_parent, icrc7_root_owner_of,
+};
+use core_nft::types::offer::{accept_offer, cancel_offer, get_offer, get_token_offers, make_offer};
+use core_nft::types::public_mint::{
+    get_allowlist_root, get_public_mint_config, get_public_mint_purchases, public_mint,
+    set_allowlist_root, set_public_mint_config, settle_public_mint,
+};
+use core_nft::types::reveal::{commit_reveal, get_reveal_commitment, reveal};
+use core_nft::types::timelock::{
+    cancel_operation, get_timelock_config, pending_operations, schedule_operation,
+};
+use core_nft::types::token_account::{
+    get_token_account, get_token_account_ledgers, set_token_account_ledgers, token_account_transfer,
+};
+use core_nft::types::user::{icrc7_user_of, set_user};
 
 generate_pocket_query_call!(icrc7_collection_metadata);
 generate_pocket_query_call!(icrc7_symbol);
@@ -55,6 +82,16 @@ generate_pocket_update_call!(icrc7_transfer);
 
 generate_pocket_update_call!(mint);
 generate_pocket_update_call!(update_nft_metadata);
+generate_pocket_update_call!(update_nft_metadata_patch);
+generate_pocket_update_call!(freeze_metadata);
+generate_pocket_update_call!(set_royalties);
+generate_pocket_update_call!(set_royalty_enforcement);
+generate_pocket_update_call!(set_transferable);
+generate_pocket_update_call!(pause_collection);
+generate_pocket_update_call!(unpause_collection);
+generate_pocket_update_call!(burn_nft);
+generate_pocket_update_call!(burn_nfts);
+generate_pocket_update_call!(admin_burn_nfts);
 generate_pocket_update_call!(init_upload);
 generate_pocket_update_call!(store_chunk);
 generate_pocket_update_call!(finalize_upload);
@@ -62,10 +99,60 @@ generate_pocket_update_call!(cancel_upload);
 generate_pocket_update_call!(update_collection_metadata);
 generate_pocket_update_call!(grant_permission);
 generate_pocket_update_call!(revoke_permission);
+generate_pocket_update_call!(set_role);
+generate_pocket_update_call!(delete_role);
+generate_pocket_update_call!(grant_role);
+generate_pocket_update_call!(revoke_role);
+generate_pocket_update_call!(set_public_mint_config);
+generate_pocket_update_call!(public_mint);
+generate_pocket_update_call!(settle_public_mint);
+generate_pocket_update_call!(set_allowlist_root);
+generate_pocket_update_call!(commit_reveal);
+generate_pocket_update_call!(reveal);
+generate_pocket_update_call!(set_marketplace_config);
+generate_pocket_update_call!(list_token);
+generate_pocket_update_call!(cancel_listing);
+generate_pocket_update_call!(buy_token);
+generate_pocket_update_call!(settle_marketplace_payouts);
+generate_pocket_update_call!(sale_transfer_from);
+generate_pocket_update_call!(make_offer);
+generate_pocket_update_call!(cancel_offer);
+generate_pocket_update_call!(accept_offer);
+generate_pocket_update_call!(set_user);
+generate_pocket_update_call!(attach_child);
+generate_pocket_update_call!(detach_child);
+generate_pocket_update_call!(set_token_account_ledgers);
+generate_pocket_update_call!(token_account_transfer);
+generate_pocket_update_call!(set_multisig_config);
+generate_pocket_update_call!(create_proposal);
+generate_pocket_update_call!(approve_proposal);
+generate_pocket_update_call!(schedule_operation);
+generate_pocket_update_call!(cancel_operation);
 
 generate_pocket_query_call!(get_user_permissions);
 generate_pocket_query_call!(has_permission);
+generate_pocket_query_call!(get_roles);
+generate_pocket_query_call!(list_permission_holders);
 generate_pocket_query_call!(get_upload_status);
+generate_pocket_query_call!(get_public_mint_config);
+generate_pocket_query_call!(get_public_mint_purchases);
+generate_pocket_query_call!(get_allowlist_root);
+generate_pocket_query_call!(get_reveal_commitment);
+generate_pocket_query_call!(get_marketplace_config);
+generate_pocket_query_call!(get_listing);
+generate_pocket_query_call!(get_offer);
+generate_pocket_query_call!(get_token_offers);
+generate_pocket_query_call!(icrc7_user_of);
+generate_pocket_query_call!(get_children);
+generate_pocket_query_call!(get_parent);
+generate_pocket_query_call!(icrc7_root_owner_of);
+generate_pocket_query_call!(get_token_account);
+generate_pocket_query_call!(get_token_account_ledgers);
+generate_pocket_query_call!(get_multisig_config);
+generate_pocket_query_call!(get_proposal);
+generate_pocket_query_call!(list_proposals);
+generate_pocket_query_call!(get_timelock_config);
+generate_pocket_query_call!(pending_operations);
 
 generate_pocket_update_call!(icrc37_approve_collection);
 generate_pocket_update_call!(icrc37_approve_tokens);
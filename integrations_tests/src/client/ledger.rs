@@ -0,0 +1,22 @@
+use crate::{generate_pocket_query_call, generate_pocket_update_call};
+
+use candid::Nat;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+
+pub mod icrc1_balance_of {
+    use super::*;
+
+    pub type Args = Account;
+    pub type Response = Nat;
+}
+
+pub mod icrc2_approve {
+    use super::*;
+
+    pub type Args = ApproveArgs;
+    pub type Response = Result<Nat, ApproveError>;
+}
+
+generate_pocket_query_call!(icrc1_balance_of);
+generate_pocket_update_call!(icrc2_approve);
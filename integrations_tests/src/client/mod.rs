@@ -1,5 +1,6 @@
 pub mod core_nft;
 pub mod indexer;
+pub mod ledger;
 pub mod macros;
 pub mod pocket;
 pub mod storage;
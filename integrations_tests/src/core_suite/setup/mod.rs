@@ -7,6 +7,7 @@ use std::collections::HashMap;
 
 pub mod setup;
 pub mod setup_core;
+pub mod setup_ledger;
 
 pub fn default_test_setup() -> TestEnv {
     let mut test_env = TestEnvBuilder::new();
@@ -21,6 +22,9 @@ pub fn default_test_setup() -> TestEnv {
             Permission::UpdateCollectionMetadata,
             Permission::ReadUploads,
             Permission::UpdateUploads,
+            Permission::Burning,
+            Permission::UpdateRoyalties,
+            Permission::Pause,
         ],
     );
 
@@ -44,6 +48,7 @@ pub fn default_test_setup() -> TestEnv {
         permitted_drift: None,
         max_canister_storage_threshold: None,
         collection_metadata: HashMap::new(),
+        metadata_validation: None,
         approval_init: InitApprovalsArg {
             max_approvals_per_token_or_collection: Some(Nat::from(10u64)),
             max_revoke_approvals: Some(Nat::from(10u64)),
@@ -66,6 +71,9 @@ pub fn test_setup_atomic_batch_transfers() -> TestEnv {
             Permission::UpdateCollectionMetadata,
             Permission::ReadUploads,
             Permission::UpdateUploads,
+            Permission::Burning,
+            Permission::UpdateRoyalties,
+            Permission::Pause,
         ],
     );
 
@@ -89,6 +97,7 @@ pub fn test_setup_atomic_batch_transfers() -> TestEnv {
         permitted_drift: None,
         max_canister_storage_threshold: None,
         collection_metadata: HashMap::new(),
+        metadata_validation: None,
         approval_init: InitApprovalsArg {
             max_approvals_per_token_or_collection: Some(Nat::from(10u64)),
             max_revoke_approvals: Some(Nat::from(10u64)),
@@ -111,6 +120,9 @@ pub fn test_setup_no_limit() -> TestEnv {
             Permission::UpdateCollectionMetadata,
             Permission::ReadUploads,
             Permission::UpdateUploads,
+            Permission::Burning,
+            Permission::UpdateRoyalties,
+            Permission::Pause,
         ],
     );
 
@@ -134,6 +146,7 @@ pub fn test_setup_no_limit() -> TestEnv {
         permitted_drift: None,
         max_canister_storage_threshold: None,
         collection_metadata: HashMap::new(),
+        metadata_validation: None,
         approval_init: InitApprovalsArg {
             max_approvals_per_token_or_collection: Some(Nat::from(10u64)),
             max_revoke_approvals: Some(Nat::from(10u64)),
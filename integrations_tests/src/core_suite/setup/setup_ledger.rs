@@ -0,0 +1,74 @@
+use crate::wasms::IC_ICRC2_LEDGER;
+
+use candid::{encode_one, CandidType, Deserialize, Nat, Principal};
+use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+use icrc_ledger_types::icrc1::account::Account;
+use pocket_ic::PocketIc;
+
+pub const LEDGER_FEE: u64 = 10_000;
+
+// Subset of the ICRC ledger init arguments; omitted optional fields default to null.
+#[derive(CandidType, Deserialize, Debug)]
+pub enum LedgerArgument {
+    Init(LedgerInitArgs),
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct LedgerInitArgs {
+    pub minting_account: Account,
+    pub transfer_fee: Nat,
+    pub token_symbol: String,
+    pub token_name: String,
+    pub metadata: Vec<(String, MetadataValue)>,
+    pub initial_balances: Vec<(Account, Nat)>,
+    pub feature_flags: Option<FeatureFlags>,
+    pub archive_options: ArchiveOptions,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct FeatureFlags {
+    pub icrc2: bool,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct ArchiveOptions {
+    pub num_blocks_to_archive: u64,
+    pub trigger_threshold: u64,
+    pub controller_id: Principal,
+}
+
+pub fn setup_ledger_canister(
+    pic: &mut PocketIc,
+    controller: Principal,
+    initial_balances: Vec<(Account, Nat)>,
+) -> Principal {
+    let ledger_canister_id = pic.create_canister_with_settings(Some(controller), None);
+    pic.add_cycles(ledger_canister_id, 100_000_000_000_000);
+
+    let args = LedgerArgument::Init(LedgerInitArgs {
+        minting_account: Account {
+            owner: controller,
+            subaccount: Some([1; 32]),
+        },
+        transfer_fee: Nat::from(LEDGER_FEE),
+        token_symbol: "TST".to_string(),
+        token_name: "Test Token".to_string(),
+        metadata: vec![],
+        initial_balances,
+        feature_flags: Some(FeatureFlags { icrc2: true }),
+        archive_options: ArchiveOptions {
+            num_blocks_to_archive: 1000,
+            trigger_threshold: 2000,
+            controller_id: controller,
+        },
+    });
+
+    pic.install_canister(
+        ledger_canister_id,
+        IC_ICRC2_LEDGER.clone(),
+        encode_one(args).unwrap(),
+        Some(controller),
+    );
+
+    ledger_canister_id
+}
@@ -2,3 +2,12 @@ pub mod test_icrc3;
 pub mod test_icrc37;
 pub mod test_icrc7;
 pub mod test_management;
+pub mod test_marketplace;
+pub mod test_multisig;
+pub mod test_nest;
+pub mod test_offers;
+pub mod test_permissions;
+pub mod test_public_mint;
+pub mod test_timelock;
+pub mod test_token_account;
+pub mod test_user;
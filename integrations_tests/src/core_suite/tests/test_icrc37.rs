@@ -2,7 +2,7 @@ use crate::client::core_nft::{
     icrc37_approve_collection, icrc37_approve_tokens, icrc37_is_approved,
     icrc37_max_approvals_per_token_or_collection, icrc37_max_revoke_approvals,
     icrc37_revoke_collection_approvals, icrc37_revoke_token_approvals, icrc37_transfer_from,
-    icrc7_owner_of,
+    icrc7_owner_of, icrc7_token_metadata, set_transferable,
 };
 use crate::core_suite::setup::default_test_setup;
 use crate::core_suite::setup::setup::{TestEnv, MINUTE_IN_MS};
@@ -16,6 +16,8 @@ use core_nft::icrc37_approve_tokens::ApproveTokenResult;
 use core_nft::lifecycle::Args;
 use core_nft::post_upgrade::UpgradeArgs;
 use core_nft::types::icrc37;
+use core_nft::types::set_transferable;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
 use icrc_ledger_types::icrc1::account::Account;
 use serde_bytes::ByteBuf;
 use std::time::Duration;
@@ -2919,3 +2921,115 @@ fn test_icrc37_approve_tokens_sliding_window_rate_limit() {
         }
     }
 }
+
+#[test]
+fn test_icrc37_soulbound_collection() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let token_id = mint_nft(
+        pic,
+        Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let current_time = pic.get_time().as_nanos_since_unix_epoch();
+    let approval_info = icrc37::ApprovalInfo {
+        spender: Account {
+            owner: nft_owner2,
+            subaccount: None,
+        },
+        from_subaccount: None,
+        expires_at: None,
+        memo: None,
+        created_at_time: current_time,
+    };
+    let approve_args = vec![icrc37::icrc37_approve_tokens::ApproveTokenArg {
+        token_id: token_id.clone(),
+        approval_info: approval_info.clone(),
+    }];
+
+    // Approve while the token can still move
+    let approve_response =
+        icrc37_approve_tokens(pic, nft_owner1, collection_canister_id, &approve_args);
+    assert!(matches!(
+        approve_response.unwrap()[0],
+        Some(ApproveTokenResult::Ok(_))
+    ));
+
+    let update_response = set_transferable(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_transferable::Args {
+            token_id: None,
+            transferable: Some(false),
+        },
+    );
+    assert!(update_response.is_ok());
+
+    // Existing approvals can no longer be used
+    let transfer_args = vec![icrc37::icrc37_transfer_from::TransferFromArg {
+        spender_subaccount: None,
+        from: Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        to: Account {
+            owner: nft_owner2,
+            subaccount: None,
+        },
+        token_id: token_id.clone(),
+        memo: None,
+        created_at_time: Some(current_time),
+    }];
+    let transfer_response =
+        icrc37_transfer_from(pic, nft_owner2, collection_canister_id, &transfer_args);
+    assert!(matches!(
+        &transfer_response.unwrap()[0],
+        Some(icrc37::icrc37_transfer_from::TransferFromResult::Err(
+            icrc37::icrc37_transfer_from::TransferFromError::GenericError { .. }
+        ))
+    ));
+
+    let approve_response =
+        icrc37_approve_tokens(pic, nft_owner1, collection_canister_id, &approve_args);
+    assert!(matches!(
+        approve_response.unwrap()[0],
+        Some(ApproveTokenResult::Err(
+            icrc37::icrc37_approve_tokens::ApproveTokenError::GenericError { .. }
+        ))
+    ));
+
+    let owner_of = icrc7_owner_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert_eq!(owner_of[0].unwrap().owner, nft_owner1);
+
+    // The collection-wide flag shows up on every token
+    let token_metadata = icrc7_token_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert!(token_metadata[0].clone().unwrap().contains(&(
+        "icrc7:transferable".to_string(),
+        ICRC3Value::Text("false".to_string())
+    )));
+}
@@ -2,19 +2,29 @@ use crate::client::core_nft::{
     icrc3_get_blocks, icrc7_atomic_batch_transfers, icrc7_balance_of, icrc7_collection_metadata,
     icrc7_description, icrc7_logo, icrc7_max_memo_size, icrc7_max_take_value, icrc7_name,
     icrc7_owner_of, icrc7_permitted_drift, icrc7_supply_cap, icrc7_symbol, icrc7_token_metadata,
-    icrc7_total_supply, icrc7_transfer, icrc7_tx_window, update_nft_metadata,
+    icrc7_total_supply, icrc7_transfer, icrc7_tx_window, pause_collection, set_transferable,
+    unpause_collection, update_collection_metadata, update_nft_metadata, update_nft_metadata_patch,
 };
 use crate::core_suite::setup::setup::TestEnv;
+use crate::core_suite::setup::setup_core::upgrade_core_canister;
 use crate::utils::{
     create_default_icrc97_metadata, create_default_metadata, extract_metadata_file_path,
     fetch_metadata_json, mint_nft, random_principal, setup_http_client, upload_metadata,
 };
+use bity_ic_types::BuildVersion;
 use candid::{Encode, Nat, Principal};
+use core_nft::lifecycle::Args;
+use core_nft::post_upgrade::UpgradeArgs;
 use core_nft::types::icrc7;
+use core_nft::types::pause_collection::PauseCollectionError;
+use core_nft::types::set_transferable;
+use core_nft::types::update_collection_metadata;
 use core_nft::types::update_nft_metadata;
+use core_nft::types::update_nft_metadata_patch;
 use icrc_ledger_types::icrc::generic_value::ICRC3Value as Value;
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
+use pocket_ic::PocketIc;
 use serde_bytes::ByteBuf;
 use serde_json::json;
 use std::collections::BTreeMap;
@@ -1631,6 +1641,118 @@ fn test_icrc7_tokens_of() {
     assert_eq!(tokens_of_owner2.len(), 1);
 }
 
+#[test]
+fn test_icrc7_tokens_pagination() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let owner1 = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let owner1_sub = Account {
+        owner: nft_owner1,
+        subaccount: Some([1u8; 32]),
+    };
+    let owner2 = Account {
+        owner: nft_owner2,
+        subaccount: None,
+    };
+
+    // Interleave owners so an owner's tokens are not contiguous in id order.
+    let mut owner1_tokens = Vec::new();
+    let mut all_tokens = Vec::new();
+    for owner in [owner1, owner2, owner1, owner1_sub, owner1, owner2] {
+        let token_id = mint_nft(
+            pic,
+            owner,
+            controller,
+            collection_canister_id,
+            create_default_metadata(),
+        )
+        .expect("Failed to mint NFT");
+        if owner == owner1 {
+            owner1_tokens.push(token_id.clone());
+        }
+        all_tokens.push(token_id);
+    }
+
+    let tokens_of = |pic: &PocketIc, account: &Account, prev: Option<Nat>, take: Option<Nat>| {
+        let tokens: icrc7::icrc7_tokens_of::Response =
+            crate::client::pocket::unwrap_response(pic.query_call(
+                collection_canister_id,
+                controller,
+                "icrc7_tokens_of",
+                Encode!(account, &prev, &take).unwrap(),
+            ));
+        tokens
+    };
+    let tokens = |pic: &PocketIc, prev: Option<Nat>, take: Option<Nat>| {
+        let tokens: icrc7::icrc7_tokens::Response =
+            crate::client::pocket::unwrap_response(pic.query_call(
+                collection_canister_id,
+                controller,
+                "icrc7_tokens",
+                Encode!(&prev, &take).unwrap(),
+            ));
+        tokens
+    };
+
+    let first_page = tokens_of(pic, &owner1, None, Some(Nat::from(2u64)));
+    assert_eq!(first_page, owner1_tokens[..2].to_vec());
+    let second_page = tokens_of(
+        pic,
+        &owner1,
+        first_page.last().cloned(),
+        Some(Nat::from(2u64)),
+    );
+    assert_eq!(
+        second_page,
+        owner1_tokens[2..].to_vec(),
+        "Pages should stop at the end of the owner's range"
+    );
+
+    let first_page = tokens(pic, None, Some(Nat::from(4u64)));
+    assert_eq!(first_page, all_tokens[..4].to_vec());
+    let second_page = tokens(pic, first_page.last().cloned(), Some(Nat::from(4u64)));
+    assert_eq!(second_page, all_tokens[4..].to_vec());
+
+    let transfer_response = icrc7_transfer(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![icrc7::TransferArg {
+            to: owner2,
+            token_id: owner1_tokens[1].clone(),
+            memo: None,
+            from_subaccount: None,
+            created_at_time: Some(pic.get_time().as_nanos_since_unix_epoch()),
+        }],
+    );
+    assert!(transfer_response[0].as_ref().unwrap().is_ok());
+
+    assert_eq!(
+        tokens_of(pic, &owner1, None, None),
+        vec![owner1_tokens[0].clone(), owner1_tokens[2].clone()]
+    );
+    let balances = icrc7_balance_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![owner1, owner1_sub, owner2],
+    );
+    assert_eq!(
+        balances,
+        vec![Nat::from(2u64), Nat::from(1u64), Nat::from(3u64)]
+    );
+}
+
 #[test]
 fn test_icrc7_balance_of() {
     let mut test_env: TestEnv = default_test_setup();
@@ -3470,3 +3592,438 @@ fn test_icrc7_balance_of_comprehensive() {
         }
     }
 }
+
+#[test]
+fn test_icrc7_tokens_persistence_after_upgrade() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let owner1 = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let owner2 = Account {
+        owner: nft_owner2,
+        subaccount: None,
+    };
+
+    let token_id_1 = mint_nft(
+        pic,
+        owner1,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint first NFT");
+
+    let token_id_2 = mint_nft(
+        pic,
+        owner1,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint second NFT");
+
+    tick_n_blocks(pic, 5);
+
+    let transfer_args = vec![icrc7::TransferArg {
+        from_subaccount: None,
+        to: owner2,
+        token_id: token_id_2.clone(),
+        memo: None,
+        created_at_time: Some(pic.get_time().as_nanos_since_unix_epoch()),
+    }];
+
+    let transfer_response = icrc7_transfer(pic, nft_owner1, collection_canister_id, &transfer_args);
+    assert!(transfer_response[0].as_ref().unwrap().is_ok());
+
+    pic.advance_time(Duration::from_secs(1));
+    tick_n_blocks(pic, 10);
+
+    upgrade_core_canister(
+        pic,
+        collection_canister_id,
+        Args::Upgrade(UpgradeArgs {
+            version: BuildVersion::min(),
+            commit_hash: "commit_hash 2".to_string(),
+        }),
+        controller,
+    );
+
+    let owners = icrc7_owner_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id_1.clone(), token_id_2.clone()],
+    );
+    assert_eq!(owners, vec![Some(owner1), Some(owner2)]);
+
+    let balances = icrc7_balance_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![owner1, owner2],
+    );
+    assert_eq!(balances, vec![Nat::from(1u64), Nat::from(1u64)]);
+
+    let tokens_of_owner2: core_nft::types::icrc7::icrc7_tokens_of::Response =
+        crate::client::pocket::unwrap_response(pic.query_call(
+            collection_canister_id,
+            controller,
+            "icrc7_tokens_of",
+            Encode!(&owner2, &(), &()).unwrap(),
+        ));
+    assert_eq!(tokens_of_owner2, vec![token_id_2]);
+
+    let total_supply = icrc7_total_supply(pic, controller, collection_canister_id, &());
+    assert_eq!(total_supply, Nat::from(2u64));
+}
+
+#[test]
+fn test_update_nft_metadata_patch() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let token_id = mint_nft(
+        pic,
+        Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let patch_args = vec![
+        update_nft_metadata_patch::MetadataPatch {
+            token_id: token_id.clone(),
+            set: vec![
+                ("name".to_string(), Value::Text("patched".to_string())),
+                ("description".to_string(), Value::Text("test".to_string())),
+                ("new_key".to_string(), Value::Nat(Nat::from(7u64))),
+            ],
+            unset: vec!["test".to_string()],
+        },
+        update_nft_metadata_patch::MetadataPatch {
+            token_id: Nat::from(9999u64),
+            set: vec![("name".to_string(), Value::Text("missing".to_string()))],
+            unset: vec![],
+        },
+    ];
+
+    let patch_response =
+        update_nft_metadata_patch(pic, controller, collection_canister_id, &patch_args);
+    assert!(matches!(patch_response[0], Some(Ok(_))));
+    assert!(matches!(
+        patch_response[1],
+        Some(Err(
+            update_nft_metadata::UpdateNftMetadataError::TokenDoesNotExist
+        ))
+    ));
+
+    let metadata = icrc7_token_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    let metadata: BTreeMap<String, Value> = metadata[0].clone().unwrap().into_iter().collect();
+
+    assert_eq!(
+        metadata.get("name"),
+        Some(&Value::Text("patched".to_string()))
+    );
+    assert_eq!(
+        metadata.get("description"),
+        Some(&Value::Text("test".to_string()))
+    );
+    assert_eq!(metadata.get("new_key"), Some(&Value::Nat(Nat::from(7u64))));
+    assert_eq!(metadata.get("test"), None, "Unset key should be removed");
+
+    // The block only records the keys that changed
+    let blocks = icrc3_get_blocks(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![GetBlocksRequest {
+            start: Nat::from(0u64),
+            length: Nat::from(100u64),
+        }],
+    );
+    let last_block = blocks
+        .blocks
+        .last()
+        .expect("Update block should not be archived yet");
+
+    let tx = match &last_block.block {
+        Value::Map(block) => {
+            assert_eq!(
+                block.get("btype"),
+                Some(&Value::Text("7update_token".to_string()))
+            );
+            block.get("tx").unwrap().clone()
+        }
+        _ => panic!("Block is not a map"),
+    };
+    let meta = match tx {
+        Value::Map(tx) => tx.get("meta").unwrap().clone(),
+        _ => panic!("Transaction is not a map"),
+    };
+
+    let mut expected_previous = BTreeMap::new();
+    expected_previous.insert("name".to_string(), Value::Text("test".to_string()));
+    expected_previous.insert("test".to_string(), Value::Text("test".to_string()));
+
+    let mut expected_new = BTreeMap::new();
+    expected_new.insert("name".to_string(), Value::Text("patched".to_string()));
+    expected_new.insert("new_key".to_string(), Value::Nat(Nat::from(7u64)));
+
+    let mut expected_meta = BTreeMap::new();
+    expected_meta.insert(
+        "icrc7:previous_metadata".to_string(),
+        Value::Map(expected_previous),
+    );
+    expected_meta.insert("icrc7:new_metadata".to_string(), Value::Map(expected_new));
+
+    assert_eq!(meta, Value::Map(expected_meta));
+}
+
+#[test]
+fn test_update_nft_metadata_patch_rejects_oversized_batch() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let mut patch_args = Vec::new();
+    for _ in 0..2 {
+        let token_id = mint_nft(
+            pic,
+            Account {
+                owner: nft_owner1,
+                subaccount: None,
+            },
+            controller,
+            collection_canister_id,
+            create_default_metadata(),
+        )
+        .expect("Failed to mint NFT");
+        patch_args.push(update_nft_metadata_patch::MetadataPatch {
+            token_id,
+            set: vec![("name".to_string(), Value::Text("patched".to_string()))],
+            unset: vec![],
+        });
+    }
+
+    update_collection_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &update_collection_metadata::Args {
+            max_update_batch_size: Some(Nat::from(1u64)),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let patch_response =
+        update_nft_metadata_patch(pic, controller, collection_canister_id, &patch_args);
+    assert_eq!(patch_response.len(), 1);
+    assert!(matches!(
+        patch_response[0],
+        Some(Err(
+            update_nft_metadata::UpdateNftMetadataError::ExceedMaxUpdateBatchSize
+        ))
+    ));
+
+    let metadata = icrc7_token_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![patch_args[0].token_id.clone()],
+    );
+    assert!(metadata[0]
+        .clone()
+        .unwrap()
+        .iter()
+        .any(|(key, value)| key == "name" && value == &Value::Text("test".to_string())));
+}
+
+#[test]
+fn test_icrc7_transfer_soulbound_token() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let token_id = mint_nft(
+        pic,
+        Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    set_transferable(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_transferable::Args {
+            token_id: Some(token_id.clone()),
+            transferable: Some(false),
+        },
+    )
+    .expect("Failed to make the token soulbound");
+
+    let transfer_response = icrc7_transfer(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![icrc7::TransferArg {
+            to: Account {
+                owner: nft_owner2,
+                subaccount: None,
+            },
+            token_id: token_id.clone(),
+            memo: None,
+            from_subaccount: None,
+            created_at_time: None,
+        }],
+    );
+    assert!(
+        matches!(
+            &transfer_response[0],
+            Some(Err(icrc7::icrc7_transfer::TransferError::GenericError { message, .. }))
+                if message.contains("soulbound")
+        ),
+        "Soulbound tokens should not be transferable"
+    );
+
+    let owner_of = icrc7_owner_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert_eq!(owner_of[0].unwrap().owner, nft_owner1);
+
+    let token_metadata = icrc7_token_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert!(token_metadata[0].clone().unwrap().contains(&(
+        "icrc7:transferable".to_string(),
+        Value::Text("false".to_string())
+    )));
+}
+
+#[test]
+fn test_pause_collection() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let token_id = mint_nft(
+        pic,
+        Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let transfer_args = vec![icrc7::TransferArg {
+        to: Account {
+            owner: nft_owner2,
+            subaccount: None,
+        },
+        token_id: token_id.clone(),
+        memo: None,
+        from_subaccount: None,
+        created_at_time: None,
+    }];
+
+    let pause_response = pause_collection(pic, controller, collection_canister_id, &());
+    assert!(pause_response.is_ok(), "Should pause the collection");
+
+    let pause_response = pause_collection(pic, controller, collection_canister_id, &());
+    assert!(matches!(
+        pause_response,
+        Err(PauseCollectionError::AlreadyPaused)
+    ));
+
+    let transfer_response = icrc7_transfer(pic, nft_owner1, collection_canister_id, &transfer_args);
+    assert!(
+        matches!(
+            transfer_response[0],
+            Some(Err(
+                icrc7::icrc7_transfer::TransferError::GenericError { .. }
+            ))
+        ),
+        "Transfers should be rejected while paused"
+    );
+
+    // Admin operations keep working while paused
+    let update_response = update_nft_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &update_nft_metadata::Args {
+            token_id: token_id.clone(),
+            metadata: create_default_metadata(),
+        },
+    );
+    assert!(update_response.is_ok());
+
+    let metadata = icrc7_collection_metadata(pic, controller, collection_canister_id, &());
+    assert!(metadata.contains(&("icrc7:paused".to_string(), Value::Text("true".to_string()))));
+
+    let unpause_response = unpause_collection(pic, controller, collection_canister_id, &());
+    assert!(unpause_response.is_ok(), "Should unpause the collection");
+
+    let transfer_response = icrc7_transfer(pic, nft_owner1, collection_canister_id, &transfer_args);
+    assert!(
+        matches!(transfer_response[0], Some(Ok(_))),
+        "Transfers should work again once unpaused"
+    );
+
+    let metadata = icrc7_collection_metadata(pic, controller, collection_canister_id, &());
+    assert!(metadata.contains(&("icrc7:paused".to_string(), Value::Text("false".to_string()))));
+}
@@ -1,17 +1,30 @@
 use crate::client::core_nft::{
-    cancel_upload, finalize_upload, get_upload_status, grant_permission, init_upload, mint,
-    revoke_permission, store_chunk, update_collection_metadata, update_nft_metadata,
+    admin_burn_nfts, burn_nfts, cancel_upload, commit_reveal, finalize_upload, freeze_metadata,
+    get_reveal_commitment, get_upload_status, grant_permission, icrc37_approve_tokens,
+    icrc7_balance_of, icrc7_collection_metadata, icrc7_owner_of, icrc7_token_metadata,
+    icrc7_total_supply, init_upload, mint, reveal, revoke_permission, set_royalties, store_chunk,
+    update_collection_metadata, update_nft_metadata, update_nft_metadata_patch,
 };
-use crate::utils::create_default_icrc97_metadata;
+use crate::utils::{create_default_icrc97_metadata, create_default_metadata, mint_nft};
 
 use candid::{Encode, Nat, Principal};
+use core_nft::types::metadata::{
+    MetadataKeyRule, MetadataValidation, MetadataValidationError, MetadataValueType,
+};
 use core_nft::types::permissions::Permission;
+use core_nft::types::reveal::{reveal_commitment, RevealEntry};
+use core_nft::types::royalties::{Royalties, RoyaltyRecipient};
+use core_nft::types::value_custom::CustomValue;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
 use icrc_ledger_types::icrc1::account::Account;
 
 use bity_ic_storage_canister_api::types::storage::UploadState;
+use core_nft::types::icrc37;
 use core_nft::types::management::{
-    cancel_upload, finalize_upload, grant_permission, init_upload, mint, mint::MintRequest,
-    revoke_permission, store_chunk, update_collection_metadata, update_nft_metadata,
+    burn_nft::BurnNftError, burn_nfts, cancel_upload, finalize_upload, freeze_metadata,
+    grant_permission, init_upload, mint, mint::MintError, mint::MintRequest, revoke_permission,
+    set_royalties, store_chunk, update_collection_metadata, update_nft_metadata,
+    update_nft_metadata_patch,
 };
 use ic_cdk::println;
 use sha2::{Digest, Sha256};
@@ -1098,6 +1111,8 @@ fn test_mint_unauthorized() {
                 },
                 memo: None,
                 metadata: create_default_icrc97_metadata(metadata_url),
+                token_id: None,
+                created_at_time: None,
             }],
         }),
     );
@@ -1122,6 +1137,7 @@ fn test_mint_authorized() {
         &(grant_permission::Args {
             principal: nft_owner1,
             permission: Permission::Minting,
+            expires_at: None,
         }),
     );
     assert!(result.is_ok(), "Should succeed with authorized principal");
@@ -1152,6 +1168,8 @@ fn test_mint_authorized() {
                 },
                 memo: None,
                 metadata: create_default_icrc97_metadata(metadata_url.clone()),
+                token_id: None,
+                created_at_time: None,
             }],
         }),
     );
@@ -1203,6 +1221,7 @@ fn test_add_then_remove_minting_authorities_unauthorized() {
         &(grant_permission::Args {
             principal: nft_owner1,
             permission: Permission::Minting,
+            expires_at: None,
         }),
     );
     assert!(result.is_ok(), "Should succeed with authorized principal");
@@ -1244,6 +1263,8 @@ fn test_add_then_remove_minting_authorities_unauthorized() {
                 },
                 memo: None,
                 metadata: create_default_icrc97_metadata(metadata_url),
+                token_id: None,
+                created_at_time: None,
             }],
         }),
     );
@@ -1296,6 +1317,8 @@ fn test_mint_with_metadata() {
                 },
                 memo: None,
                 metadata: create_default_icrc97_metadata(metadata_url.clone()),
+                token_id: None,
+                created_at_time: None,
             }],
         }),
     );
@@ -1543,12 +1566,166 @@ fn test_update_collection_metadata() {
             permitted_drift: Some(Nat::from(60u64)),
             max_canister_storage_threshold: Some(Nat::from(1000000u64)),
             collection_metadata: Some(HashMap::new()),
+            metadata_validation: None,
+        }),
+    );
+    assert!(
+        result.is_ok(),
+        "Should update collection metadata successfully"
+    );
+}
+
+#[test]
+fn test_update_collection_metadata_custom_keys() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let mut collection_metadata = HashMap::new();
+    collection_metadata.insert(
+        "com.example:website".to_string(),
+        CustomValue(ICRC3Value::Text("https://example.com".to_string())),
+    );
+    collection_metadata.insert(
+        "icrc7:name".to_string(),
+        CustomValue(ICRC3Value::Text("Shadowed name".to_string())),
+    );
+
+    let result = update_collection_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &(update_collection_metadata::Args {
+            description: None,
+            symbol: None,
+            name: None,
+            logo: None,
+            supply_cap: None,
+            max_query_batch_size: None,
+            max_update_batch_size: None,
+            max_take_value: None,
+            default_take_value: None,
+            max_memo_size: None,
+            atomic_batch_transfers: None,
+            tx_window: None,
+            permitted_drift: None,
+            max_canister_storage_threshold: None,
+            collection_metadata: Some(collection_metadata),
+            metadata_validation: None,
         }),
     );
     assert!(
         result.is_ok(),
         "Should update collection metadata successfully"
     );
+
+    let metadata = icrc7_collection_metadata(pic, controller, collection_canister_id, &());
+
+    assert!(
+        metadata
+            .iter()
+            .any(|(key, value)| key == "com.example:website"
+                && value == &ICRC3Value::Text("https://example.com".to_string())),
+        "Custom key should be returned by icrc7_collection_metadata"
+    );
+    assert!(
+        !metadata.iter().any(|(key, value)| key == "icrc7:name"
+            && value == &ICRC3Value::Text("Shadowed name".to_string())),
+        "Custom keys should not shadow the built-in fields"
+    );
+    assert!(
+        metadata.windows(2).all(|w| w[0].0 <= w[1].0),
+        "Collection metadata should be sorted by key"
+    );
+}
+
+#[test]
+fn test_update_collection_metadata_rejects_reserved_keys() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1: _,
+        nft_owner2: _,
+    } = test_env;
+
+    for key in ["icrc7:royalties", "icrc7:transferable", "icrc7:frozen_keys"] {
+        let mut collection_metadata = HashMap::new();
+        collection_metadata.insert(
+            key.to_string(),
+            CustomValue(ICRC3Value::Text("false".to_string())),
+        );
+        collection_metadata.insert(
+            "com.example:website".to_string(),
+            CustomValue(ICRC3Value::Text("https://example.com".to_string())),
+        );
+
+        let result = update_collection_metadata(
+            pic,
+            controller,
+            collection_canister_id,
+            &update_collection_metadata::Args {
+                collection_metadata: Some(collection_metadata),
+                ..Default::default()
+            },
+        );
+        assert!(
+            matches!(
+                &result,
+                Err(core_nft::types::management::update_collection_metadata::UpdateCollectionMetadataError::ReservedMetadataKey(k)) if k == key
+            ),
+            "{} should only be written through its own endpoint",
+            key
+        );
+    }
+
+    let metadata = icrc7_collection_metadata(pic, controller, collection_canister_id, &());
+    assert!(
+        !metadata.iter().any(|(key, _)| key == "com.example:website"),
+        "A rejected update should not write any key"
+    );
+}
+
+#[test]
+fn test_mint_rejects_reserved_keys() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    for key in ["icrc7:royalties", "icrc7:transferable", "icrc7:frozen_keys"] {
+        let mut metadata = create_default_metadata();
+        metadata.push((key.to_string(), ICRC3Value::Text("false".to_string())));
+
+        let result = mint_nft(
+            pic,
+            Account {
+                owner: nft_owner1,
+                subaccount: None,
+            },
+            controller,
+            collection_canister_id,
+            metadata,
+        );
+        assert!(
+            matches!(&result, Err(MintError::ReservedMetadataKey(k)) if k == key),
+            "{} should only be written through its own endpoint",
+            key
+        );
+    }
+
+    let total_supply = icrc7_total_supply(pic, controller, collection_canister_id, &());
+    assert_eq!(total_supply, Nat::from(0u64));
 }
 
 #[test]
@@ -1584,6 +1761,7 @@ fn test_update_collection_metadata_unauthorized() {
             permitted_drift: None,
             max_canister_storage_threshold: None,
             collection_metadata: None,
+            metadata_validation: None,
         }),
     );
     assert!(
@@ -1623,6 +1801,7 @@ fn test_permissions_add_and_remove_one_by_one() {
         &(grant_permission::Args {
             principal: test_principal,
             permission: Permission::Minting,
+            expires_at: None,
         }),
     );
     assert!(
@@ -1651,6 +1830,8 @@ fn test_permissions_add_and_remove_one_by_one() {
                 },
                 memo: None,
                 metadata: create_default_icrc97_metadata(metadata_url),
+                token_id: None,
+                created_at_time: None,
             }],
         }),
     );
@@ -1682,6 +1863,7 @@ fn test_permissions_add_and_remove_one_by_one() {
         &(grant_permission::Args {
             principal: test_principal,
             permission: Permission::UpdateMetadata,
+            expires_at: None,
         }),
     );
     assert!(
@@ -1739,6 +1921,7 @@ fn test_permissions_add_and_remove_one_by_one() {
         &(grant_permission::Args {
             principal: test_principal,
             permission: Permission::UpdateUploads,
+            expires_at: None,
         }),
     );
     assert!(
@@ -1783,6 +1966,7 @@ fn test_permissions_add_and_remove_one_by_one() {
         &(grant_permission::Args {
             principal: test_principal,
             permission: Permission::ReadUploads,
+            expires_at: None,
         }),
     );
     assert!(
@@ -1822,6 +2006,7 @@ fn test_permissions_add_and_remove_one_by_one() {
         &(grant_permission::Args {
             principal: test_principal,
             permission: Permission::UpdateCollectionMetadata,
+            expires_at: None,
         }),
     );
     assert!(
@@ -1849,6 +2034,7 @@ fn test_permissions_add_and_remove_one_by_one() {
             permitted_drift: None,
             max_canister_storage_threshold: None,
             collection_metadata: None,
+            metadata_validation: None,
         }),
     );
     assert!(
@@ -1877,6 +2063,7 @@ fn test_permissions_add_and_remove_one_by_one() {
         &(grant_permission::Args {
             principal: test_principal,
             permission: Permission::ManageAuthorities,
+            expires_at: None,
         }),
     );
     assert!(
@@ -1891,6 +2078,7 @@ fn test_permissions_add_and_remove_one_by_one() {
         &(grant_permission::Args {
             principal: nft_owner2,
             permission: Permission::Minting,
+            expires_at: None,
         }),
     );
     assert!(
@@ -1912,3 +2100,1381 @@ fn test_permissions_add_and_remove_one_by_one() {
         "Should revoke ManageAuthorities permission successfully"
     );
 }
+
+#[test]
+fn test_burn_nfts_cleans_up_token_state() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let owner1 = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+
+    let token_id = mint_nft(
+        pic,
+        owner1,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let approve_args = vec![icrc37::icrc37_approve_tokens::ApproveTokenArg {
+        token_id: token_id.clone(),
+        approval_info: icrc37::ApprovalInfo {
+            spender: Account {
+                owner: nft_owner2,
+                subaccount: None,
+            },
+            from_subaccount: None,
+            expires_at: None,
+            memo: None,
+            created_at_time: pic.get_time().as_nanos_since_unix_epoch(),
+        },
+    }];
+    let approve_response =
+        icrc37_approve_tokens(pic, nft_owner1, collection_canister_id, &approve_args);
+    assert!(approve_response.is_ok(), "Approval should succeed");
+
+    let burn_args = vec![burn_nfts::BurnArg {
+        token_id: token_id.clone(),
+        memo: Some(serde_bytes::ByteBuf::from("burn")),
+        created_at_time: Some(pic.get_time().as_nanos_since_unix_epoch()),
+    }];
+
+    let burn_response = burn_nfts(pic, nft_owner1, collection_canister_id, &burn_args);
+    let burn_index = match &burn_response[0] {
+        Some(Ok(index)) => index.clone(),
+        other => panic!("Burn should succeed, got {:?}", other),
+    };
+
+    let balance = icrc7_balance_of(pic, controller, collection_canister_id, &vec![owner1]);
+    assert_eq!(balance[0], Nat::from(0u64), "Owner index should be cleaned");
+
+    let owner = icrc7_owner_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert_eq!(owner[0], None, "Burned token should have no owner");
+
+    let metadata = icrc7_token_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert!(
+        metadata[0].is_none(),
+        "Burned token should have no metadata"
+    );
+
+    let token_approvals: icrc37::icrc37_get_token_approvals::Response =
+        crate::client::pocket::unwrap_response(pic.query_call(
+            collection_canister_id,
+            controller,
+            "icrc37_get_token_approvals",
+            Encode!(&token_id, &(), &()).unwrap(),
+        ));
+    assert!(
+        token_approvals.is_empty(),
+        "Token approvals should be cleaned"
+    );
+
+    let total_supply = icrc7_total_supply(pic, controller, collection_canister_id, &());
+    assert_eq!(total_supply, Nat::from(0u64));
+
+    // Replaying the same burn returns the original transaction instead of failing
+    let replay_response = burn_nfts(pic, nft_owner1, collection_canister_id, &burn_args);
+    match &replay_response[0] {
+        Some(Ok(index)) => assert_eq!(index, &burn_index, "Replay should be deduplicated"),
+        other => panic!("Replay should succeed, got {:?}", other),
+    }
+
+    let other_burn = vec![burn_nfts::BurnArg {
+        token_id: token_id.clone(),
+        memo: None,
+        created_at_time: None,
+    }];
+    let other_response = burn_nfts(pic, nft_owner1, collection_canister_id, &other_burn);
+    assert!(
+        matches!(
+            other_response[0],
+            Some(Err(BurnNftError::TokenDoesNotExist))
+        ),
+        "A different burn of a burned token should fail"
+    );
+}
+
+#[test]
+fn test_burn_nfts_not_owner() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let token_id = mint_nft(
+        pic,
+        Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let burn_args = vec![burn_nfts::BurnArg {
+        token_id,
+        memo: None,
+        created_at_time: None,
+    }];
+
+    let burn_response = burn_nfts(pic, nft_owner2, collection_canister_id, &burn_args);
+    assert!(
+        matches!(burn_response[0], Some(Err(BurnNftError::NotTokenOwner))),
+        "Only the owner should be able to burn"
+    );
+}
+
+#[test]
+fn test_burn_nfts_rejects_oversized_batch() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let mut burn_args = Vec::new();
+    for _ in 0..2 {
+        let token_id = mint_nft(
+            pic,
+            Account {
+                owner: nft_owner1,
+                subaccount: None,
+            },
+            controller,
+            collection_canister_id,
+            create_default_metadata(),
+        )
+        .expect("Failed to mint NFT");
+        burn_args.push(burn_nfts::BurnArg {
+            token_id,
+            memo: None,
+            created_at_time: None,
+        });
+    }
+
+    update_collection_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &update_collection_metadata::Args {
+            max_update_batch_size: Some(Nat::from(1u64)),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let burn_response = burn_nfts(pic, nft_owner1, collection_canister_id, &burn_args);
+    assert_eq!(burn_response.len(), 1);
+    assert!(matches!(
+        burn_response[0],
+        Some(Err(BurnNftError::ExceedMaxUpdateBatchSize))
+    ));
+    assert_eq!(
+        icrc7_total_supply(pic, controller, collection_canister_id, &()),
+        Nat::from(2u64)
+    );
+}
+
+#[test]
+fn test_admin_burn_nfts() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let owner1 = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+
+    let token_id_1 = mint_nft(
+        pic,
+        owner1,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint first NFT");
+    let token_id_2 = mint_nft(
+        pic,
+        owner1,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint second NFT");
+
+    let burn_args = vec![
+        burn_nfts::BurnArg {
+            token_id: token_id_1,
+            memo: None,
+            created_at_time: None,
+        },
+        burn_nfts::BurnArg {
+            token_id: token_id_2,
+            memo: None,
+            created_at_time: None,
+        },
+    ];
+
+    let burn_response = admin_burn_nfts(pic, controller, collection_canister_id, &burn_args);
+    assert!(
+        burn_response.iter().all(|r| matches!(r, Some(Ok(_)))),
+        "Admin burn should succeed for every token"
+    );
+
+    let balance = icrc7_balance_of(pic, controller, collection_canister_id, &vec![owner1]);
+    assert_eq!(balance[0], Nat::from(0u64));
+
+    let result = pic.update_call(
+        collection_canister_id,
+        nft_owner2,
+        "admin_burn_nfts",
+        Encode!(&burn_args).unwrap(),
+    );
+    assert!(
+        result.is_err(),
+        "Admin burn should be rejected without the Burning permission"
+    );
+}
+
+#[test]
+fn test_mint_with_token_id() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let owner1 = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+
+    let mint_request = |token_id: Option<u64>| MintRequest {
+        token_owner: owner1,
+        memo: None,
+        metadata: create_default_metadata(),
+        token_id: token_id.map(Nat::from),
+        created_at_time: None,
+    };
+
+    let result = mint(
+        pic,
+        controller,
+        collection_canister_id,
+        &(mint::Args {
+            mint_requests: vec![mint_request(Some(42))],
+        }),
+    );
+    assert_eq!(result.unwrap(), Nat::from(42u64));
+
+    let result = mint(
+        pic,
+        controller,
+        collection_canister_id,
+        &(mint::Args {
+            mint_requests: vec![mint_request(Some(42))],
+        }),
+    );
+    assert!(
+        matches!(result, Err(MintError::TokenAlreadyExists)),
+        "Minting an existing token id should fail"
+    );
+
+    // Sequential ids skip the ones already taken
+    let result = mint(
+        pic,
+        controller,
+        collection_canister_id,
+        &(mint::Args {
+            mint_requests: vec![mint_request(Some(1)), mint_request(None)],
+        }),
+    );
+    assert_eq!(result.unwrap(), Nat::from(1u64));
+
+    let owner = icrc7_owner_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![Nat::from(1u64), Nat::from(2u64)],
+    );
+    assert_eq!(owner, vec![Some(owner1), Some(owner1)]);
+
+    let result = mint(
+        pic,
+        controller,
+        collection_canister_id,
+        &(mint::Args {
+            mint_requests: vec![mint_request(Some(7)), mint_request(Some(7))],
+        }),
+    );
+    assert!(
+        matches!(result, Err(MintError::TokenAlreadyExists)),
+        "Duplicated ids within a batch should fail"
+    );
+
+    let result = mint(
+        pic,
+        controller,
+        collection_canister_id,
+        &(mint::Args {
+            mint_requests: vec![mint_request(Some(0))],
+        }),
+    );
+    assert!(
+        matches!(result, Err(MintError::InvalidTokenId)),
+        "Token id 0 is reserved for the collection"
+    );
+
+    // Burned ids cannot be minted again
+    let burn_response = burn_nfts(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![burn_nfts::BurnArg {
+            token_id: Nat::from(42u64),
+            memo: None,
+            created_at_time: None,
+        }],
+    );
+    assert!(matches!(burn_response[0], Some(Ok(_))));
+
+    let result = mint(
+        pic,
+        controller,
+        collection_canister_id,
+        &(mint::Args {
+            mint_requests: vec![mint_request(Some(42))],
+        }),
+    );
+    assert!(
+        matches!(result, Err(MintError::TokenAlreadyExists)),
+        "Minting a burned token id should fail"
+    );
+}
+
+#[test]
+fn test_mint_deduplication() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let mint_args = |memo: &str, created_at_time: u64| mint::Args {
+        mint_requests: vec![MintRequest {
+            token_owner: Account {
+                owner: nft_owner1,
+                subaccount: None,
+            },
+            memo: Some(serde_bytes::ByteBuf::from(memo.as_bytes().to_vec())),
+            metadata: create_default_metadata(),
+            token_id: None,
+            created_at_time: Some(created_at_time),
+        }],
+    };
+
+    let first = mint(
+        pic,
+        controller,
+        collection_canister_id,
+        &mint_args("a", now),
+    )
+    .expect("First mint should succeed");
+
+    // A retried call returns the original token instead of minting a new one
+    let replay = mint(
+        pic,
+        controller,
+        collection_canister_id,
+        &mint_args("a", now),
+    )
+    .expect("Replayed mint should succeed");
+    assert_eq!(replay, first, "Replay should return the original token id");
+
+    let total_supply = icrc7_total_supply(pic, controller, collection_canister_id, &());
+    assert_eq!(total_supply, Nat::from(1u64));
+
+    let other = mint(
+        pic,
+        controller,
+        collection_canister_id,
+        &mint_args("b", now),
+    )
+    .expect("Mint with another memo should succeed");
+    assert_ne!(other, first, "A different memo should mint a new token");
+
+    let too_old = mint(
+        pic,
+        controller,
+        collection_canister_id,
+        &mint_args("c", now - Duration::from_secs(3600).as_nanos() as u64),
+    );
+    assert!(
+        matches!(too_old, Err(MintError::TooOld)),
+        "Mint outside of the transaction window should fail"
+    );
+}
+
+#[test]
+fn test_mint_deduplication_within_batch() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let request = MintRequest {
+        token_owner: Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        memo: Some(serde_bytes::ByteBuf::from("a".as_bytes().to_vec())),
+        metadata: create_default_metadata(),
+        token_id: None,
+        created_at_time: Some(pic.get_time().as_nanos_since_unix_epoch()),
+    };
+
+    mint(
+        pic,
+        controller,
+        collection_canister_id,
+        &mint::Args {
+            mint_requests: vec![request.clone(), request],
+        },
+    )
+    .expect("Mint should succeed");
+
+    let total_supply = icrc7_total_supply(pic, controller, collection_canister_id, &());
+    assert_eq!(
+        total_supply,
+        Nat::from(1u64),
+        "A request repeated within a batch should only mint once"
+    );
+}
+
+#[test]
+fn test_freeze_metadata() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let token_id = mint_nft(
+        pic,
+        Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let freeze_keys_result = freeze_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &freeze_metadata::Args::TokenKeys {
+            token_id: token_id.clone(),
+            keys: vec!["name".to_string()],
+        },
+    );
+    assert!(freeze_keys_result.is_ok(), "Should freeze the name key");
+
+    // Changing a frozen key is rejected
+    let update_result = update_nft_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &update_nft_metadata::Args {
+            token_id: token_id.clone(),
+            metadata: vec![(
+                "name".to_string(),
+                ICRC3Value::Text("rewritten".to_string()),
+            )],
+        },
+    );
+    assert!(
+        matches!(
+            update_result,
+            Err(update_nft_metadata::UpdateNftMetadataError::MetadataFrozen)
+        ),
+        "Frozen key should not be rewritten"
+    );
+
+    // Other keys can still change as long as the frozen one is kept
+    let update_result = update_nft_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &update_nft_metadata::Args {
+            token_id: token_id.clone(),
+            metadata: vec![
+                ("name".to_string(), ICRC3Value::Text("test".to_string())),
+                (
+                    "description".to_string(),
+                    ICRC3Value::Text("updated".to_string()),
+                ),
+            ],
+        },
+    );
+    assert!(update_result.is_ok(), "Unfrozen keys should be updatable");
+
+    let metadata = icrc7_token_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    let frozen_keys = metadata[0]
+        .clone()
+        .unwrap()
+        .into_iter()
+        .find(|(key, _)| key == "icrc7:frozen_keys")
+        .map(|(_, value)| value);
+    assert_eq!(
+        frozen_keys,
+        Some(ICRC3Value::Array(vec![ICRC3Value::Text(
+            "name".to_string()
+        )]))
+    );
+
+    let repeated_result = freeze_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &freeze_metadata::Args::TokenKeys {
+            token_id: token_id.clone(),
+            keys: vec!["name".to_string()],
+        },
+    );
+    assert!(
+        matches!(
+            repeated_result,
+            Err(freeze_metadata::FreezeMetadataError::AlreadyFrozen)
+        ),
+        "Freezing the same key twice should fail"
+    );
+
+    let freeze_token_result = freeze_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &freeze_metadata::Args::Token(token_id.clone()),
+    );
+    assert!(freeze_token_result.is_ok(), "Should freeze the whole token");
+
+    let update_result = update_nft_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &update_nft_metadata::Args {
+            token_id: token_id.clone(),
+            metadata: vec![
+                ("name".to_string(), ICRC3Value::Text("test".to_string())),
+                (
+                    "description".to_string(),
+                    ICRC3Value::Text("updated again".to_string()),
+                ),
+            ],
+        },
+    );
+    assert!(
+        matches!(
+            update_result,
+            Err(update_nft_metadata::UpdateNftMetadataError::MetadataFrozen)
+        ),
+        "Frozen token should not be updatable"
+    );
+
+    let missing_result = freeze_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &freeze_metadata::Args::Token(Nat::from(9999u64)),
+    );
+    assert!(matches!(
+        missing_result,
+        Err(freeze_metadata::FreezeMetadataError::TokenDoesNotExist)
+    ));
+
+    let freeze_collection_result = freeze_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &freeze_metadata::Args::Collection,
+    );
+    assert!(
+        freeze_collection_result.is_ok(),
+        "Should freeze the collection"
+    );
+
+    let collection_update_result = update_collection_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &(update_collection_metadata::Args {
+            description: None,
+            symbol: None,
+            name: Some("Renamed".to_string()),
+            logo: None,
+            supply_cap: None,
+            max_query_batch_size: None,
+            max_update_batch_size: None,
+            max_take_value: None,
+            default_take_value: None,
+            max_memo_size: None,
+            atomic_batch_transfers: None,
+            tx_window: None,
+            permitted_drift: None,
+            max_canister_storage_threshold: None,
+            collection_metadata: None,
+            metadata_validation: None,
+        }),
+    );
+    assert!(
+        matches!(
+            collection_update_result,
+            Err(update_collection_metadata::UpdateCollectionMetadataError::MetadataFrozen)
+        ),
+        "Frozen collection should not be renamed"
+    );
+
+    let collection_metadata =
+        icrc7_collection_metadata(pic, controller, collection_canister_id, &());
+    assert!(collection_metadata
+        .iter()
+        .any(|(key, value)| key == "icrc7:frozen_keys"
+            && value == &ICRC3Value::Array(vec![ICRC3Value::Text("*".to_string())])));
+}
+
+#[test]
+fn test_mint_metadata_validation() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let mut key_rules = HashMap::new();
+    key_rules.insert(
+        "edition".to_string(),
+        MetadataKeyRule {
+            value_type: Some(MetadataValueType::Nat),
+            max_size: None,
+        },
+    );
+
+    let result = update_collection_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &(update_collection_metadata::Args {
+            description: None,
+            symbol: None,
+            name: None,
+            logo: None,
+            supply_cap: None,
+            max_query_batch_size: None,
+            max_update_batch_size: None,
+            max_take_value: None,
+            default_take_value: None,
+            max_memo_size: None,
+            atomic_batch_transfers: None,
+            tx_window: None,
+            permitted_drift: None,
+            max_canister_storage_threshold: None,
+            collection_metadata: None,
+            metadata_validation: Some(MetadataValidation {
+                icrc97: true,
+                max_value_size: Some(Nat::from(64u64)),
+                key_rules,
+            }),
+        }),
+    );
+    assert!(result.is_ok(), "Should enable metadata validation");
+
+    let mint_with = |pic: &mut pocket_ic::PocketIc, metadata: Vec<(String, ICRC3Value)>| {
+        mint(
+            pic,
+            controller,
+            collection_canister_id,
+            &(mint::Args {
+                mint_requests: vec![MintRequest {
+                    token_owner: Account {
+                        owner: nft_owner1,
+                        subaccount: None,
+                    },
+                    memo: None,
+                    metadata,
+                    token_id: None,
+                    created_at_time: None,
+                }],
+            }),
+        )
+    };
+
+    let result = mint_with(
+        pic,
+        vec![(
+            "icrc97:metadata".to_string(),
+            ICRC3Value::Text("https://example.com/1.json".to_string()),
+        )],
+    );
+    assert!(
+        matches!(
+            result,
+            Err(MintError::InvalidMetadata(
+                MetadataValidationError::InvalidIcrc97Metadata { .. }
+            ))
+        ),
+        "ICRC-97 metadata must be an array of URLs"
+    );
+
+    let result = mint_with(
+        pic,
+        vec![("edition".to_string(), ICRC3Value::Text("1".to_string()))],
+    );
+    assert!(
+        matches!(
+            result,
+            Err(MintError::InvalidMetadata(
+                MetadataValidationError::InvalidValueType {
+                    expected: MetadataValueType::Nat,
+                    ..
+                }
+            ))
+        ),
+        "Key rules should enforce the value type"
+    );
+
+    let result = mint_with(
+        pic,
+        vec![("name".to_string(), ICRC3Value::Text("x".repeat(65)))],
+    );
+    assert!(
+        matches!(
+            result,
+            Err(MintError::InvalidMetadata(
+                MetadataValidationError::ValueTooLarge { .. }
+            ))
+        ),
+        "Values above the size limit should be rejected"
+    );
+
+    let token_id = mint_with(
+        pic,
+        create_default_icrc97_metadata(url::Url::parse("https://example.com/1.json").unwrap()),
+    )
+    .expect("Valid ICRC-97 metadata should be minted");
+
+    let update_result = update_nft_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &update_nft_metadata::Args {
+            token_id,
+            metadata: vec![(
+                "icrc97:metadata".to_string(),
+                ICRC3Value::Array(vec![ICRC3Value::Text("not a url".to_string())]),
+            )],
+        },
+    );
+    assert!(
+        matches!(
+            update_result,
+            Err(update_nft_metadata::UpdateNftMetadataError::InvalidMetadata(_))
+        ),
+        "Metadata updates should be validated too"
+    );
+}
+
+#[test]
+fn test_set_royalties() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let token_id = mint_nft(
+        pic,
+        Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let creator = Account {
+        owner: controller,
+        subaccount: None,
+    };
+    let royalty_info = |pic: &mut pocket_ic::PocketIc, token_id: &Nat, sale_price: u64| {
+        let info: core_nft::types::icrc7::icrc7_royalty_info::Response =
+            crate::client::pocket::unwrap_response(pic.query_call(
+                collection_canister_id,
+                controller,
+                "icrc7_royalty_info",
+                Encode!(token_id, &Nat::from(sale_price)).unwrap(),
+            ));
+        info
+    };
+
+    let invalid_result = set_royalties(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_royalties::Args {
+            token_id: None,
+            royalties: Some(Royalties {
+                recipients: vec![RoyaltyRecipient {
+                    account: creator,
+                    basis_points: 10_001,
+                }],
+                ledger: None,
+            }),
+        },
+    );
+    assert!(
+        matches!(
+            invalid_result,
+            Err(set_royalties::SetRoyaltiesError::InvalidBasisPoints)
+        ),
+        "Royalties above 100% should be rejected"
+    );
+
+    // Collection defaults apply to every token
+    let result = set_royalties(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_royalties::Args {
+            token_id: None,
+            royalties: Some(Royalties {
+                recipients: vec![RoyaltyRecipient {
+                    account: creator,
+                    basis_points: 500,
+                }],
+                ledger: Some(collection_canister_id),
+            }),
+        },
+    );
+    assert!(result.is_ok(), "Should set collection royalties");
+
+    let info = royalty_info(pic, &token_id, 1_000).expect("Token should exist");
+    assert_eq!(info.ledger, Some(collection_canister_id));
+    assert_eq!(info.payouts.len(), 1);
+    assert_eq!(info.payouts[0].account, creator);
+    assert_eq!(info.payouts[0].amount, Nat::from(50u64));
+
+    // Token level royalties override the collection defaults
+    let second_recipient = Account {
+        owner: nft_owner2,
+        subaccount: Some([1; 32]),
+    };
+    let result = set_royalties(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_royalties::Args {
+            token_id: Some(token_id.clone()),
+            royalties: Some(Royalties {
+                recipients: vec![
+                    RoyaltyRecipient {
+                        account: creator,
+                        basis_points: 250,
+                    },
+                    RoyaltyRecipient {
+                        account: second_recipient,
+                        basis_points: 125,
+                    },
+                ],
+                ledger: None,
+            }),
+        },
+    );
+    assert!(result.is_ok(), "Should set token royalties");
+
+    let info = royalty_info(pic, &token_id, 1_000).expect("Token should exist");
+    assert_eq!(info.ledger, None);
+    assert_eq!(info.payouts.len(), 2);
+    assert_eq!(info.payouts[0].amount, Nat::from(25u64));
+    assert_eq!(info.payouts[1].account, second_recipient);
+    assert_eq!(info.payouts[1].amount, Nat::from(12u64));
+
+    let metadata = icrc7_token_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert!(metadata[0]
+        .clone()
+        .unwrap()
+        .iter()
+        .any(|(key, _)| key == "icrc7:royalties"));
+
+    // Clearing the token royalties falls back to the collection defaults
+    let result = set_royalties(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_royalties::Args {
+            token_id: Some(token_id.clone()),
+            royalties: None,
+        },
+    );
+    assert!(result.is_ok(), "Should clear token royalties");
+
+    let info = royalty_info(pic, &token_id, 1_000).expect("Token should exist");
+    assert_eq!(info.payouts.len(), 1);
+    assert_eq!(info.payouts[0].amount, Nat::from(50u64));
+
+    assert!(
+        royalty_info(pic, &Nat::from(9999u64), 1_000).is_none(),
+        "Unknown tokens have no royalty info"
+    );
+}
+
+#[test]
+fn test_token_metadata_updates_keep_reserved_keys() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let token_id = mint_nft(
+        pic,
+        Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    set_royalties(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_royalties::Args {
+            token_id: Some(token_id.clone()),
+            royalties: Some(Royalties {
+                recipients: vec![RoyaltyRecipient {
+                    account: Account {
+                        owner: controller,
+                        subaccount: None,
+                    },
+                    basis_points: 500,
+                }],
+                ledger: None,
+            }),
+        },
+    )
+    .expect("Should set token royalties");
+
+    let patch_response = update_nft_metadata_patch(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![update_nft_metadata_patch::MetadataPatch {
+            token_id: token_id.clone(),
+            set: vec![],
+            unset: vec!["icrc7:royalties".to_string()],
+        }],
+    );
+    assert!(
+        matches!(
+            &patch_response[0],
+            Some(Err(update_nft_metadata::UpdateNftMetadataError::ReservedMetadataKey(key)))
+                if key == "icrc7:royalties"
+        ),
+        "Royalties should only be cleared through set_royalties"
+    );
+
+    let update_result = update_nft_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &update_nft_metadata::Args {
+            token_id: token_id.clone(),
+            metadata: vec![(
+                "icrc7:transferable".to_string(),
+                ICRC3Value::Text("false".to_string()),
+            )],
+        },
+    );
+    assert!(
+        matches!(
+            &update_result,
+            Err(update_nft_metadata::UpdateNftMetadataError::ReservedMetadataKey(key))
+                if key == "icrc7:transferable"
+        ),
+        "The transferable flag should only be set through set_transferable"
+    );
+
+    // A full replacement keeps the reserved keys it does not mention
+    update_nft_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &update_nft_metadata::Args {
+            token_id: token_id.clone(),
+            metadata: vec![("name".to_string(), ICRC3Value::Text("Replaced".to_string()))],
+        },
+    )
+    .expect("Should replace the token metadata");
+
+    let metadata = icrc7_token_metadata(pic, controller, collection_canister_id, &vec![token_id]);
+    let metadata = metadata[0].clone().unwrap();
+    assert!(metadata.iter().any(|(key, _)| key == "icrc7:royalties"));
+    assert!(metadata
+        .iter()
+        .any(|(key, value)| key == "name" && value == &ICRC3Value::Text("Replaced".to_string())));
+}
+
+#[test]
+fn test_commit_reveal() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let placeholder = vec![(
+        "name".to_string(),
+        ICRC3Value::Text("Unrevealed".to_string()),
+    )];
+    let mut token_ids = Vec::new();
+    for _ in 0..2 {
+        let token_id = mint_nft(
+            pic,
+            Account {
+                owner: nft_owner1,
+                subaccount: None,
+            },
+            controller,
+            collection_canister_id,
+            placeholder.clone(),
+        )
+        .expect("Failed to mint NFT");
+        token_ids.push(token_id);
+    }
+
+    let entries: Vec<RevealEntry> = token_ids
+        .iter()
+        .enumerate()
+        .map(|(index, token_id)| RevealEntry {
+            token_id: token_id.clone(),
+            metadata: vec![(
+                "name".to_string(),
+                ICRC3Value::Text(format!("Revealed #{}", index)),
+            )],
+        })
+        .collect();
+    let salt = serde_bytes::ByteBuf::from(b"drop salt".to_vec());
+    let commitment = reveal_commitment(&salt, &entries);
+
+    let reveal_result = reveal(
+        pic,
+        controller,
+        collection_canister_id,
+        &core_nft::types::reveal::reveal::Args {
+            salt: salt.clone(),
+            tokens: entries.clone(),
+        },
+    );
+    assert!(
+        matches!(
+            reveal_result,
+            Err(core_nft::types::reveal::reveal::RevealError::NotCommitted)
+        ),
+        "Reveal should require a commitment"
+    );
+
+    let commit_result = commit_reveal(
+        pic,
+        controller,
+        collection_canister_id,
+        &serde_bytes::ByteBuf::from(commitment.to_vec()),
+    );
+    assert!(commit_result.is_ok(), "Should commit the reveal hash");
+
+    let commit_result = commit_reveal(
+        pic,
+        controller,
+        collection_canister_id,
+        &serde_bytes::ByteBuf::from(commitment.to_vec()),
+    );
+    assert!(
+        matches!(
+            commit_result,
+            Err(core_nft::types::reveal::commit_reveal::CommitRevealError::AlreadyCommitted)
+        ),
+        "A pending commitment cannot be replaced"
+    );
+
+    let collection_metadata =
+        icrc7_collection_metadata(pic, controller, collection_canister_id, &());
+    assert!(collection_metadata
+        .iter()
+        .any(|(key, value)| key == "icrc7:reveal_commitment"
+            && *value == ICRC3Value::Blob(serde_bytes::ByteBuf::from(commitment.to_vec()))));
+
+    // Swapping the metadata of the two tokens must not match the commitment
+    let mut shuffled = entries.clone();
+    let first_metadata = shuffled[0].metadata.clone();
+    shuffled[0].metadata = shuffled[1].metadata.clone();
+    shuffled[1].metadata = first_metadata;
+
+    let reveal_result = reveal(
+        pic,
+        controller,
+        collection_canister_id,
+        &core_nft::types::reveal::reveal::Args {
+            salt: salt.clone(),
+            tokens: shuffled,
+        },
+    );
+    assert!(
+        matches!(
+            reveal_result,
+            Err(core_nft::types::reveal::reveal::RevealError::CommitmentMismatch)
+        ),
+        "A reshuffled reveal should be rejected"
+    );
+
+    let metadata = icrc7_token_metadata(pic, controller, collection_canister_id, &token_ids);
+    assert!(metadata[0]
+        .as_ref()
+        .unwrap()
+        .iter()
+        .any(|(key, value)| key == "name" && *value == ICRC3Value::Text("Unrevealed".to_string())));
+
+    let reveal_result = reveal(
+        pic,
+        controller,
+        collection_canister_id,
+        &core_nft::types::reveal::reveal::Args {
+            salt: salt.clone(),
+            tokens: entries.clone(),
+        },
+    );
+    assert!(reveal_result.is_ok(), "Reveal should succeed");
+
+    let metadata = icrc7_token_metadata(pic, controller, collection_canister_id, &token_ids);
+    for (index, token_metadata) in metadata.iter().enumerate() {
+        assert!(token_metadata
+            .as_ref()
+            .unwrap()
+            .iter()
+            .any(|(key, value)| key == "name"
+                && *value == ICRC3Value::Text(format!("Revealed #{}", index))));
+    }
+
+    let status = get_reveal_commitment(pic, controller, collection_canister_id, &())
+        .expect("Commitment should be recorded");
+    assert_eq!(status.salt, Some(salt.clone()));
+    assert!(status.revealed_at.is_some());
+
+    let collection_metadata =
+        icrc7_collection_metadata(pic, controller, collection_canister_id, &());
+    assert!(collection_metadata
+        .iter()
+        .any(|(key, value)| key == "icrc7:revealed"
+            && *value == ICRC3Value::Text("true".to_string())));
+
+    let reveal_result = reveal(
+        pic,
+        controller,
+        collection_canister_id,
+        &core_nft::types::reveal::reveal::Args {
+            salt,
+            tokens: entries,
+        },
+    );
+    assert!(
+        matches!(
+            reveal_result,
+            Err(core_nft::types::reveal::reveal::RevealError::AlreadyRevealed)
+        ),
+        "A collection can only be revealed once per commitment"
+    );
+}
+
+#[test]
+fn test_reveal_keeps_reserved_keys() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let token_id = mint_nft(
+        pic,
+        Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    set_royalties(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_royalties::Args {
+            token_id: Some(token_id.clone()),
+            royalties: Some(Royalties {
+                recipients: vec![RoyaltyRecipient {
+                    account: Account {
+                        owner: controller,
+                        subaccount: None,
+                    },
+                    basis_points: 500,
+                }],
+                ledger: None,
+            }),
+        },
+    )
+    .expect("Should set token royalties");
+
+    let reveal_with =
+        |pic: &mut pocket_ic::PocketIc, name: &str, metadata: Vec<(String, ICRC3Value)>| {
+            let entries = vec![RevealEntry {
+                token_id: token_id.clone(),
+                metadata,
+            }];
+            let salt = serde_bytes::ByteBuf::from(name.as_bytes().to_vec());
+            commit_reveal(
+                pic,
+                controller,
+                collection_canister_id,
+                &serde_bytes::ByteBuf::from(reveal_commitment(&salt, &entries).to_vec()),
+            )
+            .expect("Should commit the reveal hash");
+            reveal(
+                pic,
+                controller,
+                collection_canister_id,
+                &core_nft::types::reveal::reveal::Args {
+                    salt,
+                    tokens: entries,
+                },
+            )
+        };
+
+    let reveal_result = reveal_with(
+        pic,
+        "first",
+        vec![("name".to_string(), ICRC3Value::Text("Revealed".to_string()))],
+    );
+    assert!(reveal_result.is_ok(), "Reveal should succeed");
+
+    let metadata = icrc7_token_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert!(
+        metadata[0]
+            .as_ref()
+            .unwrap()
+            .iter()
+            .any(|(key, _)| key == "icrc7:royalties"),
+        "A reveal should keep the token royalties"
+    );
+
+    let reveal_result = reveal_with(
+        pic,
+        "second",
+        vec![(
+            "icrc7:transferable".to_string(),
+            ICRC3Value::Text("false".to_string()),
+        )],
+    );
+    assert!(
+        matches!(
+            &reveal_result,
+            Err(core_nft::types::reveal::reveal::RevealError::ReservedMetadataKey(key))
+                if key == "icrc7:transferable"
+        ),
+        "A reveal should not write reserved keys"
+    );
+}
@@ -0,0 +1,677 @@
+use crate::client::core_nft::{
+    burn_nfts, buy_token, cancel_listing, get_listing, icrc37_approve_tokens, icrc37_transfer_from,
+    icrc7_owner_of, icrc7_transfer, list_token, sale_transfer_from, set_marketplace_config,
+    set_royalties, set_royalty_enforcement, settle_marketplace_payouts,
+};
+use crate::client::ledger::icrc1_balance_of;
+use crate::core_suite::setup::default_test_setup;
+use crate::core_suite::setup::setup::TestEnv;
+use crate::core_suite::setup::setup_ledger::{setup_ledger_canister, LEDGER_FEE};
+use crate::utils::{approve_spender, create_default_metadata, mint_nft};
+use candid::{Encode, Nat, Principal};
+use core_nft::types::icrc37::{icrc37_approve_tokens, icrc37_transfer_from, ApprovalInfo};
+use core_nft::types::icrc7;
+use core_nft::types::management::{burn_nfts, set_royalties};
+use core_nft::types::marketplace::buy_token::BuyTokenError;
+use core_nft::types::marketplace::cancel_listing::CancelListingError;
+use core_nft::types::marketplace::list_token::ListTokenError;
+use core_nft::types::marketplace::sale_transfer_from::SaleTransferFromError;
+use core_nft::types::marketplace::{self, MarketplaceConfig};
+use core_nft::types::royalties::{Royalties, RoyaltyRecipient};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
+
+const INITIAL_BALANCE: u64 = 1_000_000_000;
+const PRICE: u64 = 5_000_000;
+
+fn list_args(token_id: &Nat, ledger: Principal) -> marketplace::list_token::Args {
+    marketplace::list_token::Args {
+        token_id: token_id.clone(),
+        from_subaccount: None,
+        price: Nat::from(PRICE),
+        ledger,
+        expires_at: None,
+    }
+}
+
+fn buy_args(token_id: &Nat, ledger: Principal) -> marketplace::buy_token::Args {
+    marketplace::buy_token::Args {
+        token_id: token_id.clone(),
+        from_subaccount: None,
+        price: Nat::from(PRICE),
+        ledger,
+    }
+}
+
+#[test]
+fn test_marketplace_listing_and_purchase() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let seller = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let buyer = Account {
+        owner: nft_owner2,
+        subaccount: None,
+    };
+
+    let ledger = setup_ledger_canister(pic, controller, vec![(buyer, Nat::from(INITIAL_BALANCE))]);
+
+    let token_id = mint_nft(
+        pic,
+        seller,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let list_response = list_token(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &list_args(&token_id, ledger),
+    );
+    assert!(
+        matches!(list_response, Err(ListTokenError::MarketplaceClosed)),
+        "Listing should require the marketplace to be configured"
+    );
+
+    let config_response = set_marketplace_config(
+        pic,
+        controller,
+        collection_canister_id,
+        &Some(MarketplaceConfig {
+            ledgers: vec![ledger],
+        }),
+    );
+    assert!(config_response.is_ok());
+
+    let list_response = list_token(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &list_args(&token_id, ledger),
+    );
+    assert!(
+        matches!(list_response, Err(ListTokenError::NotTokenOwner)),
+        "Only the owner can list a token"
+    );
+
+    let list_response = list_token(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &list_args(&token_id, Principal::management_canister()),
+    );
+    assert!(matches!(
+        list_response,
+        Err(ListTokenError::UnsupportedLedger)
+    ));
+
+    let list_response = list_token(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &list_args(&token_id, ledger),
+    );
+    assert!(list_response.is_ok(), "Owner should be able to list");
+
+    let listing = get_listing(pic, controller, collection_canister_id, &token_id)
+        .expect("Listing should be queryable");
+    assert_eq!(listing.seller, seller);
+    assert_eq!(listing.price, Nat::from(PRICE));
+
+    let listings: marketplace::get_listings::Response =
+        crate::client::pocket::unwrap_response(pic.query_call(
+            collection_canister_id,
+            controller,
+            "get_listings",
+            Encode!(&None::<Nat>, &None::<Nat>).unwrap(),
+        ));
+    assert_eq!(listings.len(), 1);
+
+    let cancel_response = cancel_listing(pic, nft_owner2, collection_canister_id, &token_id);
+    assert!(matches!(
+        cancel_response,
+        Err(CancelListingError::NotSeller)
+    ));
+
+    let buy_response = buy_token(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &marketplace::buy_token::Args {
+            price: Nat::from(PRICE - 1),
+            ..buy_args(&token_id, ledger)
+        },
+    );
+    assert!(
+        matches!(buy_response, Err(BuyTokenError::PriceMismatch { .. })),
+        "The buyer's price must match the listing"
+    );
+
+    let buy_response = buy_token(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &buy_args(&token_id, ledger),
+    );
+    assert!(
+        matches!(
+            buy_response,
+            Err(BuyTokenError::PaymentFailed(
+                TransferFromError::InsufficientAllowance { .. }
+            ))
+        ),
+        "Purchase should fail without an allowance"
+    );
+    let listing = get_listing(pic, controller, collection_canister_id, &token_id)
+        .expect("A failed payment should keep the listing");
+    assert!(!listing.pending_purchase);
+
+    approve_spender(pic, ledger, nft_owner2, collection_canister_id, 2 * PRICE);
+
+    let buy_response = buy_token(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &buy_args(&token_id, ledger),
+    );
+    assert!(buy_response.is_ok(), "Purchase should succeed");
+
+    let owner = icrc7_owner_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert_eq!(owner[0], Some(buyer), "The buyer should own the token");
+    assert!(get_listing(pic, controller, collection_canister_id, &token_id).is_none());
+
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &seller),
+        Nat::from(PRICE - LEDGER_FEE),
+        "The seller should receive the price minus the payout fee"
+    );
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &buyer),
+        Nat::from(INITIAL_BALANCE - LEDGER_FEE - PRICE - LEDGER_FEE)
+    );
+}
+
+#[test]
+fn test_marketplace_listing_invalidation() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let seller = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let ledger = setup_ledger_canister(pic, controller, vec![]);
+
+    let config_response = set_marketplace_config(
+        pic,
+        controller,
+        collection_canister_id,
+        &Some(MarketplaceConfig {
+            ledgers: vec![ledger],
+        }),
+    );
+    assert!(config_response.is_ok());
+
+    let transferred_token = mint_nft(
+        pic,
+        seller,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+    let burned_token = mint_nft(
+        pic,
+        seller,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    for token_id in [&transferred_token, &burned_token] {
+        let list_response = list_token(
+            pic,
+            nft_owner1,
+            collection_canister_id,
+            &list_args(token_id, ledger),
+        );
+        assert!(list_response.is_ok());
+    }
+
+    let transfer_response = icrc7_transfer(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![icrc7::TransferArg {
+            to: Account {
+                owner: nft_owner2,
+                subaccount: None,
+            },
+            token_id: transferred_token.clone(),
+            memo: None,
+            from_subaccount: None,
+            created_at_time: None,
+        }],
+    );
+    assert!(matches!(transfer_response[0], Some(Ok(_))));
+    assert!(
+        get_listing(pic, controller, collection_canister_id, &transferred_token).is_none(),
+        "Transfers should invalidate the listing"
+    );
+
+    let burn_response = burn_nfts(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![burn_nfts::BurnArg {
+            token_id: burned_token.clone(),
+            memo: None,
+            created_at_time: None,
+        }],
+    );
+    assert!(matches!(burn_response[0], Some(Ok(_))));
+    assert!(
+        get_listing(pic, controller, collection_canister_id, &burned_token).is_none(),
+        "Burns should invalidate the listing"
+    );
+}
+
+#[test]
+fn test_sale_transfer_from_pays_royalties() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let seller = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let buyer = Account {
+        owner: nft_owner2,
+        subaccount: None,
+    };
+    let creator = Account {
+        owner: controller,
+        subaccount: None,
+    };
+
+    let ledger = setup_ledger_canister(pic, controller, vec![(buyer, Nat::from(INITIAL_BALANCE))]);
+
+    let token_id = mint_nft(
+        pic,
+        seller,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let royalties_response = set_royalties(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_royalties::Args {
+            token_id: None,
+            royalties: Some(Royalties {
+                recipients: vec![RoyaltyRecipient {
+                    account: creator,
+                    basis_points: 1_000,
+                }],
+                ledger: Some(ledger),
+            }),
+        },
+    );
+    assert!(royalties_response.is_ok());
+
+    let enforcement_response =
+        set_royalty_enforcement(pic, controller, collection_canister_id, &true);
+    assert!(enforcement_response.is_ok());
+
+    let approve_response = icrc37_approve_tokens(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![icrc37_approve_tokens::ApproveTokenArg {
+            token_id: token_id.clone(),
+            approval_info: ApprovalInfo {
+                spender: buyer,
+                from_subaccount: None,
+                expires_at: None,
+                memo: None,
+                created_at_time: pic.get_time().as_nanos_since_unix_epoch(),
+            },
+        }],
+    );
+    assert!(approve_response.is_ok());
+
+    let transfer_response = icrc37_transfer_from(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &vec![icrc37_transfer_from::TransferFromArg {
+            spender_subaccount: None,
+            from: seller,
+            to: buyer,
+            token_id: token_id.clone(),
+            memo: None,
+            created_at_time: None,
+        }],
+    )
+    .expect("Batch should be accepted");
+    assert!(
+        matches!(
+            transfer_response[0],
+            Some(icrc37_transfer_from::TransferFromResult::Err(
+                icrc37_transfer_from::TransferFromError::GenericError { .. }
+            ))
+        ),
+        "Plain transfer_from should be rejected while royalties are enforced"
+    );
+
+    let sale_args = marketplace::sale_transfer_from::Args {
+        spender_subaccount: None,
+        from: seller,
+        to: buyer,
+        token_id: token_id.clone(),
+        memo: None,
+        created_at_time: None,
+        price: Nat::from(PRICE),
+        ledger,
+    };
+
+    let sale_response = sale_transfer_from(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &marketplace::sale_transfer_from::Args {
+            ledger: Principal::management_canister(),
+            ..sale_args.clone()
+        },
+    );
+    assert!(
+        matches!(sale_response, Err(SaleTransferFromError::UnsupportedLedger)),
+        "Sales must use the royalty ledger"
+    );
+
+    let sale_response = sale_transfer_from(pic, controller, collection_canister_id, &sale_args);
+    assert!(
+        matches!(
+            sale_response,
+            Err(SaleTransferFromError::InvalidTransfer(
+                icrc37_transfer_from::TransferFromError::Unauthorized
+            ))
+        ),
+        "Only approved spenders should reach the payment"
+    );
+
+    let sale_response = sale_transfer_from(pic, nft_owner2, collection_canister_id, &sale_args);
+    assert!(
+        matches!(sale_response, Err(SaleTransferFromError::PaymentFailed(_))),
+        "Sales should fail without an allowance"
+    );
+
+    approve_spender(pic, ledger, nft_owner2, collection_canister_id, 2 * PRICE);
+
+    let sale_response = sale_transfer_from(pic, nft_owner2, collection_canister_id, &sale_args);
+    assert!(sale_response.is_ok(), "Sale should succeed");
+
+    let owner = icrc7_owner_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert_eq!(owner[0], Some(buyer), "The buyer should own the token");
+
+    let royalty = PRICE / 10;
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &creator),
+        Nat::from(royalty - LEDGER_FEE),
+        "The royalty recipient should be paid"
+    );
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &seller),
+        Nat::from(PRICE - royalty - LEDGER_FEE),
+        "The seller should receive the price minus royalties"
+    );
+}
+
+#[test]
+fn test_purchase_revokes_token_approvals() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let seller = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let buyer = Account {
+        owner: nft_owner2,
+        subaccount: None,
+    };
+    let spender = Account {
+        owner: controller,
+        subaccount: None,
+    };
+
+    let ledger = setup_ledger_canister(pic, controller, vec![(buyer, Nat::from(INITIAL_BALANCE))]);
+    let config_response = set_marketplace_config(
+        pic,
+        controller,
+        collection_canister_id,
+        &Some(MarketplaceConfig {
+            ledgers: vec![ledger],
+        }),
+    );
+    assert!(config_response.is_ok());
+
+    let token_id = mint_nft(
+        pic,
+        seller,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let approve_response = icrc37_approve_tokens(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![icrc37_approve_tokens::ApproveTokenArg {
+            token_id: token_id.clone(),
+            approval_info: ApprovalInfo {
+                spender,
+                from_subaccount: None,
+                expires_at: None,
+                memo: None,
+                created_at_time: pic.get_time().as_nanos_since_unix_epoch(),
+            },
+        }],
+    );
+    assert!(approve_response.is_ok());
+
+    let list_response = list_token(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &list_args(&token_id, ledger),
+    );
+    assert!(list_response.is_ok());
+
+    approve_spender(pic, ledger, nft_owner2, collection_canister_id, 2 * PRICE);
+    let buy_response = buy_token(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &buy_args(&token_id, ledger),
+    );
+    assert!(buy_response.is_ok(), "Purchase should succeed");
+
+    let transfer_response = icrc37_transfer_from(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![icrc37_transfer_from::TransferFromArg {
+            spender_subaccount: None,
+            from: buyer,
+            to: spender,
+            token_id: token_id.clone(),
+            memo: None,
+            created_at_time: None,
+        }],
+    )
+    .expect("Batch should be accepted");
+    assert!(
+        matches!(
+            transfer_response[0],
+            Some(icrc37_transfer_from::TransferFromResult::Err(
+                icrc37_transfer_from::TransferFromError::Unauthorized
+            ))
+        ),
+        "The seller's approval should not survive the sale"
+    );
+
+    let owner = icrc7_owner_of(pic, controller, collection_canister_id, &vec![token_id]);
+    assert_eq!(owner[0], Some(buyer));
+}
+
+#[test]
+fn test_royalty_dust_goes_to_seller() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let seller = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let buyer = Account {
+        owner: nft_owner2,
+        subaccount: None,
+    };
+    let creator = Account {
+        owner: controller,
+        subaccount: None,
+    };
+
+    let ledger = setup_ledger_canister(pic, controller, vec![(buyer, Nat::from(INITIAL_BALANCE))]);
+    let config_response = set_marketplace_config(
+        pic,
+        controller,
+        collection_canister_id,
+        &Some(MarketplaceConfig {
+            ledgers: vec![ledger],
+        }),
+    );
+    assert!(config_response.is_ok());
+
+    let token_id = mint_nft(
+        pic,
+        seller,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    // 0.1% of the price is below the ledger fee
+    let royalties_response = set_royalties(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_royalties::Args {
+            token_id: None,
+            royalties: Some(Royalties {
+                recipients: vec![RoyaltyRecipient {
+                    account: creator,
+                    basis_points: 10,
+                }],
+                ledger: None,
+            }),
+        },
+    );
+    assert!(royalties_response.is_ok());
+
+    let list_response = list_token(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &list_args(&token_id, ledger),
+    );
+    assert!(list_response.is_ok());
+
+    approve_spender(pic, ledger, nft_owner2, collection_canister_id, 2 * PRICE);
+    let buy_response = buy_token(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &buy_args(&token_id, ledger),
+    );
+    assert!(buy_response.is_ok(), "Purchase should succeed");
+
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &creator),
+        Nat::from(0u64)
+    );
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &seller),
+        Nat::from(PRICE - LEDGER_FEE),
+        "A royalty share below the fee should be paid to the seller"
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_settle_marketplace_payouts_unauthorized() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller: _,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    settle_marketplace_payouts(pic, nft_owner1, collection_canister_id, &());
+}
@@ -0,0 +1,343 @@
+use crate::client::core_nft::{
+    approve_proposal, create_proposal, delete_role, get_multisig_config, get_proposal, get_roles,
+    get_user_permissions, grant_permission, grant_role, icrc3_get_blocks,
+    icrc7_collection_metadata, list_proposals, pause_collection, revoke_permission, revoke_role,
+    set_multisig_config, set_role,
+};
+use crate::core_suite::setup::default_test_setup;
+use crate::core_suite::setup::setup::TestEnv;
+use crate::utils::random_principal;
+use candid::Nat;
+use core_nft::types::management::{
+    delete_role, get_user_permissions, grant_permission, grant_role, revoke_permission,
+    revoke_role, set_role,
+};
+use core_nft::types::multisig::approve_proposal::ApproveProposalError;
+use core_nft::types::multisig::create_proposal::CreateProposalError;
+use core_nft::types::multisig::set_multisig_config::SetMultisigConfigError;
+use core_nft::types::multisig::{list_proposals, MultisigConfig, ProposalAction, ProposalStatus};
+use core_nft::types::pause_collection::PauseCollectionError;
+use core_nft::types::permissions::Permission;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
+use std::time::Duration;
+
+const PROPOSAL_DURATION: Duration = Duration::from_secs(3600);
+
+#[test]
+fn test_proposal_executes_at_threshold() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let config = MultisigConfig {
+        signers: vec![controller, nft_owner1, nft_owner2],
+        threshold: 2,
+        proposal_duration: PROPOSAL_DURATION.as_nanos() as u64,
+    };
+    let response = set_multisig_config(
+        pic,
+        controller,
+        collection_canister_id,
+        &MultisigConfig {
+            threshold: 4,
+            ..config.clone()
+        },
+    );
+    assert!(matches!(
+        response,
+        Err(SetMultisigConfigError::InvalidConfig)
+    ));
+    assert!(set_multisig_config(pic, controller, collection_canister_id, &config).is_ok());
+    assert_eq!(
+        get_multisig_config(pic, controller, collection_canister_id, &()),
+        Some(config.clone())
+    );
+
+    let response = set_multisig_config(pic, controller, collection_canister_id, &config);
+    assert!(matches!(
+        response,
+        Err(SetMultisigConfigError::MultisigRequired)
+    ));
+
+    let response = pause_collection(pic, controller, collection_canister_id, &());
+    assert!(
+        matches!(response, Err(PauseCollectionError::MultisigRequired)),
+        "Pausing should need a proposal"
+    );
+    let response = grant_permission(
+        pic,
+        controller,
+        collection_canister_id,
+        &grant_permission::Args {
+            principal: random_principal(),
+            permission: Permission::ManageAuthorities,
+            expires_at: None,
+        },
+    );
+    assert!(matches!(
+        response,
+        Err(grant_permission::GrantPermissionError::MultisigRequired)
+    ));
+
+    let response = create_proposal(
+        pic,
+        random_principal(),
+        collection_canister_id,
+        &ProposalAction::PauseCollection,
+    );
+    assert!(matches!(response, Err(CreateProposalError::NotSigner)));
+
+    let proposal_id = create_proposal(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &ProposalAction::PauseCollection,
+    )
+    .expect("Signer should create a proposal");
+
+    let proposal = get_proposal(pic, controller, collection_canister_id, &proposal_id)
+        .expect("Proposal should exist");
+    assert_eq!(proposal.status, ProposalStatus::Open);
+    assert_eq!(proposal.approvals, vec![nft_owner1]);
+
+    let response = approve_proposal(pic, nft_owner1, collection_canister_id, &proposal_id);
+    assert!(matches!(
+        response,
+        Err(ApproveProposalError::AlreadyApproved)
+    ));
+
+    let response = approve_proposal(pic, nft_owner2, collection_canister_id, &proposal_id);
+    assert_eq!(response.unwrap(), ProposalStatus::Executed);
+
+    let metadata = icrc7_collection_metadata(pic, controller, collection_canister_id, &());
+    assert!(metadata.contains(&(
+        "icrc7:paused".to_string(),
+        ICRC3Value::Text("true".to_string())
+    )));
+
+    let response = approve_proposal(pic, controller, collection_canister_id, &proposal_id);
+    assert!(matches!(
+        response,
+        Err(ApproveProposalError::ProposalNotOpen)
+    ));
+
+    let blocks = icrc3_get_blocks(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![GetBlocksRequest {
+            start: Nat::from(0u64),
+            length: Nat::from(100u64),
+        }],
+    );
+    let btypes: Vec<String> = blocks
+        .blocks
+        .iter()
+        .filter_map(|block| match &block.block {
+            ICRC3Value::Map(map) => match map.get("btype") {
+                Some(ICRC3Value::Text(btype)) if btype.starts_with("proposal_") => {
+                    Some(btype.clone())
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        btypes,
+        vec!["proposal_create", "proposal_approve", "proposal_execute"]
+    );
+}
+
+#[test]
+fn test_proposal_expires() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    set_multisig_config(
+        pic,
+        controller,
+        collection_canister_id,
+        &MultisigConfig {
+            signers: vec![nft_owner1, nft_owner2],
+            threshold: 2,
+            proposal_duration: PROPOSAL_DURATION.as_nanos() as u64,
+        },
+    )
+    .unwrap();
+
+    let grantee = random_principal();
+    let proposal_id = create_proposal(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &ProposalAction::GrantPermission(grant_permission::Args {
+            principal: grantee,
+            permission: Permission::Minting,
+            expires_at: None,
+        }),
+    )
+    .unwrap();
+
+    pic.advance_time(PROPOSAL_DURATION + Duration::from_secs(60));
+    pic.tick();
+
+    let proposal = get_proposal(pic, controller, collection_canister_id, &proposal_id).unwrap();
+    assert_eq!(proposal.status, ProposalStatus::Expired);
+
+    let response = approve_proposal(pic, nft_owner2, collection_canister_id, &proposal_id);
+    assert!(matches!(
+        response,
+        Err(ApproveProposalError::ProposalNotOpen)
+    ));
+
+    let proposals = list_proposals(
+        pic,
+        controller,
+        collection_canister_id,
+        &list_proposals::Args {
+            prev: None,
+            take: None,
+        },
+    );
+    assert_eq!(proposals.len(), 1);
+    assert_eq!(proposals[0].proposal_id, proposal_id);
+}
+
+#[test]
+fn test_revocations_need_a_proposal() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1: _,
+        nft_owner2: _,
+    } = test_env;
+
+    let grantee = random_principal();
+    set_role(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_role::Args {
+            name: "minter".to_string(),
+            permissions: vec![Permission::Minting],
+        },
+    )
+    .unwrap();
+    grant_role(
+        pic,
+        controller,
+        collection_canister_id,
+        &grant_role::Args {
+            principal: grantee,
+            role: "minter".to_string(),
+            expires_at: None,
+        },
+    )
+    .unwrap();
+    grant_permission(
+        pic,
+        controller,
+        collection_canister_id,
+        &grant_permission::Args {
+            principal: grantee,
+            permission: Permission::UpdateMetadata,
+            expires_at: None,
+        },
+    )
+    .unwrap();
+
+    set_multisig_config(
+        pic,
+        controller,
+        collection_canister_id,
+        &MultisigConfig {
+            signers: vec![controller],
+            threshold: 1,
+            proposal_duration: PROPOSAL_DURATION.as_nanos() as u64,
+        },
+    )
+    .unwrap();
+
+    let revoke_permission_args = revoke_permission::Args {
+        principal: grantee,
+        permission: Permission::UpdateMetadata,
+    };
+    let response = revoke_permission(
+        pic,
+        controller,
+        collection_canister_id,
+        &revoke_permission_args,
+    );
+    assert!(matches!(
+        response,
+        Err(revoke_permission::RevokePermissionError::MultisigRequired)
+    ));
+    let revoke_role_args = revoke_role::Args {
+        principal: grantee,
+        role: "minter".to_string(),
+    };
+    let response = revoke_role(pic, controller, collection_canister_id, &revoke_role_args);
+    assert!(matches!(
+        response,
+        Err(revoke_role::RevokeRoleError::MultisigRequired)
+    ));
+    let response = delete_role(
+        pic,
+        controller,
+        collection_canister_id,
+        &"minter".to_string(),
+    );
+    assert!(matches!(
+        response,
+        Err(delete_role::DeleteRoleError::MultisigRequired)
+    ));
+    assert_eq!(
+        get_user_permissions(
+            pic,
+            controller,
+            collection_canister_id,
+            &get_user_permissions::Args { principal: grantee },
+        )
+        .unwrap()
+        .len(),
+        2,
+        "Refused revocations should not touch the grants"
+    );
+
+    for action in [
+        ProposalAction::RevokePermission(revoke_permission_args),
+        ProposalAction::RevokeRole(revoke_role_args),
+        ProposalAction::DeleteRole("minter".to_string()),
+    ] {
+        let proposal_id = create_proposal(pic, controller, collection_canister_id, &action)
+            .expect("Signer should create a proposal");
+        let proposal = get_proposal(pic, controller, collection_canister_id, &proposal_id).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Executed);
+    }
+
+    assert!(matches!(
+        get_user_permissions(
+            pic,
+            controller,
+            collection_canister_id,
+            &get_user_permissions::Args { principal: grantee },
+        ),
+        Err(get_user_permissions::GetUserPermissionsError::UserNotFound)
+    ));
+    assert!(get_roles(pic, controller, collection_canister_id, &()).is_empty());
+}
@@ -0,0 +1,259 @@
+use crate::client::core_nft::{
+    attach_child, burn_nft, detach_child, get_children, get_parent, icrc3_get_blocks,
+    icrc7_owner_of, icrc7_root_owner_of, icrc7_transfer,
+};
+use crate::core_suite::setup::default_test_setup;
+use crate::core_suite::setup::setup::TestEnv;
+use crate::utils::{create_default_metadata, mint_nft};
+use candid::Nat;
+use core_nft::types::icrc7;
+use core_nft::types::management::burn_nft::BurnNftError;
+use core_nft::types::nest::attach_child::{self, AttachChildError};
+use core_nft::types::nest::detach_child::{self, DetachChildError};
+use core_nft::types::nest::{ChildToken, MAX_NESTING_DEPTH};
+use core_nft::types::nft::token_subaccount;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
+
+#[test]
+fn test_nest_attach_transfer_and_detach() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let owner1 = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let owner2 = Account {
+        owner: nft_owner2,
+        subaccount: None,
+    };
+
+    let parent_id = mint_nft(
+        pic,
+        owner1,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint parent");
+    let child_id = mint_nft(
+        pic,
+        owner1,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint child");
+
+    let child = ChildToken {
+        collection: collection_canister_id,
+        token_id: child_id.clone(),
+    };
+    let attach_args = attach_child::Args {
+        parent_id: parent_id.clone(),
+        child: child.clone(),
+        from_subaccount: None,
+    };
+
+    let response = attach_child(pic, nft_owner2, collection_canister_id, &attach_args);
+    assert!(
+        matches!(response, Err(AttachChildError::NotTokenOwner)),
+        "Only the parent's owner should attach children"
+    );
+
+    let response = attach_child(pic, nft_owner1, collection_canister_id, &attach_args);
+    assert!(response.is_ok(), "Attach should succeed: {:?}", response);
+
+    let children = get_children(pic, controller, collection_canister_id, &parent_id);
+    assert_eq!(children, vec![child.clone()]);
+    let parent = get_parent(pic, controller, collection_canister_id, &child_id);
+    assert_eq!(parent, Some(parent_id.clone()));
+
+    let owners = icrc7_owner_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![child_id.clone()],
+    );
+    assert_eq!(
+        owners,
+        vec![Some(Account {
+            owner: collection_canister_id,
+            subaccount: Some(token_subaccount(&parent_id)),
+        })],
+        "The child should be held by the parent's token account"
+    );
+
+    let response = attach_child(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &attach_child::Args {
+            parent_id: child_id.clone(),
+            child: ChildToken {
+                collection: collection_canister_id,
+                token_id: parent_id.clone(),
+            },
+            from_subaccount: None,
+        },
+    );
+    assert!(matches!(response, Err(AttachChildError::CycleDetected)));
+
+    let response = burn_nft(pic, nft_owner1, collection_canister_id, &parent_id);
+    assert!(
+        matches!(response, Err(BurnNftError::HasChildren)),
+        "A parent with children should not be burnable"
+    );
+
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let transfer_response = icrc7_transfer(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![icrc7::TransferArg {
+            to: owner2,
+            token_id: parent_id.clone(),
+            memo: None,
+            from_subaccount: None,
+            created_at_time: Some(now),
+        }],
+    );
+    assert!(transfer_response[0].as_ref().is_some_and(|r| r.is_ok()));
+
+    let root_owners = icrc7_root_owner_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![child_id.clone()],
+    );
+    assert_eq!(
+        root_owners,
+        vec![Some(owner2)],
+        "The child should follow its parent"
+    );
+
+    let detach_args = detach_child::Args {
+        parent_id: parent_id.clone(),
+        child: child.clone(),
+        from_subaccount: None,
+        to: None,
+    };
+
+    let response = detach_child(pic, nft_owner1, collection_canister_id, &detach_args);
+    assert!(matches!(response, Err(DetachChildError::NotTokenOwner)));
+
+    let response = detach_child(pic, nft_owner2, collection_canister_id, &detach_args);
+    assert!(response.is_ok(), "Detach should succeed: {:?}", response);
+
+    let owners = icrc7_owner_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![child_id.clone()],
+    );
+    assert_eq!(owners, vec![Some(owner2)]);
+    let children = get_children(pic, controller, collection_canister_id, &parent_id);
+    assert!(children.is_empty());
+
+    let response = detach_child(pic, nft_owner2, collection_canister_id, &detach_args);
+    assert!(matches!(response, Err(DetachChildError::ChildNotFound)));
+
+    let blocks = icrc3_get_blocks(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![GetBlocksRequest {
+            start: Nat::from(0u64),
+            length: Nat::from(100u64),
+        }],
+    );
+    let btypes: Vec<String> = blocks
+        .blocks
+        .iter()
+        .filter_map(|block| match &block.block {
+            ICRC3Value::Map(map) => match map.get("btype") {
+                Some(ICRC3Value::Text(btype)) => Some(btype.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    assert!(btypes.contains(&"nest_attach".to_string()));
+    assert!(btypes.contains(&"nest_detach".to_string()));
+}
+
+#[test]
+fn test_nest_depth_counts_child_subtree() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let owner = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let token_ids: Vec<Nat> = (0..=MAX_NESTING_DEPTH)
+        .map(|_| {
+            mint_nft(
+                pic,
+                owner,
+                controller,
+                collection_canister_id,
+                create_default_metadata(),
+            )
+            .expect("Failed to mint NFT")
+        })
+        .collect();
+    let attach_args = |parent_id: &Nat, child_id: &Nat| attach_child::Args {
+        parent_id: parent_id.clone(),
+        child: ChildToken {
+            collection: collection_canister_id,
+            token_id: child_id.clone(),
+        },
+        from_subaccount: None,
+    };
+
+    // Chain the last MAX_NESTING_DEPTH tokens bottom-up, so every attach moves a whole subtree.
+    let chain = &token_ids[1..];
+    for pair in chain.windows(2).rev() {
+        let response = attach_child(
+            pic,
+            nft_owner1,
+            collection_canister_id,
+            &attach_args(&pair[0], &pair[1]),
+        );
+        assert!(response.is_ok(), "Attach should succeed: {:?}", response);
+    }
+
+    let response = attach_child(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &attach_args(&token_ids[0], &chain[0]),
+    );
+    assert!(
+        matches!(response, Err(AttachChildError::MaxDepthExceeded)),
+        "Attaching the chain under another token would exceed the maximum depth"
+    );
+
+    let root_owners = icrc7_root_owner_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![chain[chain.len() - 1].clone()],
+    );
+    assert_eq!(root_owners, vec![Some(owner)]);
+}
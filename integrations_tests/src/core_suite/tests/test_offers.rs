@@ -0,0 +1,320 @@
+use crate::client::core_nft::{
+    accept_offer, cancel_offer, get_offer, get_token_offers, icrc3_get_blocks, icrc7_owner_of,
+    make_offer, set_marketplace_config,
+};
+use crate::client::ledger::icrc1_balance_of;
+use crate::core_suite::setup::default_test_setup;
+use crate::core_suite::setup::setup::TestEnv;
+use crate::core_suite::setup::setup_ledger::{setup_ledger_canister, LEDGER_FEE};
+use crate::utils::{approve_spender, create_default_metadata, mint_nft};
+use candid::{Nat, Principal};
+use core_nft::types::marketplace::MarketplaceConfig;
+use core_nft::types::offer::accept_offer::AcceptOfferError;
+use core_nft::types::offer::cancel_offer::CancelOfferError;
+use core_nft::types::offer::make_offer::{self, MakeOfferError};
+use core_nft::types::offer::offer_escrow_subaccount;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
+use pocket_ic::PocketIc;
+use std::time::Duration;
+
+const INITIAL_BALANCE: u64 = 1_000_000_000;
+const AMOUNT: u64 = 5_000_000;
+
+fn offer_args(token_id: &Nat, ledger: Principal, expires_at: u64) -> make_offer::Args {
+    make_offer::Args {
+        token_id: token_id.clone(),
+        from_subaccount: None,
+        amount: Nat::from(AMOUNT),
+        ledger,
+        expires_at,
+    }
+}
+
+fn block_types(pic: &mut PocketIc, controller: Principal, collection: Principal) -> Vec<String> {
+    let blocks = icrc3_get_blocks(
+        pic,
+        controller,
+        collection,
+        &vec![GetBlocksRequest {
+            start: Nat::from(0u64),
+            length: Nat::from(100u64),
+        }],
+    );
+
+    blocks
+        .blocks
+        .iter()
+        .filter_map(|block| match &block.block {
+            ICRC3Value::Map(map) => match map.get("btype") {
+                Some(ICRC3Value::Text(btype)) => Some(btype.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_offer_accept_and_cancel() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let seller = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let buyer = Account {
+        owner: nft_owner2,
+        subaccount: None,
+    };
+
+    let ledger = setup_ledger_canister(pic, controller, vec![(buyer, Nat::from(INITIAL_BALANCE))]);
+
+    let token_id = mint_nft(
+        pic,
+        seller,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let expires_at =
+        pic.get_time().as_nanos_since_unix_epoch() + Duration::from_secs(3600).as_nanos() as u64;
+
+    let offer_response = make_offer(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &offer_args(&token_id, ledger, expires_at),
+    );
+    assert!(
+        matches!(offer_response, Err(MakeOfferError::MarketplaceClosed)),
+        "Offers should require the marketplace to be configured"
+    );
+
+    let config_response = set_marketplace_config(
+        pic,
+        controller,
+        collection_canister_id,
+        &Some(MarketplaceConfig {
+            ledgers: vec![ledger],
+        }),
+    );
+    assert!(config_response.is_ok());
+
+    let offer_response = make_offer(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &offer_args(&token_id, ledger, expires_at),
+    );
+    assert!(matches!(
+        offer_response,
+        Err(MakeOfferError::AlreadyTokenOwner)
+    ));
+
+    let offer_response = make_offer(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &offer_args(&token_id, ledger, expires_at),
+    );
+    assert!(
+        matches!(offer_response, Err(MakeOfferError::PaymentFailed(_))),
+        "Offers should fail without an allowance"
+    );
+
+    approve_spender(pic, ledger, nft_owner2, collection_canister_id, 4 * AMOUNT);
+
+    let cancelled_offer = make_offer(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &offer_args(&token_id, ledger, expires_at),
+    )
+    .expect("Offer should be escrowed");
+    assert_eq!(
+        icrc1_balance_of(
+            pic,
+            controller,
+            ledger,
+            &Account {
+                owner: collection_canister_id,
+                subaccount: Some(offer_escrow_subaccount(&cancelled_offer)),
+            }
+        ),
+        Nat::from(AMOUNT),
+        "Funds should be held in the offer's escrow subaccount"
+    );
+
+    let cancel_response = cancel_offer(pic, nft_owner1, collection_canister_id, &cancelled_offer);
+    assert!(matches!(cancel_response, Err(CancelOfferError::NotBuyer)));
+
+    let cancel_response = cancel_offer(pic, nft_owner2, collection_canister_id, &cancelled_offer);
+    assert!(
+        cancel_response.is_ok(),
+        "The buyer should be able to cancel"
+    );
+    assert!(get_offer(pic, controller, collection_canister_id, &cancelled_offer).is_none());
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &buyer),
+        Nat::from(INITIAL_BALANCE - LEDGER_FEE - AMOUNT - LEDGER_FEE + AMOUNT - LEDGER_FEE),
+        "A cancelled offer should be refunded"
+    );
+
+    let offer_id = make_offer(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &offer_args(&token_id, ledger, expires_at),
+    )
+    .expect("Offer should be escrowed");
+
+    let offers = get_token_offers(pic, controller, collection_canister_id, &token_id);
+    assert_eq!(offers.len(), 1);
+    assert_eq!(offers[0].buyer, buyer);
+
+    let accept_response = accept_offer(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &core_nft::types::offer::accept_offer::Args {
+            offer_id: offer_id.clone(),
+            from_subaccount: None,
+        },
+    );
+    assert!(
+        matches!(accept_response, Err(AcceptOfferError::NotTokenOwner)),
+        "Only the token owner can accept an offer"
+    );
+
+    let accept_response = accept_offer(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &core_nft::types::offer::accept_offer::Args {
+            offer_id: offer_id.clone(),
+            from_subaccount: None,
+        },
+    );
+    assert!(accept_response.is_ok(), "The owner should accept the offer");
+
+    let owner = icrc7_owner_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert_eq!(owner[0], Some(buyer), "The buyer should own the token");
+    assert!(get_offer(pic, controller, collection_canister_id, &offer_id).is_none());
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &seller),
+        Nat::from(AMOUNT - LEDGER_FEE),
+        "The seller should receive the escrow minus the payout fee"
+    );
+
+    let btypes = block_types(pic, controller, collection_canister_id);
+    for btype in ["offer", "offer_cancel", "offer_accept", "7xfer"] {
+        assert!(
+            btypes.iter().any(|b| b == btype),
+            "Missing {} block in the ICRC-3 log",
+            btype
+        );
+    }
+}
+
+#[test]
+fn test_offer_expiry() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let buyer = Account {
+        owner: nft_owner2,
+        subaccount: None,
+    };
+    let ledger = setup_ledger_canister(pic, controller, vec![(buyer, Nat::from(INITIAL_BALANCE))]);
+
+    let config_response = set_marketplace_config(
+        pic,
+        controller,
+        collection_canister_id,
+        &Some(MarketplaceConfig {
+            ledgers: vec![ledger],
+        }),
+    );
+    assert!(config_response.is_ok());
+
+    let token_id = mint_nft(
+        pic,
+        Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    approve_spender(pic, ledger, nft_owner2, collection_canister_id, 2 * AMOUNT);
+
+    let expires_at =
+        pic.get_time().as_nanos_since_unix_epoch() + Duration::from_secs(60).as_nanos() as u64;
+    let offer_id = make_offer(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &offer_args(&token_id, ledger, expires_at),
+    )
+    .expect("Offer should be escrowed");
+
+    pic.advance_time(Duration::from_secs(120));
+    for _ in 0..10 {
+        pic.tick();
+    }
+
+    let accept_response = accept_offer(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &core_nft::types::offer::accept_offer::Args {
+            offer_id: offer_id.clone(),
+            from_subaccount: None,
+        },
+    );
+    assert!(
+        matches!(
+            accept_response,
+            Err(AcceptOfferError::OfferNotFound) | Err(AcceptOfferError::OfferExpired)
+        ),
+        "Expired offers cannot be accepted"
+    );
+
+    assert!(
+        get_offer(pic, controller, collection_canister_id, &offer_id).is_none(),
+        "The expiry job should remove the offer"
+    );
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &buyer),
+        Nat::from(INITIAL_BALANCE - LEDGER_FEE - LEDGER_FEE - LEDGER_FEE),
+        "The escrow should be refunded minus the ledger fees"
+    );
+    assert!(block_types(pic, controller, collection_canister_id)
+        .iter()
+        .any(|b| b == "offer_expire"));
+}
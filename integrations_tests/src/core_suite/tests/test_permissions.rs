@@ -0,0 +1,504 @@
+use crate::client::core_nft::{
+    delete_role, get_roles, get_user_permissions, grant_permission, grant_role, has_permission,
+    icrc3_get_blocks, list_permission_holders, revoke_permission, revoke_role, set_role,
+    update_collection_metadata,
+};
+use crate::core_suite::setup::default_test_setup;
+use crate::core_suite::setup::setup::TestEnv;
+use crate::utils::random_principal;
+use candid::{Nat, Principal};
+use core_nft::types::management::{
+    delete_role, get_user_permissions, grant_permission, grant_role, has_permission,
+    list_permission_holders, revoke_permission, revoke_role, set_role, update_collection_metadata,
+};
+use core_nft::types::permissions::{Grant, GrantTarget, Permission};
+use core_nft::types::royalties::account_to_value;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const GRANT_DURATION: Duration = Duration::from_secs(3600);
+
+fn holder_grants(
+    pic: &pocket_ic::PocketIc,
+    controller: Principal,
+    collection_canister_id: Principal,
+    principal: Principal,
+) -> Vec<Grant> {
+    list_permission_holders(
+        pic,
+        controller,
+        collection_canister_id,
+        &list_permission_holders::Args {
+            prev: None,
+            take: None,
+        },
+    )
+    .into_iter()
+    .find(|holder| holder.principal == principal)
+    .map(|holder| holder.grants)
+    .unwrap_or_default()
+}
+
+// The `tx` maps of every logged block of type `btype`.
+fn logged_txs(
+    pic: &pocket_ic::PocketIc,
+    controller: Principal,
+    collection_canister_id: Principal,
+    btype: &str,
+) -> Vec<BTreeMap<String, ICRC3Value>> {
+    let blocks = icrc3_get_blocks(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![GetBlocksRequest {
+            start: Nat::from(0u64),
+            length: Nat::from(100u64),
+        }],
+    );
+    blocks
+        .blocks
+        .into_iter()
+        .filter_map(|block| match block.block {
+            ICRC3Value::Map(map) => match (map.get("btype"), map.get("tx")) {
+                (Some(ICRC3Value::Text(block_type)), Some(ICRC3Value::Map(tx)))
+                    if block_type == btype =>
+                {
+                    Some(tx.clone())
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn check_permission(
+    pic: &pocket_ic::PocketIc,
+    controller: Principal,
+    collection_canister_id: Principal,
+    principal: Principal,
+    permission: Permission,
+) -> bool {
+    has_permission(
+        pic,
+        controller,
+        collection_canister_id,
+        &has_permission::Args {
+            principal,
+            permission,
+        },
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_grant_permission_is_idempotent() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1: _,
+        nft_owner2: _,
+    } = test_env;
+
+    let user = random_principal();
+    for _ in 0..2 {
+        let result = grant_permission(
+            pic,
+            controller,
+            collection_canister_id,
+            &grant_permission::Args {
+                principal: user,
+                permission: Permission::Minting,
+                expires_at: None,
+            },
+        );
+        assert!(result.is_ok());
+    }
+
+    assert_eq!(
+        holder_grants(pic, controller, collection_canister_id, user),
+        vec![Grant {
+            target: GrantTarget::Permission(Permission::Minting),
+            expires_at: None,
+        }]
+    );
+
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let result = grant_permission(
+        pic,
+        controller,
+        collection_canister_id,
+        &grant_permission::Args {
+            principal: user,
+            permission: Permission::Minting,
+            expires_at: Some(now),
+        },
+    );
+    assert!(matches!(
+        result,
+        Err(grant_permission::GrantPermissionError::InvalidExpiry)
+    ));
+}
+
+#[test]
+fn test_role_grant_expires() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1: _,
+        nft_owner2: _,
+    } = test_env;
+
+    let user = random_principal();
+    let result = grant_role(
+        pic,
+        controller,
+        collection_canister_id,
+        &grant_role::Args {
+            principal: user,
+            role: "minter".to_string(),
+            expires_at: None,
+        },
+    );
+    assert!(matches!(
+        result,
+        Err(grant_role::GrantRoleError::RoleNotFound)
+    ));
+
+    let result = set_role(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_role::Args {
+            name: "minter".to_string(),
+            permissions: vec![
+                Permission::Minting,
+                Permission::Burning,
+                Permission::Minting,
+            ],
+        },
+    );
+    assert!(result.is_ok());
+    assert_eq!(
+        get_roles(pic, controller, collection_canister_id, &()),
+        vec![(
+            "minter".to_string(),
+            vec![Permission::Minting, Permission::Burning]
+        )]
+    );
+
+    let expires_at = pic.get_time().as_nanos_since_unix_epoch() + GRANT_DURATION.as_nanos() as u64;
+    let result = grant_role(
+        pic,
+        controller,
+        collection_canister_id,
+        &grant_role::Args {
+            principal: user,
+            role: "minter".to_string(),
+            expires_at: Some(expires_at),
+        },
+    );
+    assert!(result.is_ok());
+    assert!(check_permission(
+        pic,
+        controller,
+        collection_canister_id,
+        user,
+        Permission::Burning
+    ));
+    assert!(!check_permission(
+        pic,
+        controller,
+        collection_canister_id,
+        user,
+        Permission::Pause
+    ));
+
+    pic.advance_time(GRANT_DURATION + Duration::from_secs(1));
+    pic.tick();
+
+    assert!(!check_permission(
+        pic,
+        controller,
+        collection_canister_id,
+        user,
+        Permission::Burning
+    ));
+
+    // Let the sweep job run.
+    pic.advance_time(Duration::from_secs(60));
+    pic.tick();
+
+    assert!(holder_grants(pic, controller, collection_canister_id, user).is_empty());
+    assert!(matches!(
+        get_user_permissions(
+            pic,
+            controller,
+            collection_canister_id,
+            &get_user_permissions::Args { principal: user },
+        ),
+        Err(get_user_permissions::GetUserPermissionsError::UserNotFound)
+    ));
+
+    let result = revoke_role(
+        pic,
+        controller,
+        collection_canister_id,
+        &revoke_role::Args {
+            principal: user,
+            role: "minter".to_string(),
+        },
+    );
+    assert!(result.is_ok(), "Revoking a missing grant should succeed");
+}
+
+#[test]
+fn test_delete_role_revokes_grants() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1: _,
+        nft_owner2: _,
+    } = test_env;
+
+    let user = random_principal();
+    set_role(
+        pic,
+        controller,
+        collection_canister_id,
+        &set_role::Args {
+            name: "pauser".to_string(),
+            permissions: vec![Permission::Pause],
+        },
+    )
+    .unwrap();
+    grant_role(
+        pic,
+        controller,
+        collection_canister_id,
+        &grant_role::Args {
+            principal: user,
+            role: "pauser".to_string(),
+            expires_at: None,
+        },
+    )
+    .unwrap();
+    assert!(check_permission(
+        pic,
+        controller,
+        collection_canister_id,
+        user,
+        Permission::Pause
+    ));
+
+    let result = delete_role(
+        pic,
+        controller,
+        collection_canister_id,
+        &"pauser".to_string(),
+    );
+    assert!(result.is_ok());
+    assert!(!check_permission(
+        pic,
+        controller,
+        collection_canister_id,
+        user,
+        Permission::Pause
+    ));
+    assert!(holder_grants(pic, controller, collection_canister_id, user).is_empty());
+
+    let result = delete_role(
+        pic,
+        controller,
+        collection_canister_id,
+        &"pauser".to_string(),
+    );
+    assert!(matches!(
+        result,
+        Err(delete_role::DeleteRoleError::RoleNotFound)
+    ));
+}
+
+#[test]
+fn test_list_permission_holders_pagination() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1: _,
+        nft_owner2: _,
+    } = test_env;
+
+    for _ in 0..4 {
+        grant_permission(
+            pic,
+            controller,
+            collection_canister_id,
+            &grant_permission::Args {
+                principal: random_principal(),
+                permission: Permission::UpdateMetadata,
+                expires_at: None,
+            },
+        )
+        .unwrap();
+    }
+
+    let all = list_permission_holders(
+        pic,
+        controller,
+        collection_canister_id,
+        &list_permission_holders::Args {
+            prev: None,
+            take: None,
+        },
+    );
+    assert!(all.len() >= 5, "Controller and the four grantees");
+    assert!(all.iter().any(|holder| holder.principal == controller));
+
+    let mut paged = vec![];
+    let mut prev = None;
+    loop {
+        let page = list_permission_holders(
+            pic,
+            controller,
+            collection_canister_id,
+            &list_permission_holders::Args {
+                prev,
+                take: Some(Nat::from(2u64)),
+            },
+        );
+        if page.is_empty() {
+            break;
+        }
+        assert!(page.len() <= 2);
+        prev = page.last().map(|holder| holder.principal);
+        paged.extend(page);
+    }
+    assert_eq!(paged, all);
+}
+
+#[test]
+fn test_permission_changes_are_logged() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1: _,
+        nft_owner2: _,
+    } = test_env;
+
+    let user = random_principal();
+    let expires_at = pic.get_time().as_nanos_since_unix_epoch() + GRANT_DURATION.as_nanos() as u64;
+    for expires_at in [None, None, Some(expires_at)] {
+        grant_permission(
+            pic,
+            controller,
+            collection_canister_id,
+            &grant_permission::Args {
+                principal: user,
+                permission: Permission::Burning,
+                expires_at,
+            },
+        )
+        .unwrap();
+    }
+    for _ in 0..2 {
+        revoke_permission(
+            pic,
+            controller,
+            collection_canister_id,
+            &revoke_permission::Args {
+                principal: user,
+                permission: Permission::Burning,
+            },
+        )
+        .unwrap();
+    }
+
+    let perm = |expires_at: Option<u64>| {
+        let mut value = BTreeMap::new();
+        value.insert("perm".to_string(), ICRC3Value::Text("Burning".to_string()));
+        if let Some(expires_at) = expires_at {
+            value.insert("exp".to_string(), ICRC3Value::Nat(Nat::from(expires_at)));
+        }
+        ICRC3Value::Map(value)
+    };
+
+    let grants = logged_txs(pic, controller, collection_canister_id, "perm_grant");
+    assert_eq!(grants.len(), 2, "The repeated grant should not be logged");
+    assert_eq!(
+        grants[0].get("to"),
+        Some(&account_to_value(&Account::from(user)))
+    );
+    assert_eq!(grants[0].get("before"), None);
+    assert_eq!(grants[0].get("after"), Some(&perm(None)));
+    assert_eq!(grants[1].get("before"), Some(&perm(None)));
+    assert_eq!(grants[1].get("after"), Some(&perm(Some(expires_at))));
+
+    let revokes = logged_txs(pic, controller, collection_canister_id, "perm_revoke");
+    assert_eq!(revokes.len(), 1, "The repeated revoke should not be logged");
+    assert_eq!(revokes[0].get("before"), Some(&perm(Some(expires_at))));
+    assert_eq!(revokes[0].get("after"), None);
+}
+
+#[test]
+fn test_collection_update_is_logged() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1: _,
+        nft_owner2: _,
+    } = test_env;
+
+    let args = update_collection_metadata::Args {
+        description: None,
+        symbol: Some("AUDIT".to_string()),
+        name: None,
+        logo: None,
+        supply_cap: Some(Nat::from(500u64)),
+        max_query_batch_size: None,
+        max_update_batch_size: None,
+        max_take_value: None,
+        default_take_value: None,
+        max_memo_size: None,
+        atomic_batch_transfers: None,
+        tx_window: None,
+        permitted_drift: None,
+        max_canister_storage_threshold: None,
+        collection_metadata: None,
+        metadata_validation: None,
+    };
+    for _ in 0..2 {
+        update_collection_metadata(pic, controller, collection_canister_id, &args).unwrap();
+    }
+
+    let updates = logged_txs(pic, controller, collection_canister_id, "coll_update");
+    assert_eq!(updates.len(), 1, "The repeated update should not be logged");
+
+    let mut after = BTreeMap::new();
+    after.insert("symbol".to_string(), ICRC3Value::Text("AUDIT".to_string()));
+    after.insert("supply_cap".to_string(), ICRC3Value::Nat(Nat::from(500u64)));
+    assert_eq!(updates[0].get("after"), Some(&ICRC3Value::Map(after)));
+
+    match updates[0].get("before") {
+        Some(ICRC3Value::Map(before)) => {
+            assert!(
+                matches!(before.get("symbol"), Some(ICRC3Value::Text(symbol)) if symbol != "AUDIT")
+            );
+        }
+        other => panic!("Unexpected before value: {:?}", other),
+    }
+}
@@ -0,0 +1,408 @@
+use crate::client::core_nft::{
+    get_public_mint_config, get_public_mint_purchases, icrc7_owner_of, icrc7_total_supply,
+    pause_collection, public_mint, set_allowlist_root, set_public_mint_config,
+};
+use crate::client::ledger::icrc1_balance_of;
+use crate::core_suite::setup::default_test_setup;
+use crate::core_suite::setup::setup::TestEnv;
+use crate::core_suite::setup::setup_ledger::{setup_ledger_canister, LEDGER_FEE};
+use crate::utils::{approve_spender, random_principal};
+use candid::Nat;
+use core_nft::types::public_mint::public_mint::{self, PublicMintError};
+use core_nft::types::public_mint::set_public_mint_config::SetPublicMintConfigError;
+use core_nft::types::public_mint::{
+    allowlist_leaf, allowlist_node, MintPhase, MintPhaseKind, PublicMintConfig,
+};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
+use serde_bytes::ByteBuf;
+use std::time::Duration;
+
+const INITIAL_BALANCE: u64 = 1_000_000_000;
+const PRICE: u64 = 1_000_000;
+
+fn mint_args(quantity: u64) -> public_mint::Args {
+    public_mint::Args {
+        quantity: Nat::from(quantity),
+        proof: None,
+    }
+}
+
+#[test]
+fn test_public_mint() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let buyer1 = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let buyer2 = Account {
+        owner: nft_owner2,
+        subaccount: None,
+    };
+    let treasury = Account {
+        owner: controller,
+        subaccount: None,
+    };
+
+    let ledger = setup_ledger_canister(
+        pic,
+        controller,
+        vec![
+            (buyer1, Nat::from(INITIAL_BALANCE)),
+            (buyer2, Nat::from(INITIAL_BALANCE)),
+        ],
+    );
+
+    let mint_response = public_mint(pic, nft_owner1, collection_canister_id, &mint_args(1));
+    assert!(
+        matches!(mint_response, Err(PublicMintError::PublicMintClosed)),
+        "Public mint should be closed until configured"
+    );
+
+    let config = PublicMintConfig {
+        ledger,
+        price: Nat::from(PRICE),
+        treasury,
+        max_per_account: Some(Nat::from(3u64)),
+        metadata: vec![],
+        phases: vec![],
+    };
+    let config_response = set_public_mint_config(
+        pic,
+        controller,
+        collection_canister_id,
+        &Some(config.clone()),
+    );
+    assert!(config_response.is_ok(), "Should set the public mint config");
+    assert_eq!(
+        get_public_mint_config(pic, controller, collection_canister_id, &()),
+        Some(config.clone())
+    );
+
+    let mint_response = public_mint(pic, nft_owner1, collection_canister_id, &mint_args(2));
+    assert!(
+        matches!(
+            mint_response,
+            Err(PublicMintError::PaymentFailed(
+                TransferFromError::InsufficientAllowance { .. }
+            ))
+        ),
+        "Public mint should fail without an allowance"
+    );
+    assert_eq!(
+        get_public_mint_purchases(pic, controller, collection_canister_id, &nft_owner1),
+        Nat::from(0u64),
+        "A failed payment should not count towards the account limit"
+    );
+
+    approve_spender(pic, ledger, nft_owner1, collection_canister_id, 10 * PRICE);
+
+    let token_ids = public_mint(pic, nft_owner1, collection_canister_id, &mint_args(2))
+        .expect("Public mint should succeed");
+    assert_eq!(token_ids.len(), 2);
+
+    let owners = icrc7_owner_of(pic, controller, collection_canister_id, &token_ids);
+    assert!(owners.iter().all(|owner| *owner == Some(buyer1)));
+
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &buyer1),
+        Nat::from(INITIAL_BALANCE - LEDGER_FEE - 2 * PRICE - LEDGER_FEE)
+    );
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &treasury),
+        Nat::from(2 * PRICE - LEDGER_FEE),
+        "Proceeds should be forwarded to the treasury"
+    );
+
+    let mint_response = public_mint(pic, nft_owner1, collection_canister_id, &mint_args(2));
+    assert!(
+        matches!(
+            mint_response,
+            Err(PublicMintError::ExceedAccountLimit { ref remaining }) if *remaining == Nat::from(1u64)
+        ),
+        "Purchases should be capped per account"
+    );
+    assert_eq!(
+        get_public_mint_purchases(pic, controller, collection_canister_id, &nft_owner1),
+        Nat::from(2u64)
+    );
+
+    // default_test_setup caps the supply at 10 tokens
+    let config_response = set_public_mint_config(
+        pic,
+        controller,
+        collection_canister_id,
+        &Some(PublicMintConfig {
+            max_per_account: None,
+            ..config.clone()
+        }),
+    );
+    assert!(config_response.is_ok());
+
+    approve_spender(pic, ledger, nft_owner2, collection_canister_id, 10 * PRICE);
+
+    let mint_response = public_mint(pic, nft_owner2, collection_canister_id, &mint_args(9));
+    assert!(
+        matches!(
+            mint_response,
+            Err(PublicMintError::ExceedMaxAllowedSupplyCap)
+        ),
+        "Public mint should respect the supply cap"
+    );
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &buyer2),
+        Nat::from(INITIAL_BALANCE - LEDGER_FEE),
+        "Nothing should be charged when the supply cap is exceeded"
+    );
+
+    let token_ids = public_mint(pic, nft_owner2, collection_canister_id, &mint_args(8))
+        .expect("Public mint up to the supply cap should succeed");
+    assert_eq!(token_ids.len(), 8);
+    assert_eq!(
+        icrc7_total_supply(pic, controller, collection_canister_id, &()),
+        Nat::from(10u64)
+    );
+
+    let pause_response = pause_collection(pic, controller, collection_canister_id, &());
+    assert!(pause_response.is_ok());
+
+    let mint_response = public_mint(pic, nft_owner1, collection_canister_id, &mint_args(1));
+    assert!(
+        matches!(mint_response, Err(PublicMintError::CollectionPaused)),
+        "Public mint should be rejected while paused"
+    );
+
+    let config_response = set_public_mint_config(pic, controller, collection_canister_id, &None);
+    assert!(config_response.is_ok());
+
+    let mint_response = public_mint(pic, nft_owner1, collection_canister_id, &mint_args(1));
+    assert!(matches!(
+        mint_response,
+        Err(PublicMintError::PublicMintClosed)
+    ));
+}
+
+#[test]
+fn test_public_mint_phases() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let ledger = setup_ledger_canister(
+        pic,
+        controller,
+        vec![
+            (
+                Account {
+                    owner: nft_owner1,
+                    subaccount: None,
+                },
+                Nat::from(INITIAL_BALANCE),
+            ),
+            (
+                Account {
+                    owner: nft_owner2,
+                    subaccount: None,
+                },
+                Nat::from(INITIAL_BALANCE),
+            ),
+        ],
+    );
+    approve_spender(pic, ledger, nft_owner1, collection_canister_id, 10 * PRICE);
+    approve_spender(pic, ledger, nft_owner2, collection_canister_id, 10 * PRICE);
+
+    // Two-leaf tree: nft_owner1 and another allowlisted principal
+    let other_leaf = allowlist_leaf(&random_principal());
+    let root = allowlist_node(&allowlist_leaf(&nft_owner1), &other_leaf);
+    let proof = vec![ByteBuf::from(other_leaf.to_vec())];
+
+    let root_response = set_allowlist_root(
+        pic,
+        controller,
+        collection_canister_id,
+        &Some(ByteBuf::from(root.to_vec())),
+    );
+    assert!(root_response.is_ok(), "Should set the allowlist root");
+
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let hour = Duration::from_secs(3600).as_nanos() as u64;
+    let allowlist_phase = MintPhase {
+        name: "allowlist".to_string(),
+        kind: MintPhaseKind::Allowlist,
+        start: now,
+        end: now + hour,
+        price: Nat::from(PRICE / 2),
+        max_per_account: Some(Nat::from(1u64)),
+    };
+    let public_phase = MintPhase {
+        name: "public".to_string(),
+        kind: MintPhaseKind::Public,
+        start: now + hour,
+        end: now + 2 * hour,
+        price: Nat::from(PRICE),
+        max_per_account: Some(Nat::from(2u64)),
+    };
+    let config = PublicMintConfig {
+        ledger,
+        price: Nat::from(PRICE),
+        treasury: Account {
+            owner: controller,
+            subaccount: None,
+        },
+        max_per_account: None,
+        metadata: vec![],
+        phases: vec![
+            allowlist_phase.clone(),
+            MintPhase {
+                start: now + hour / 2,
+                ..public_phase.clone()
+            },
+        ],
+    };
+
+    let config_response = set_public_mint_config(
+        pic,
+        controller,
+        collection_canister_id,
+        &Some(config.clone()),
+    );
+    assert!(
+        matches!(
+            config_response,
+            Err(SetPublicMintConfigError::InvalidPhases(_))
+        ),
+        "Overlapping phases should be rejected"
+    );
+
+    let config = PublicMintConfig {
+        phases: vec![allowlist_phase, public_phase],
+        ..config
+    };
+    let config_response =
+        set_public_mint_config(pic, controller, collection_canister_id, &Some(config));
+    assert!(config_response.is_ok(), "Should set the mint phases");
+
+    let mint_response = public_mint(pic, nft_owner2, collection_canister_id, &mint_args(1));
+    assert!(
+        matches!(mint_response, Err(PublicMintError::NotAllowlisted)),
+        "Callers without a proof should be rejected during the allowlist phase"
+    );
+
+    let mint_response = public_mint(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &public_mint::Args {
+            quantity: Nat::from(1u64),
+            proof: Some(proof.clone()),
+        },
+    );
+    assert!(
+        matches!(mint_response, Err(PublicMintError::NotAllowlisted)),
+        "A proof for another principal should be rejected"
+    );
+
+    let allowlist_args = public_mint::Args {
+        quantity: Nat::from(1u64),
+        proof: Some(proof),
+    };
+    let token_ids = public_mint(pic, nft_owner1, collection_canister_id, &allowlist_args)
+        .expect("Allowlisted mint should succeed");
+    assert_eq!(token_ids.len(), 1);
+    assert_eq!(
+        icrc1_balance_of(
+            pic,
+            controller,
+            ledger,
+            &Account {
+                owner: nft_owner1,
+                subaccount: None,
+            }
+        ),
+        Nat::from(INITIAL_BALANCE - LEDGER_FEE - PRICE / 2 - LEDGER_FEE),
+        "The allowlist phase price should apply"
+    );
+
+    let mint_response = public_mint(pic, nft_owner1, collection_canister_id, &allowlist_args);
+    assert!(
+        matches!(
+            mint_response,
+            Err(PublicMintError::ExceedAccountLimit { ref remaining }) if *remaining == Nat::from(0u64)
+        ),
+        "The allowlist phase cap should apply"
+    );
+
+    pic.advance_time(Duration::from_secs(3600));
+    pic.tick();
+
+    let token_ids = public_mint(pic, nft_owner2, collection_canister_id, &mint_args(2))
+        .expect("Public phase mint should not need a proof");
+    assert_eq!(token_ids.len(), 2);
+
+    let token_ids = public_mint(pic, nft_owner1, collection_canister_id, &mint_args(2))
+        .expect("Public phase caps should be tracked separately from the allowlist phase");
+    assert_eq!(token_ids.len(), 2);
+
+    pic.advance_time(Duration::from_secs(3600));
+    pic.tick();
+
+    let mint_response = public_mint(pic, nft_owner2, collection_canister_id, &mint_args(1));
+    assert!(
+        matches!(mint_response, Err(PublicMintError::PublicMintClosed)),
+        "Minting should be closed after the last phase"
+    );
+}
+
+#[test]
+fn test_public_mint_config_rejects_reserved_keys() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1: _,
+        nft_owner2: _,
+    } = test_env;
+
+    let config = PublicMintConfig {
+        ledger: random_principal(),
+        price: Nat::from(PRICE),
+        treasury: Account {
+            owner: controller,
+            subaccount: None,
+        },
+        max_per_account: None,
+        metadata: vec![(
+            "icrc7:transferable".to_string(),
+            ICRC3Value::Text("false".to_string()),
+        )],
+        phases: vec![],
+    };
+    let config_response =
+        set_public_mint_config(pic, controller, collection_canister_id, &Some(config));
+    assert!(
+        matches!(
+            &config_response,
+            Err(SetPublicMintConfigError::ReservedMetadataKey(key)) if key == "icrc7:transferable"
+        ),
+        "Publicly minted tokens should not carry reserved keys"
+    );
+    assert_eq!(
+        get_public_mint_config(pic, controller, collection_canister_id, &()),
+        None
+    );
+}
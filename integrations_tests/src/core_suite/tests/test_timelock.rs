@@ -0,0 +1,262 @@
+use crate::client::core_nft::{
+    cancel_operation, get_timelock_config, icrc3_get_blocks, icrc7_supply_cap,
+    icrc7_token_metadata, pending_operations, reveal, schedule_operation,
+    update_collection_metadata, update_nft_metadata, update_nft_metadata_patch,
+};
+use crate::core_suite::setup::default_test_setup;
+use crate::core_suite::setup::setup::TestEnv;
+use crate::utils::{create_default_metadata, mint_nft};
+use candid::Nat;
+use core_nft::types::management::update_collection_metadata::UpdateCollectionMetadataError;
+use core_nft::types::management::update_nft_metadata::UpdateNftMetadataError;
+use core_nft::types::management::{
+    update_collection_metadata, update_nft_metadata, update_nft_metadata_patch,
+};
+use core_nft::types::reveal::reveal::{self, RevealError};
+use core_nft::types::timelock::cancel_operation::CancelOperationError;
+use core_nft::types::timelock::schedule_operation::ScheduleOperationError;
+use core_nft::types::timelock::{pending_operations, TimelockConfig, TimelockedOperation};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
+use std::time::Duration;
+
+const DELAY: Duration = Duration::from_secs(3600);
+
+fn all_pending(
+    pic: &pocket_ic::PocketIc,
+    controller: candid::Principal,
+    collection_canister_id: candid::Principal,
+) -> pending_operations::Response {
+    pending_operations(
+        pic,
+        controller,
+        collection_canister_id,
+        &pending_operations::Args {
+            prev: None,
+            take: None,
+        },
+    )
+}
+
+#[test]
+fn test_timelocked_supply_cap_runs_after_delay() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2: _,
+    } = test_env;
+
+    let config = TimelockConfig {
+        delay: DELAY.as_nanos() as u64,
+        guardians: vec![nft_owner1],
+    };
+    let response = schedule_operation(
+        pic,
+        controller,
+        collection_canister_id,
+        &TimelockedOperation::SetTimelockConfig(Some(TimelockConfig {
+            guardians: vec![],
+            ..config.clone()
+        })),
+    );
+    assert!(matches!(
+        response,
+        Err(ScheduleOperationError::InvalidOperation(_))
+    ));
+    schedule_operation(
+        pic,
+        controller,
+        collection_canister_id,
+        &TimelockedOperation::SetTimelockConfig(Some(config.clone())),
+    )
+    .expect("Timelock should be set right away");
+    assert_eq!(
+        get_timelock_config(pic, controller, collection_canister_id, &()),
+        Some(config)
+    );
+
+    let response = update_collection_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &update_collection_metadata::Args {
+            supply_cap: Some(Nat::from(500u64)),
+            ..Default::default()
+        },
+    );
+    assert!(matches!(
+        response,
+        Err(UpdateCollectionMetadataError::TimelockRequired)
+    ));
+
+    let supply_cap_before = icrc7_supply_cap(pic, controller, collection_canister_id, &());
+    let operation_id = schedule_operation(
+        pic,
+        controller,
+        collection_canister_id,
+        &TimelockedOperation::SetSupplyCap(Nat::from(500u64)),
+    )
+    .expect("Supply cap change should be queued");
+
+    let pending = all_pending(pic, controller, collection_canister_id);
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].operation_id, operation_id);
+    assert_eq!(pending[0].scheduled_by, controller);
+    assert_eq!(
+        icrc7_supply_cap(pic, controller, collection_canister_id, &()),
+        supply_cap_before
+    );
+
+    pic.advance_time(DELAY + Duration::from_secs(60));
+    pic.tick();
+
+    assert_eq!(
+        icrc7_supply_cap(pic, controller, collection_canister_id, &()),
+        Some(Nat::from(500u64))
+    );
+    assert!(all_pending(pic, controller, collection_canister_id).is_empty());
+
+    let blocks = icrc3_get_blocks(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![GetBlocksRequest {
+            start: Nat::from(0u64),
+            length: Nat::from(100u64),
+        }],
+    );
+    let btypes: Vec<String> = blocks
+        .blocks
+        .iter()
+        .filter_map(|block| match &block.block {
+            ICRC3Value::Map(map) => match map.get("btype") {
+                Some(ICRC3Value::Text(btype)) if btype.starts_with("op_") => Some(btype.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        btypes,
+        vec!["op_schedule", "op_execute", "op_schedule", "op_execute"]
+    );
+}
+
+#[test]
+fn test_guardian_cancels_operation() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let token_id = mint_nft(
+        pic,
+        Account {
+            owner: nft_owner2,
+            subaccount: None,
+        },
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+    let metadata_before = icrc7_token_metadata(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+
+    schedule_operation(
+        pic,
+        controller,
+        collection_canister_id,
+        &TimelockedOperation::SetTimelockConfig(Some(TimelockConfig {
+            delay: DELAY.as_nanos() as u64,
+            guardians: vec![nft_owner1],
+        })),
+    )
+    .unwrap();
+
+    let args = update_nft_metadata::Args {
+        token_id: token_id.clone(),
+        metadata: vec![("name".to_string(), ICRC3Value::Text("Replaced".to_string()))],
+    };
+    let response = update_nft_metadata(pic, controller, collection_canister_id, &args);
+    assert!(matches!(
+        response,
+        Err(UpdateNftMetadataError::TimelockRequired)
+    ));
+    let response = update_nft_metadata_patch(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![update_nft_metadata_patch::MetadataPatch {
+            token_id: token_id.clone(),
+            set: args.metadata.clone(),
+            unset: vec![],
+        }],
+    );
+    assert!(
+        matches!(
+            response[0],
+            Some(Err(UpdateNftMetadataError::TimelockRequired))
+        ),
+        "Patches should not bypass the timelock"
+    );
+    let response = reveal(
+        pic,
+        controller,
+        collection_canister_id,
+        &reveal::Args {
+            salt: serde_bytes::ByteBuf::new(),
+            tokens: vec![],
+        },
+    );
+    assert!(matches!(response, Err(RevealError::TimelockRequired)));
+
+    let response = schedule_operation(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &TimelockedOperation::ReplaceTokenMetadata(args.clone()),
+    );
+    assert!(matches!(
+        response,
+        Err(ScheduleOperationError::Unauthorized)
+    ));
+
+    let operation_id = schedule_operation(
+        pic,
+        controller,
+        collection_canister_id,
+        &TimelockedOperation::ReplaceTokenMetadata(args),
+    )
+    .expect("Metadata replacement should be queued");
+
+    let response = cancel_operation(pic, controller, collection_canister_id, &operation_id);
+    assert!(matches!(response, Err(CancelOperationError::NotGuardian)));
+    assert!(cancel_operation(pic, nft_owner1, collection_canister_id, &operation_id).is_ok());
+    let response = cancel_operation(pic, nft_owner1, collection_canister_id, &operation_id);
+    assert!(matches!(
+        response,
+        Err(CancelOperationError::OperationNotFound)
+    ));
+    assert!(all_pending(pic, controller, collection_canister_id).is_empty());
+
+    pic.advance_time(DELAY + Duration::from_secs(60));
+    pic.tick();
+
+    assert_eq!(
+        icrc7_token_metadata(pic, controller, collection_canister_id, &vec![token_id]),
+        metadata_before
+    );
+}
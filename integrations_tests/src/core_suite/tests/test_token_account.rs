@@ -0,0 +1,138 @@
+use crate::client::core_nft::{
+    get_token_account, icrc7_transfer, set_token_account_ledgers, token_account_transfer,
+};
+use crate::client::ledger::icrc1_balance_of;
+use crate::core_suite::setup::default_test_setup;
+use crate::core_suite::setup::setup::TestEnv;
+use crate::core_suite::setup::setup_ledger::{setup_ledger_canister, LEDGER_FEE};
+use crate::utils::{create_default_metadata, mint_nft};
+use candid::Nat;
+use core_nft::types::icrc7;
+use core_nft::types::nft::token_subaccount;
+use core_nft::types::token_account::token_account_transfer::{self, TokenAccountTransferError};
+use icrc_ledger_types::icrc1::account::Account;
+
+const REWARDS: u64 = 1_000_000_000;
+const WITHDRAWAL: u64 = 400_000_000;
+
+#[test]
+fn test_token_account_follows_token() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let owner1 = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let owner2 = Account {
+        owner: nft_owner2,
+        subaccount: None,
+    };
+
+    let token_id = mint_nft(
+        pic,
+        owner1,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let token_account = get_token_account(pic, controller, collection_canister_id, &token_id)
+        .expect("Minted token should have an account");
+    assert_eq!(
+        token_account,
+        Account {
+            owner: collection_canister_id,
+            subaccount: Some(token_subaccount(&token_id)),
+        }
+    );
+    assert!(
+        get_token_account(pic, controller, collection_canister_id, &Nat::from(999u64)).is_none()
+    );
+
+    let ledger = setup_ledger_canister(pic, controller, vec![(token_account, Nat::from(REWARDS))]);
+
+    let withdraw_args = token_account_transfer::Args {
+        token_id: token_id.clone(),
+        from_subaccount: None,
+        ledger,
+        to: owner1,
+        amount: Nat::from(WITHDRAWAL),
+        memo: None,
+        created_at_time: None,
+    };
+
+    let response = token_account_transfer(pic, nft_owner1, collection_canister_id, &withdraw_args);
+    assert!(
+        matches!(response, Err(TokenAccountTransferError::UnsupportedLedger)),
+        "Withdrawals should require a configured ledger"
+    );
+
+    let config_response =
+        set_token_account_ledgers(pic, controller, collection_canister_id, &vec![ledger]);
+    assert!(config_response.is_ok());
+
+    let response = token_account_transfer(pic, nft_owner2, collection_canister_id, &withdraw_args);
+    assert!(matches!(
+        response,
+        Err(TokenAccountTransferError::NotTokenOwner)
+    ));
+
+    let response = token_account_transfer(pic, nft_owner1, collection_canister_id, &withdraw_args);
+    assert!(response.is_ok(), "Owner should withdraw: {:?}", response);
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &owner1),
+        Nat::from(WITHDRAWAL)
+    );
+
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let transfer_response = icrc7_transfer(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![icrc7::TransferArg {
+            to: owner2,
+            token_id: token_id.clone(),
+            memo: None,
+            from_subaccount: None,
+            created_at_time: Some(now),
+        }],
+    );
+    assert!(transfer_response[0].as_ref().is_some_and(|r| r.is_ok()));
+
+    let response = token_account_transfer(pic, nft_owner1, collection_canister_id, &withdraw_args);
+    assert!(
+        matches!(response, Err(TokenAccountTransferError::NotTokenOwner)),
+        "The previous owner should lose access to the token account"
+    );
+
+    let response = token_account_transfer(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &token_account_transfer::Args {
+            to: owner2,
+            ..withdraw_args
+        },
+    );
+    assert!(
+        response.is_ok(),
+        "New owner should withdraw: {:?}",
+        response
+    );
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &owner2),
+        Nat::from(WITHDRAWAL)
+    );
+    assert_eq!(
+        icrc1_balance_of(pic, controller, ledger, &token_account),
+        Nat::from(REWARDS - 2 * (WITHDRAWAL + LEDGER_FEE))
+    );
+}
@@ -0,0 +1,307 @@
+use crate::client::core_nft::{
+    icrc37_approve_tokens, icrc3_get_blocks, icrc7_transfer, icrc7_user_of, set_user,
+};
+use crate::core_suite::setup::default_test_setup;
+use crate::core_suite::setup::setup::TestEnv;
+use crate::utils::{create_default_metadata, mint_nft, random_principal};
+use candid::Nat;
+use core_nft::types::icrc37;
+use core_nft::types::icrc7;
+use core_nft::types::user::set_user::{self, SetUserError};
+use core_nft::types::user::UserRole;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
+use std::time::Duration;
+
+const RENTAL_DURATION: Duration = Duration::from_secs(3600);
+
+fn user_args(token_id: &Nat, user: Option<Account>, expires_at: u64) -> set_user::Args {
+    set_user::Args {
+        token_id: token_id.clone(),
+        spender_subaccount: None,
+        user,
+        expires_at,
+    }
+}
+
+#[test]
+fn test_set_user_and_expiry() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let owner = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let renter = Account {
+        owner: random_principal(),
+        subaccount: None,
+    };
+
+    let token_id = mint_nft(
+        pic,
+        owner,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let expires_at = now + RENTAL_DURATION.as_nanos() as u64;
+
+    let response = set_user(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &user_args(&token_id, Some(renter), expires_at),
+    );
+    assert!(
+        matches!(response, Err(SetUserError::Unauthorized)),
+        "Only the owner or an approved spender should set the user"
+    );
+
+    let response = set_user(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &user_args(&token_id, Some(renter), now),
+    );
+    assert!(matches!(response, Err(SetUserError::InvalidExpiry)));
+
+    let response = set_user(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &user_args(&token_id, Some(renter), expires_at),
+    );
+    assert!(
+        response.is_ok(),
+        "Owner should set the user: {:?}",
+        response
+    );
+
+    let users = icrc7_user_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert_eq!(
+        users,
+        vec![Some(UserRole {
+            user: renter,
+            expires_at,
+        })]
+    );
+
+    let blocks = icrc3_get_blocks(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![GetBlocksRequest {
+            start: Nat::from(0u64),
+            length: Nat::from(100u64),
+        }],
+    );
+    let has_set_user_block = blocks.blocks.iter().any(|block| match &block.block {
+        ICRC3Value::Map(map) => {
+            matches!(map.get("btype"), Some(ICRC3Value::Text(btype)) if btype == "set_user")
+        }
+        _ => false,
+    });
+    assert!(has_set_user_block, "set_user should log a block");
+
+    pic.advance_time(RENTAL_DURATION + Duration::from_secs(1));
+    pic.tick();
+
+    let users = icrc7_user_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert_eq!(users, vec![None], "The user should expire");
+}
+
+#[test]
+fn test_user_set_by_spender_and_cleared_on_transfer() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let owner = Account {
+        owner: nft_owner1,
+        subaccount: None,
+    };
+    let spender = Account {
+        owner: nft_owner2,
+        subaccount: None,
+    };
+    let renter = Account {
+        owner: random_principal(),
+        subaccount: None,
+    };
+
+    let token_id = mint_nft(
+        pic,
+        owner,
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let approve_response = icrc37_approve_tokens(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![icrc37::icrc37_approve_tokens::ApproveTokenArg {
+            token_id: token_id.clone(),
+            approval_info: icrc37::ApprovalInfo {
+                spender,
+                from_subaccount: None,
+                expires_at: None,
+                memo: None,
+                created_at_time: now,
+            },
+        }],
+    );
+    assert!(approve_response.is_ok());
+
+    let expires_at = now + RENTAL_DURATION.as_nanos() as u64;
+    let response = set_user(
+        pic,
+        nft_owner2,
+        collection_canister_id,
+        &user_args(&token_id, Some(renter), expires_at),
+    );
+    assert!(
+        response.is_ok(),
+        "Approved spender should set the user: {:?}",
+        response
+    );
+
+    let users = icrc7_user_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert!(users[0].is_some());
+
+    let transfer_response = icrc7_transfer(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![icrc7::TransferArg {
+            to: spender,
+            token_id: token_id.clone(),
+            memo: None,
+            from_subaccount: None,
+            created_at_time: Some(now),
+        }],
+    );
+    assert!(transfer_response[0].as_ref().is_some_and(|r| r.is_ok()));
+
+    let users = icrc7_user_of(
+        pic,
+        controller,
+        collection_canister_id,
+        &vec![token_id.clone()],
+    );
+    assert_eq!(users, vec![None], "A transfer should clear the user");
+}
+
+#[test]
+fn test_stale_approval_cannot_set_user_after_transfer() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        collection_canister_id,
+        controller,
+        nft_owner1,
+        nft_owner2,
+    } = test_env;
+
+    let spender_principal = random_principal();
+    let spender = Account {
+        owner: spender_principal,
+        subaccount: None,
+    };
+
+    let token_id = mint_nft(
+        pic,
+        Account {
+            owner: nft_owner1,
+            subaccount: None,
+        },
+        controller,
+        collection_canister_id,
+        create_default_metadata(),
+    )
+    .expect("Failed to mint NFT");
+
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let approve_response = icrc37_approve_tokens(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![icrc37::icrc37_approve_tokens::ApproveTokenArg {
+            token_id: token_id.clone(),
+            approval_info: icrc37::ApprovalInfo {
+                spender,
+                from_subaccount: None,
+                expires_at: None,
+                memo: None,
+                created_at_time: now,
+            },
+        }],
+    );
+    assert!(approve_response.is_ok());
+
+    let transfer_response = icrc7_transfer(
+        pic,
+        nft_owner1,
+        collection_canister_id,
+        &vec![icrc7::TransferArg {
+            to: Account {
+                owner: nft_owner2,
+                subaccount: None,
+            },
+            token_id: token_id.clone(),
+            memo: None,
+            from_subaccount: None,
+            created_at_time: Some(now),
+        }],
+    );
+    assert!(transfer_response[0].as_ref().is_some_and(|r| r.is_ok()));
+
+    let expires_at = now + RENTAL_DURATION.as_nanos() as u64;
+    let response = set_user(
+        pic,
+        spender_principal,
+        collection_canister_id,
+        &user_args(&token_id, Some(spender), expires_at),
+    );
+    assert!(
+        matches!(response, Err(SetUserError::Unauthorized)),
+        "The previous owner's approval should not survive the transfer"
+    );
+
+    let users = icrc7_user_of(pic, controller, collection_canister_id, &vec![token_id]);
+    assert_eq!(users, vec![None]);
+}
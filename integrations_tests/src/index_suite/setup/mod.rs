@@ -23,6 +23,9 @@ pub fn default_test_setup() -> TestEnv {
             Permission::UpdateCollectionMetadata,
             Permission::ReadUploads,
             Permission::UpdateUploads,
+            Permission::Burning,
+            Permission::UpdateRoyalties,
+            Permission::Pause,
         ],
     );
 
@@ -54,6 +57,7 @@ pub fn default_test_setup() -> TestEnv {
         permitted_drift: None,
         max_canister_storage_threshold: None,
         collection_metadata: HashMap::new(),
+        metadata_validation: None,
         approval_init: InitApprovalsArg {
             max_approvals_per_token_or_collection: Some(Nat::from(10u64)),
             max_revoke_approvals: Some(Nat::from(10u64)),
@@ -76,6 +80,9 @@ pub fn test_setup_atomic_batch_transfers() -> TestEnv {
             Permission::UpdateCollectionMetadata,
             Permission::ReadUploads,
             Permission::UpdateUploads,
+            Permission::Burning,
+            Permission::UpdateRoyalties,
+            Permission::Pause,
         ],
     );
 
@@ -107,6 +114,7 @@ pub fn test_setup_atomic_batch_transfers() -> TestEnv {
         permitted_drift: None,
         max_canister_storage_threshold: None,
         collection_metadata: HashMap::new(),
+        metadata_validation: None,
         approval_init: InitApprovalsArg {
             max_approvals_per_token_or_collection: Some(Nat::from(10u64)),
             max_revoke_approvals: Some(Nat::from(10u64)),
@@ -129,6 +137,9 @@ pub fn test_setup_no_limit() -> TestEnv {
             Permission::UpdateCollectionMetadata,
             Permission::ReadUploads,
             Permission::UpdateUploads,
+            Permission::Burning,
+            Permission::UpdateRoyalties,
+            Permission::Pause,
         ],
     );
 
@@ -160,6 +171,7 @@ pub fn test_setup_no_limit() -> TestEnv {
         permitted_drift: None,
         max_canister_storage_threshold: None,
         collection_metadata: HashMap::new(),
+        metadata_validation: None,
         approval_init: InitApprovalsArg {
             max_approvals_per_token_or_collection: Some(Nat::from(10u64)),
             max_revoke_approvals: Some(Nat::from(10u64)),
@@ -1,4 +1,5 @@
 use crate::client::core_nft::mint;
+use crate::client::ledger::icrc2_approve;
 use crate::client::storage::{finalize_upload, init_upload, store_chunk};
 use crate::core_suite::setup::setup::MINUTE_IN_MS;
 
@@ -13,6 +14,7 @@ use ic_agent::Agent;
 use ic_http_gateway::{HttpGatewayClient, HttpGatewayRequestArgs};
 use icrc_ledger_types::icrc::generic_value::ICRC3Value;
 use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc2::approve::ApproveArgs;
 use pocket_ic::PocketIc;
 use rand::{rng, RngCore};
 use sha2::{Digest, Sha256};
@@ -48,6 +50,8 @@ pub fn mint_nft(
             token_owner: owner,
             memo: Some(serde_bytes::ByteBuf::from("memo")),
             metadata,
+            token_id: None,
+            created_at_time: None,
         }],
     };
 
@@ -59,6 +63,34 @@ pub fn mint_nft(
     return mint_call;
 }
 
+pub fn approve_spender(
+    pic: &mut PocketIc,
+    ledger: Principal,
+    owner: Principal,
+    spender: Principal,
+    amount: u64,
+) {
+    let approve_response = icrc2_approve(
+        pic,
+        owner,
+        ledger,
+        &ApproveArgs {
+            from_subaccount: None,
+            spender: Account {
+                owner: spender,
+                subaccount: None,
+            },
+            amount: Nat::from(amount),
+            expected_allowance: None,
+            expires_at: None,
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        },
+    );
+    assert!(approve_response.is_ok(), "Approval should succeed");
+}
+
 pub fn upload_file(
     pic: &mut PocketIc,
     controller: Principal,
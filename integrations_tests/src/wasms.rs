@@ -7,7 +7,7 @@ use std::path::PathBuf;
 lazy_static! {
     // Wasms in wasms folder
     // pub static ref IC_ICRC1_LEDGER: CanisterWasm = get_canister_wasm("ic_icrc1_ledger");
-    // pub static ref IC_ICRC2_LEDGER: CanisterWasm = get_canister_wasm_gz("icrc_ledger");
+    pub static ref IC_ICRC2_LEDGER: CanisterWasm = get_canister_wasm_gz("icrc_ledger");
     // pub static ref SNS_GOVERNANCE: CanisterWasm = get_canister_wasm("sns_governance");
     // pub static ref SNS_ROOT: CanisterWasm = get_canister_wasm("sns_root");
     // pub static ref ICP_LEDGER: CanisterWasm = get_canister_wasm("ledger");
@@ -6,5 +6,6 @@ ulimit -n 65536
 
 mkdir -p integrations_tests/wasm
 cp src/core_nft/wasm/core_nft_canister.wasm.gz integrations_tests/wasm/
+curl -L -o integrations_tests/wasm/icrc_ledger_canister.wasm.gz "https://github.com/dfinity/ic/releases/download/ledger-suite-icrc-2025-06-19/ic-icrc1-ledger.wasm.gz"
 
 cargo test -p integration_tests -- --test-threads=1
\ No newline at end of file
@@ -76,8 +76,12 @@ macro_rules! create_permission_guard {
     ($guard_name:ident, $permission:expr, $error_message:expr) => {
         pub fn $guard_name() -> Result<(), String> {
             let caller = ic_cdk::api::msg_caller();
-            let has_permission =
-                read_state(|state| state.data.permissions.has_permission(&caller, &$permission));
+            let has_permission = read_state(|state| {
+                state
+                    .data
+                    .permissions
+                    .has_permission(&caller, &$permission, ic_cdk::api::time())
+            });
 
             if has_permission {
                 Ok(())
@@ -123,3 +127,21 @@ create_permission_guard!(
     Permission::UpdateUploads,
     "Caller does not have update uploads permission"
 );
+
+create_permission_guard!(
+    caller_has_burning_permission,
+    Permission::Burning,
+    "Caller does not have burning permission"
+);
+
+create_permission_guard!(
+    caller_has_update_royalties_permission,
+    Permission::UpdateRoyalties,
+    "Caller does not have update royalties permission"
+);
+
+create_permission_guard!(
+    caller_has_pause_permission,
+    Permission::Pause,
+    "Caller does not have pause permission"
+);
@@ -1,5 +1,15 @@
+mod offer_expiry;
+mod permission_expiry;
+mod proposal_expiry;
+mod timelock;
 mod upload_garbage_collector;
+mod user_expiry;
 
 pub(crate) fn start() {
+    offer_expiry::start_job();
+    permission_expiry::start_job();
+    proposal_expiry::start_job();
+    timelock::start_job();
     upload_garbage_collector::start_job();
+    user_expiry::start_job();
 }
@@ -0,0 +1,13 @@
+use std::time::Duration;
+
+use bity_ic_canister_time::{run_interval, MINUTE_IN_MS};
+
+use crate::updates::offer::expire_offers;
+
+pub fn start_job() {
+    run_interval(Duration::from_millis(MINUTE_IN_MS), offer_expiry_job);
+}
+
+fn offer_expiry_job() {
+    ic_cdk::futures::spawn(expire_offers());
+}
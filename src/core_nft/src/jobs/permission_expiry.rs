@@ -0,0 +1,19 @@
+use std::time::Duration;
+
+use bity_ic_canister_time::{run_interval, MINUTE_IN_MS};
+
+use crate::state::mutate_state;
+use crate::utils::trace;
+
+pub fn start_job() {
+    run_interval(Duration::from_millis(MINUTE_IN_MS), permission_expiry_job);
+}
+
+// Expired grants are already ignored by permission checks; this only frees their entries.
+fn permission_expiry_job() {
+    let now = ic_cdk::api::time();
+    let removed = mutate_state(|state| state.data.permissions.remove_expired_grants(now));
+    if removed > 0 {
+        trace(&format!("Removed {} expired permission grants", removed));
+    }
+}
@@ -0,0 +1,9 @@
+use std::time::Duration;
+
+use bity_ic_canister_time::{run_interval, MINUTE_IN_MS};
+
+use crate::updates::timelock::execute_due_operations;
+
+pub fn start_job() {
+    run_interval(Duration::from_millis(MINUTE_IN_MS), execute_due_operations);
+}
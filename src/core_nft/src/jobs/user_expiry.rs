@@ -0,0 +1,19 @@
+use std::time::Duration;
+
+use bity_ic_canister_time::{run_interval, MINUTE_IN_MS};
+
+use crate::state::mutate_state;
+use crate::utils::trace;
+
+pub fn start_job() {
+    run_interval(Duration::from_millis(MINUTE_IN_MS), user_expiry_job);
+}
+
+// Expired users are already hidden from queries; this only frees their entries.
+fn user_expiry_job() {
+    let now = ic_cdk::api::time();
+    let pruned = mutate_state(|state| state.data.prune_expired_users(now));
+    if pruned > 0 {
+        trace(&format!("Pruned {} expired token users", pruned));
+    }
+}
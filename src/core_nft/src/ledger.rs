@@ -0,0 +1,72 @@
+use crate::types::icrc7;
+
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
+
+pub async fn icrc1_fee(ledger: Principal) -> Result<Nat, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(ledger, "icrc1_fee")
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<Nat>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn icrc1_transfer(
+    ledger: Principal,
+    args: TransferArg,
+) -> Result<Result<Nat, TransferError>, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(ledger, "icrc1_transfer")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<Result<Nat, TransferError>>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn icrc2_transfer_from(
+    ledger: Principal,
+    args: TransferFromArgs,
+) -> Result<Result<Nat, TransferFromError>, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(ledger, "icrc2_transfer_from")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<Result<Nat, TransferFromError>>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn icrc7_owner_of(
+    collection: Principal,
+    token_ids: Vec<Nat>,
+) -> Result<Vec<Option<Account>>, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(collection, "icrc7_owner_of")
+        .with_arg(token_ids)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<Vec<Option<Account>>>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn icrc7_transfer(
+    collection: Principal,
+    args: Vec<icrc7::TransferArg>,
+) -> Result<icrc7::icrc7_transfer::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(collection, "icrc7_transfer")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<icrc7::icrc7_transfer::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
@@ -4,6 +4,15 @@ pub use crate::types::icrc10;
 pub use crate::types::icrc21;
 pub use crate::types::icrc7;
 pub use crate::types::management;
+pub use crate::types::marketplace;
+pub use crate::types::multisig;
+pub use crate::types::nest;
+pub use crate::types::offer;
+pub use crate::types::public_mint;
+pub use crate::types::reveal;
+pub use crate::types::timelock;
+pub use crate::types::token_account;
+pub use crate::types::user;
 pub use bity_ic_icrc3::transaction::ICRC7Transaction;
 pub use bity_ic_storage_canister_api::updates::cancel_upload;
 pub use bity_ic_storage_canister_api::updates::finalize_upload;
@@ -12,6 +21,7 @@ pub use bity_ic_storage_canister_api::updates::store_chunk;
 
 mod guards;
 mod jobs;
+mod ledger;
 pub mod lifecycle;
 mod memory;
 pub mod queries;
@@ -6,8 +6,11 @@ use crate::lifecycle::Args;
 pub use crate::state::InitApprovalsArg;
 use crate::state::{init_icrc3, start_default_archive_job, Data, RuntimeState};
 use crate::types::http::certify_all_assets;
+use crate::types::icrc3::register_extension_block_types;
+use crate::types::metadata::MetadataValidation;
 use crate::types::permissions::{Permission, PermissionManager};
 use crate::types::value_custom::CustomValue as Value;
+use crate::utils::set_collection_metadata;
 
 use bity_ic_canister_tracing_macros::trace;
 use bity_ic_icrc3::config::{ICRC3Config, ICRC3Properties};
@@ -40,6 +43,7 @@ pub struct InitArgs {
     pub permitted_drift: Option<Nat>,
     pub max_canister_storage_threshold: Option<Nat>,
     pub collection_metadata: HashMap<String, Value>,
+    pub metadata_validation: Option<MetadataValidation>,
     pub approval_init: InitApprovalsArg,
 }
 
@@ -88,6 +92,7 @@ fn init(args: Args) {
                 init_args.max_canister_storage_threshold,
                 init_args.approval_init.clone(),
             );
+            data.metadata_validation = init_args.metadata_validation.unwrap_or_default();
 
             if env.is_test_mode() {
                 data.permissions
@@ -102,6 +107,12 @@ fn init(args: Args) {
                     .grant_permission(env.caller(), Permission::ManageAuthorities);
                 data.permissions
                     .grant_permission(env.caller(), Permission::ReadUploads);
+                data.permissions
+                    .grant_permission(env.caller(), Permission::Burning);
+                data.permissions
+                    .grant_permission(env.caller(), Permission::UpdateRoyalties);
+                data.permissions
+                    .grant_permission(env.caller(), Permission::Pause);
             }
 
             let _tx_window = match init_args.tx_window {
@@ -119,7 +130,7 @@ fn init(args: Args) {
                 ic_cdk::trap("max_approvals_per_token_or_collection cannot be 0");
             }
 
-            let icrc3_config = ICRC3Config {
+            let mut icrc3_config = ICRC3Config {
                 supported_blocks: vec![SupportedBlockType {
                     block_type: "7mint".to_string(),
                     url: "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-7/ICRC-7.md#mint-block-schema".to_string(),
@@ -170,11 +181,18 @@ fn init(args: Args) {
                 ),
             };
 
+            register_extension_block_types(&mut icrc3_config);
+
+            let caller = env.caller();
             let runtime_state = RuntimeState::new(env, data);
 
             init_canister(runtime_state);
             init_icrc3(icrc3_config);
             start_default_archive_job();
+
+            if let Err(e) = set_collection_metadata(caller, init_args.collection_metadata) {
+                ic_cdk::trap(format!("Failed to store collection metadata: {:?}", e));
+            }
             certify_all_assets();
 
             info!("Init complete.")
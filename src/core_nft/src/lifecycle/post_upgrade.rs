@@ -2,6 +2,7 @@ use crate::lifecycle::init_canister;
 use crate::memory::get_upgrades_memory;
 use crate::state::{read_state, replace_icrc3, start_default_archive_job, RuntimeState};
 use crate::types::http::add_redirection;
+use crate::types::icrc3::register_extension_block_types;
 use crate::Args;
 
 use bity_ic_canister_logger::LogEntry;
@@ -34,7 +35,7 @@ fn post_upgrade(args: Args) {
             let reader = get_reader(&memory);
 
             // uncomment these lines if you want to do a normal upgrade
-            let (mut state, logs, traces, icrc3): (RuntimeState, Vec<LogEntry>, Vec<LogEntry>, ICRC3) = bity_ic_serializer
+            let (mut state, logs, traces, mut icrc3): (RuntimeState, Vec<LogEntry>, Vec<LogEntry>, ICRC3) = bity_ic_serializer
                 ::deserialize(reader)
                 .unwrap();
 
@@ -48,9 +49,12 @@ fn post_upgrade(args: Args) {
 
             state.env.set_version(upgrade_args.version);
             state.env.set_commit_hash(upgrade_args.commit_hash);
+            state.data.migrate_legacy_tokens();
+            state.data.permissions.migrate_user_permissions();
 
             bity_ic_canister_logger::init_with_logs(state.env.is_test_mode(), logs, traces);
             init_canister(state.clone());
+            register_extension_block_types(&mut icrc3.icrc3_config);
             replace_icrc3(icrc3);
             start_default_archive_job();
 
@@ -8,6 +8,13 @@ const UPGRADES: MemoryId = MemoryId::new(0);
 pub const TOKEN_APPROVALS: MemoryId = MemoryId::new(1);
 pub const COLLECTION_APPROVALS: MemoryId = MemoryId::new(2);
 pub const METADATA: MemoryId = MemoryId::new(3);
+pub const TOKENS: MemoryId = MemoryId::new(4);
+pub const OWNER_TOKENS: MemoryId = MemoryId::new(5);
+pub const OWNER_BALANCES: MemoryId = MemoryId::new(6);
+pub const BURNED_TOKENS: MemoryId = MemoryId::new(7);
+pub const TOKEN_USERS: MemoryId = MemoryId::new(8);
+pub const TOKEN_CHILDREN: MemoryId = MemoryId::new(9);
+pub const TOKEN_PARENTS: MemoryId = MemoryId::new(10);
 
 pub type VM = VirtualMemory<DefaultMemoryImpl>;
 
@@ -33,6 +40,34 @@ pub fn get_metadata_memory() -> VM {
     get_memory(METADATA)
 }
 
+pub fn get_tokens_memory() -> VM {
+    get_memory(TOKENS)
+}
+
+pub fn get_owner_tokens_memory() -> VM {
+    get_memory(OWNER_TOKENS)
+}
+
+pub fn get_owner_balances_memory() -> VM {
+    get_memory(OWNER_BALANCES)
+}
+
+pub fn get_burned_tokens_memory() -> VM {
+    get_memory(BURNED_TOKENS)
+}
+
+pub fn get_token_users_memory() -> VM {
+    get_memory(TOKEN_USERS)
+}
+
+pub fn get_token_children_memory() -> VM {
+    get_memory(TOKEN_CHILDREN)
+}
+
+pub fn get_token_parents_memory() -> VM {
+    get_memory(TOKEN_PARENTS)
+}
+
 fn get_memory(id: MemoryId) -> VM {
     MEMORY_MANAGER.with(|m| m.get(id))
 }
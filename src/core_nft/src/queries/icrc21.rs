@@ -1,4 +1,4 @@
-use candid::Decode;
+use candid::{Decode, Nat};
 use ic_cdk::query;
 
 use crate::state::read_state;
@@ -6,6 +6,7 @@ pub use crate::types::icrc21;
 pub use crate::types::icrc37;
 pub use crate::types::icrc7;
 pub use crate::types::management;
+use crate::utils::{is_token_transferable, non_transferable_message};
 
 use icrc_ledger_types::icrc21::errors::{ErrorInfo, Icrc21Error};
 use icrc_ledger_types::icrc21::requests::ConsentMessageMetadata;
@@ -43,9 +44,24 @@ fn create_error_response(description: String) -> icrc21::Response {
     icrc21::Response::Err(Icrc21Error::UnsupportedCanisterCall(error_info))
 }
 
+// Soulbound tokens are refused up front so the wallet never asks for consent to a doomed call.
+fn non_transferable_response<'a>(
+    mut token_ids: impl Iterator<Item = &'a Nat>,
+) -> Option<icrc21::Response> {
+    token_ids
+        .find(|token_id| !is_token_transferable(token_id))
+        .map(|token_id| create_error_response(non_transferable_message(token_id)))
+}
+
 fn handle_transfer_consent(args: icrc21::Args) -> icrc21::Response {
     match Decode!(&args.arg, icrc7::icrc7_transfer::Args) {
         Ok(transfer_args) => {
+            if let Some(response) =
+                non_transferable_response(transfer_args.iter().map(|arg| &arg.token_id))
+            {
+                return response;
+            }
+
             let mut fields = vec![
                 ("Action".to_string(), "NFT Transfer".to_string()),
                 ("Method".to_string(), "icrc7_transfer".to_string()),
@@ -80,6 +96,12 @@ fn handle_transfer_consent(args: icrc21::Args) -> icrc21::Response {
 fn handle_approve_tokens_consent(args: icrc21::Args) -> icrc21::Response {
     match Decode!(&args.arg, icrc37::icrc37_approve_tokens::Args) {
         Ok(approve_args) => {
+            if let Some(response) =
+                non_transferable_response(approve_args.iter().map(|arg| &arg.token_id))
+            {
+                return response;
+            }
+
             let mut fields = vec![
                 ("Action".to_string(), "Approve Tokens".to_string()),
                 ("Method".to_string(), "icrc37_approve_tokens".to_string()),
@@ -239,6 +261,12 @@ fn handle_revoke_collection_approvals_consent(args: icrc21::Args) -> icrc21::Res
 fn handle_transfer_from_consent(args: icrc21::Args) -> icrc21::Response {
     match Decode!(&args.arg, icrc37::icrc37_transfer_from::Args) {
         Ok(transfer_args) => {
+            if let Some(response) =
+                non_transferable_response(transfer_args.iter().map(|arg| &arg.token_id))
+            {
+                return response;
+            }
+
             let mut fields = vec![
                 ("Action".to_string(), "Transfer Using Approval".to_string()),
                 ("Method".to_string(), "icrc37_transfer_from".to_string()),
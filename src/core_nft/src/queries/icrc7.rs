@@ -1,6 +1,10 @@
 use crate::state::read_state;
 use crate::types::icrc7;
-use crate::types::metadata::__METADATA;
+use crate::types::metadata::{
+    __METADATA, FROZEN_KEYS_METADATA_KEY, PAUSED_METADATA_KEY, TRANSFERABLE_METADATA_KEY,
+};
+use crate::types::royalties::ROYALTY_ENFORCEMENT_METADATA_KEY;
+use crate::utils::{get_collection_metadata, get_royalties, is_token_transferable};
 
 use candid::Nat;
 use ic_cdk_macros::query;
@@ -99,6 +103,35 @@ pub fn icrc7_collection_metadata() -> icrc7::icrc7_collection_metadata::Response
             ));
         }
 
+        metadata.push((
+            PAUSED_METADATA_KEY.to_string(),
+            ICRC3Value::Text(state.data.paused.to_string()),
+        ));
+
+        metadata.push((
+            ROYALTY_ENFORCEMENT_METADATA_KEY.to_string(),
+            ICRC3Value::Text(state.data.royalty_enforcement.to_string()),
+        ));
+
+        if let Some(reveal) = &state.data.reveal {
+            metadata.extend(reveal.metadata());
+        }
+
+        let frozen_keys = state.data.frozen_metadata.frozen_keys(&Nat::from(0u64));
+        if !frozen_keys.is_empty() {
+            metadata.push((
+                FROZEN_KEYS_METADATA_KEY.to_string(),
+                ICRC3Value::Array(frozen_keys.into_iter().map(ICRC3Value::Text).collect()),
+            ));
+        }
+
+        // Custom keys never shadow the fields above.
+        for (key, value) in get_collection_metadata() {
+            if !metadata.iter().any(|(k, _)| k == &key) {
+                metadata.push((key, value));
+            }
+        }
+
         metadata.sort_by(|a, b| a.0.cmp(&b.0));
         metadata
     })
@@ -195,10 +228,32 @@ pub fn icrc7_token_metadata(
     let mut ret = Vec::new();
 
     for token_id in token_ids {
-        let token = read_state(|state| state.data.get_token_by_id(&token_id).cloned());
+        let token = read_state(|state| state.data.get_token_by_id(&token_id));
         match token {
             Some(token) => {
-                let metadata = token.token_metadata(&__METADATA.with_borrow(|m| m.clone()));
+                let mut metadata = token.token_metadata(&__METADATA.with_borrow(|m| m.clone()));
+
+                let frozen_keys =
+                    read_state(|state| state.data.frozen_metadata.frozen_keys(&token_id));
+                metadata.retain(|(key, _)| key != FROZEN_KEYS_METADATA_KEY);
+                // Surface a collection-wide soulbound flag on every token.
+                if !metadata
+                    .iter()
+                    .any(|(key, _)| key == TRANSFERABLE_METADATA_KEY)
+                    && !is_token_transferable(&token_id)
+                {
+                    metadata.push((
+                        TRANSFERABLE_METADATA_KEY.to_string(),
+                        ICRC3Value::Text("false".to_string()),
+                    ));
+                }
+                if !frozen_keys.is_empty() {
+                    metadata.push((
+                        FROZEN_KEYS_METADATA_KEY.to_string(),
+                        ICRC3Value::Array(frozen_keys.into_iter().map(ICRC3Value::Text).collect()),
+                    ));
+                }
+
                 ret.push(Some(metadata));
             }
             None => {
@@ -292,13 +347,7 @@ pub fn icrc7_tokens(
         )
         .unwrap_or(icrc7::DEFAULT_TAKE_VALUE);
 
-        let mut tokens: Vec<_> = state.data.tokens_list.keys().cloned().collect();
-        tokens.sort();
-        let start_index = tokens
-            .iter()
-            .position(|id| id > &prev)
-            .unwrap_or(tokens.len());
-        tokens.into_iter().skip(start_index).take(take).collect()
+        state.data.token_ids(&prev, take)
     })
 }
 
@@ -335,12 +384,27 @@ pub fn icrc7_tokens_of(
         )
         .unwrap_or(icrc7::DEFAULT_TAKE_VALUE);
 
-        let mut tokens: Vec<Nat> = state.data.tokens_ids_of_account(&account);
-        tokens.sort();
-        let start_index = tokens
-            .iter()
-            .position(|id| id > &prev)
-            .unwrap_or(tokens.len());
-        tokens.into_iter().skip(start_index).take(take).collect()
+        state.data.tokens_ids_of_account(&account, &prev, take)
+    })
+}
+
+#[query]
+pub fn icrc7_royalty_info(
+    token_id: icrc7::icrc7_royalty_info::Args0,
+    sale_price: icrc7::icrc7_royalty_info::Args1,
+) -> icrc7::icrc7_royalty_info::Response {
+    if !read_state(|state| state.data.token_exists(&token_id)) {
+        return None;
+    }
+
+    Some(match get_royalties(&token_id) {
+        Some(royalties) => icrc7::icrc7_royalty_info::RoyaltyInfo {
+            ledger: royalties.ledger,
+            payouts: royalties.payouts(&sale_price),
+        },
+        None => icrc7::icrc7_royalty_info::RoyaltyInfo {
+            ledger: None,
+            payouts: vec![],
+        },
     })
 }
@@ -1,9 +1,23 @@
-use crate::types::nft::Icrc7Token;
-use crate::types::permissions::{Permission, PermissionManager};
+use crate::types::icrc37::__TOKEN_APPROVALS;
+use crate::types::icrc7;
+use crate::types::marketplace::{Listing, MarketplaceConfig, PendingPayout};
+use crate::types::metadata::{FrozenMetadata, MetadataValidation, __METADATA};
+use crate::types::multisig::{MultisigConfig, Proposal};
+use crate::types::nest::{ChildToken, NestingError, MAX_NESTING_DEPTH};
+use crate::types::nft::{
+    BurnedToken, Icrc7Token, OwnerTokenKey, TokenUser, __BURNED_TOKENS, __OWNER_BALANCES,
+    __OWNER_TOKENS, __TOKENS, __TOKEN_CHILDREN, __TOKEN_PARENTS, __TOKEN_USERS,
+};
+use crate::types::offer::Offer;
+use crate::types::permissions::{Permission, PermissionManager, PermissionRegistry};
+use crate::types::public_mint::PublicMintConfig;
+use crate::types::reveal::RevealCommitment;
 use crate::types::sub_canister;
 use crate::types::sub_canister::{
     StorageSubCanisterManager, INITIAL_CYCLES_BALANCE, RESERVED_CYCLES_BALANCE,
 };
+use crate::types::timelock::{PendingOperation, TimelockConfig};
+use crate::types::wrapped_types::{WrappedAccount, WrappedChildTokens, WrappedNat};
 
 use bity_ic_canister_state_macros::canister_state;
 use bity_ic_icrc3::transaction::TransactionType;
@@ -17,11 +31,12 @@ use bity_ic_utils::memory::MemorySize;
 use candid::{CandidType, Nat, Principal};
 use icrc_ledger_types::icrc1::account::Account;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Bound;
 
 pub use bity_ic_storage_canister_api::lifecycle::{init::InitArgs, post_upgrade::UpgradeArgs};
 
-const STORAGE_WASM: &[u8] = include_bytes!("../../../wasm/storage_canister.wasm.gz");
+pub(crate) const STORAGE_WASM: &[u8] = include_bytes!("../../../wasm/storage_canister.wasm.gz");
 
 icrc3_state!();
 canister_state!(RuntimeState);
@@ -63,7 +78,7 @@ impl RuntimeState {
 
 #[derive(Serialize, Deserialize)]
 pub struct Data {
-    pub permissions: PermissionManager,
+    pub permissions: PermissionRegistry,
     pub description: Option<String>,
     pub symbol: String,
     pub name: String,
@@ -78,12 +93,67 @@ pub struct Data {
     pub tx_window: Option<Nat>,
     pub permitted_drift: Option<Nat>,
     pub max_canister_storage_threshold: Option<Nat>,
-    pub tokens_list: HashMap<Nat, Icrc7Token>,
-    pub tokens_list_by_owner: HashMap<Account, Vec<Nat>>,
+    // Tokens now live in __TOKENS / __OWNER_TOKENS; only read back from older snapshots.
+    #[serde(default, rename = "tokens_list", skip_serializing)]
+    pub legacy_tokens_list: HashMap<Nat, Icrc7Token>,
     pub approval_init: InitApprovalsArg,
     pub sub_canister_manager: StorageSubCanisterManager,
     pub last_token_id: Nat,
     pub media_redirections: HashMap<String, String>,
+    #[serde(default)]
+    pub recent_mints: HashMap<MintDedupKey, Nat>,
+    #[serde(default)]
+    pub frozen_metadata: FrozenMetadata,
+    #[serde(default)]
+    pub metadata_validation: MetadataValidation,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub public_mint: Option<PublicMintConfig>,
+    // Counts include purchases whose payment is still in flight.
+    #[serde(default)]
+    pub public_mint_purchases: HashMap<Principal, Nat>,
+    // Supply held for public mints awaiting payment.
+    #[serde(default)]
+    pub public_mint_reserved: Nat,
+    // Proceeds received but not yet forwarded to the treasury.
+    #[serde(default)]
+    pub public_mint_unsettled: Nat,
+    #[serde(default)]
+    pub public_mint_pending_refunds: HashMap<Principal, Nat>,
+    // Purchases per mint phase name, on top of the collection-wide counts.
+    #[serde(default)]
+    pub public_mint_phase_purchases: HashMap<String, HashMap<Principal, Nat>>,
+    #[serde(default)]
+    pub allowlist_root: Option<[u8; 32]>,
+    #[serde(default)]
+    pub reveal: Option<RevealCommitment>,
+    #[serde(default)]
+    pub marketplace: Option<MarketplaceConfig>,
+    #[serde(default)]
+    pub listings: BTreeMap<Nat, Listing>,
+    #[serde(default)]
+    pub marketplace_pending_payouts: Vec<PendingPayout>,
+    #[serde(default)]
+    pub royalty_enforcement: bool,
+    #[serde(default)]
+    pub offers: BTreeMap<Nat, Offer>,
+    #[serde(default)]
+    pub next_offer_id: Nat,
+    #[serde(default)]
+    pub token_account_ledgers: Vec<Principal>,
+    #[serde(default)]
+    pub multisig: Option<MultisigConfig>,
+    #[serde(default)]
+    pub proposals: BTreeMap<Nat, Proposal>,
+    #[serde(default)]
+    pub next_proposal_id: Nat,
+    #[serde(default)]
+    pub timelock: Option<TimelockConfig>,
+    #[serde(default)]
+    pub pending_operations: BTreeMap<Nat, PendingOperation>,
+    #[serde(default)]
+    pub next_operation_id: Nat,
 }
 
 impl Data {
@@ -109,20 +179,8 @@ impl Data {
         permitted_drift: Option<Nat>,
         approval_init: InitApprovalsArg,
     ) -> Self {
-        let mut authorized_principals = vec![];
-
-        for permission in permissions.user_permissions.clone() {
-            match permission
-                .1
-                .iter()
-                .find(|p| *p == &Permission::ManageAuthorities)
-            {
-                Some(_) => {
-                    authorized_principals.push(permission.0.clone());
-                }
-                None => {}
-            }
-        }
+        let permissions = PermissionRegistry::from(permissions);
+        let authorized_principals = permissions.holders_of(&Permission::ManageAuthorities, 0);
 
         let sub_canister_manager = StorageSubCanisterManager::new(
             sub_canister::ArgsStorage::Init(InitArgs {
@@ -166,64 +224,363 @@ impl Data {
             tx_window,
             permitted_drift,
             max_canister_storage_threshold,
-            tokens_list: HashMap::new(),
-            tokens_list_by_owner: HashMap::new(),
+            legacy_tokens_list: HashMap::new(),
             approval_init,
             sub_canister_manager,
             last_token_id: Nat::from(1u64), // 0 is the reserved value for the collection metadata
             media_redirections: HashMap::new(),
+            recent_mints: HashMap::new(),
+            frozen_metadata: FrozenMetadata::default(),
+            metadata_validation: MetadataValidation::default(),
+            paused: false,
+            public_mint: None,
+            public_mint_purchases: HashMap::new(),
+            public_mint_reserved: Nat::from(0u64),
+            public_mint_unsettled: Nat::from(0u64),
+            public_mint_pending_refunds: HashMap::new(),
+            public_mint_phase_purchases: HashMap::new(),
+            allowlist_root: None,
+            reveal: None,
+            marketplace: None,
+            listings: BTreeMap::new(),
+            marketplace_pending_payouts: Vec::new(),
+            royalty_enforcement: false,
+            offers: BTreeMap::new(),
+            next_offer_id: Nat::from(0u64),
+            token_account_ledgers: Vec::new(),
+            multisig: None,
+            proposals: BTreeMap::new(),
+            next_proposal_id: Nat::from(0u64),
+            timelock: None,
+            pending_operations: BTreeMap::new(),
+            next_operation_id: Nat::from(0u64),
         }
     }
 
-    pub fn get_token_by_id(&self, token_id: &Nat) -> Option<&Icrc7Token> {
-        self.tokens_list.get(token_id)
+    pub fn get_token_by_id(&self, token_id: &Nat) -> Option<Icrc7Token> {
+        __TOKENS.with_borrow(|tokens| {
+            tokens
+                .get(&WrappedNat(token_id.clone()))
+                .map(|owner| Icrc7Token::new(token_id.clone(), owner.0))
+        })
     }
 
     pub fn update_token_by_id(&mut self, token_id: &Nat, token: &Icrc7Token) {
-        self.tokens_list.insert(token_id.clone(), token.clone());
+        let previous_owner = __TOKENS.with_borrow_mut(|tokens| {
+            tokens.insert(
+                WrappedNat(token_id.clone()),
+                WrappedAccount(token.token_owner.clone()),
+            )
+        });
+
+        match previous_owner {
+            Some(previous_owner) if previous_owner.0 == token.token_owner => {}
+            Some(previous_owner) => {
+                remove_from_owner_index(&previous_owner.0, token_id);
+                add_to_owner_index(&token.token_owner, token_id);
+                // Neither a listing, an approval nor a user role survives its token changing hands.
+                self.listings.remove(token_id);
+                self.clear_token_user(token_id);
+                __TOKEN_APPROVALS.with_borrow_mut(|token_approvals| {
+                    token_approvals.remove(&WrappedNat(token_id.clone()))
+                });
+            }
+            None => add_to_owner_index(&token.token_owner, token_id),
+        }
     }
 
     pub fn add_token(&mut self, token: &Icrc7Token) {
-        self.tokens_list
-            .insert(token.clone().token_id, token.clone());
+        self.update_token_by_id(&token.token_id, token);
+    }
+
+    pub fn remove_token(&mut self, token_id: &Nat) -> Option<Icrc7Token> {
+        let owner =
+            __TOKENS.with_borrow_mut(|tokens| tokens.remove(&WrappedNat(token_id.clone())))?;
+        remove_from_owner_index(&owner.0, token_id);
+        self.listings.remove(token_id);
+        self.clear_token_user(token_id);
+        if let Some(parent_id) = self.parent_of(token_id) {
+            let child = ChildToken {
+                collection: ic_cdk::api::canister_self(),
+                token_id: token_id.clone(),
+            };
+            self.remove_child(&parent_id, &child);
+        }
+
+        Some(Icrc7Token::new(token_id.clone(), owner.0))
+    }
+
+    pub fn burn_token(&mut self, token_id: &Nat, burned_token: BurnedToken) -> Option<Icrc7Token> {
+        let token = self.remove_token(token_id)?;
+        let key = WrappedNat(token_id.clone());
+
+        __TOKEN_APPROVALS.with_borrow_mut(|token_approvals| token_approvals.remove(&key));
+        __METADATA.with_borrow_mut(|m| m.delete_all_data(Some(token_id.clone())));
+        self.frozen_metadata.remove_token(token_id);
+        __BURNED_TOKENS.with_borrow_mut(|burned_tokens| burned_tokens.insert(key, burned_token));
+
+        Some(token)
+    }
+
+    pub fn get_burned_token(&self, token_id: &Nat) -> Option<BurnedToken> {
+        __BURNED_TOKENS
+            .with_borrow(|burned_tokens| burned_tokens.get(&WrappedNat(token_id.clone())))
     }
 
     pub fn owner_of(&self, token_id: &Nat) -> Option<Account> {
-        self.tokens_list
-            .get(token_id)
-            .map(|token| token.token_owner.clone())
+        __TOKENS.with_borrow(|tokens| {
+            tokens
+                .get(&WrappedNat(token_id.clone()))
+                .map(|owner| owner.0)
+        })
+    }
+
+    // Expired users are filtered out here; the user expiry job removes them from memory.
+    pub fn user_of(&self, token_id: &Nat, now: TimestampNanos) -> Option<TokenUser> {
+        __TOKEN_USERS
+            .with_borrow(|users| users.get(&WrappedNat(token_id.clone())))
+            .filter(|user| !user.is_expired(now))
+    }
+
+    pub fn set_token_user(&mut self, token_id: &Nat, user: TokenUser) {
+        __TOKEN_USERS.with_borrow_mut(|users| users.insert(WrappedNat(token_id.clone()), user));
+    }
+
+    pub fn clear_token_user(&mut self, token_id: &Nat) -> Option<TokenUser> {
+        __TOKEN_USERS.with_borrow_mut(|users| users.remove(&WrappedNat(token_id.clone())))
+    }
+
+    pub fn prune_expired_users(&mut self, now: TimestampNanos) -> usize {
+        __TOKEN_USERS.with_borrow_mut(|users| {
+            let expired: Vec<WrappedNat> = users
+                .iter()
+                .filter(|(_, user)| user.is_expired(now))
+                .map(|(token_id, _)| token_id)
+                .collect();
+            for token_id in &expired {
+                users.remove(token_id);
+            }
+            expired.len()
+        })
+    }
+
+    pub fn children_of(&self, token_id: &Nat) -> Vec<ChildToken> {
+        __TOKEN_CHILDREN.with_borrow(|children| {
+            children
+                .get(&WrappedNat(token_id.clone()))
+                .map(|c| c.0)
+                .unwrap_or_default()
+        })
+    }
+
+    pub fn parent_of(&self, token_id: &Nat) -> Option<Nat> {
+        __TOKEN_PARENTS
+            .with_borrow(|parents| parents.get(&WrappedNat(token_id.clone())))
+            .map(|parent| parent.0)
+    }
+
+    // Parent first, root last. Chains are capped at MAX_NESTING_DEPTH when children are attached.
+    pub fn ancestors_of(&self, token_id: &Nat) -> Result<Vec<Nat>, NestingError> {
+        let mut ancestors = Vec::new();
+        let mut current = token_id.clone();
+        while let Some(parent_id) = self.parent_of(&current) {
+            if ancestors.len() == MAX_NESTING_DEPTH {
+                return Err(NestingError::MaxDepthExceeded);
+            }
+            ancestors.push(parent_id.clone());
+            current = parent_id;
+        }
+        Ok(ancestors)
+    }
+
+    // Levels of children from this collection below token_id, walked no further than
+    // max_height levels down.
+    pub fn subtree_height(&self, token_id: &Nat, max_height: usize) -> Result<usize, NestingError> {
+        let collection = ic_cdk::api::canister_self();
+        let mut level = vec![token_id.clone()];
+        let mut height = 0;
+        loop {
+            let next: Vec<Nat> = level
+                .iter()
+                .flat_map(|token_id| self.children_of(token_id))
+                .filter(|child| child.collection == collection)
+                .map(|child| child.token_id)
+                .collect();
+            if next.is_empty() {
+                return Ok(height);
+            }
+            if height == max_height {
+                return Err(NestingError::MaxDepthExceeded);
+            }
+            height += 1;
+            level = next;
+        }
+    }
+
+    // Owner of the top-most token above token_id, i.e. the account that controls it.
+    pub fn root_owner_of(&self, token_id: &Nat) -> Result<Account, NestingError> {
+        let root = self
+            .ancestors_of(token_id)?
+            .pop()
+            .unwrap_or_else(|| token_id.clone());
+        self.owner_of(&root).ok_or(NestingError::TokenDoesNotExist)
+    }
+
+    // Children from this collection are also indexed by their parent.
+    pub fn add_child(&mut self, parent_id: &Nat, child: ChildToken) {
+        if child.collection == ic_cdk::api::canister_self() {
+            __TOKEN_PARENTS.with_borrow_mut(|parents| {
+                parents.insert(
+                    WrappedNat(child.token_id.clone()),
+                    WrappedNat(parent_id.clone()),
+                )
+            });
+        }
+        __TOKEN_CHILDREN.with_borrow_mut(|index| {
+            let key = WrappedNat(parent_id.clone());
+            let mut children = index.get(&key).unwrap_or_default();
+            children.0.push(child);
+            index.insert(key, children);
+        });
+    }
+
+    pub fn remove_child(&mut self, parent_id: &Nat, child: &ChildToken) -> bool {
+        let removed = __TOKEN_CHILDREN.with_borrow_mut(|index| {
+            let key = WrappedNat(parent_id.clone());
+            let Some(mut children) = index.get(&key) else {
+                return false;
+            };
+            let len = children.0.len();
+            children.0.retain(|c| c != child);
+            let removed = children.0.len() != len;
+            if children.0.is_empty() {
+                index.remove(&key);
+            } else {
+                index.insert(key, WrappedChildTokens(children.0));
+            }
+            removed
+        });
+
+        if removed && child.collection == ic_cdk::api::canister_self() {
+            __TOKEN_PARENTS
+                .with_borrow_mut(|parents| parents.remove(&WrappedNat(child.token_id.clone())));
+        }
+        removed
     }
 
     pub fn tokens_balance_of(&self, owner: &Account) -> Nat {
         Nat::from(
-            self.tokens_list_by_owner
-                .get(owner)
-                .map(|v| v.len() as u64)
+            __OWNER_BALANCES
+                .with_borrow(|balances| balances.get(&WrappedAccount(*owner)))
                 .unwrap_or(0),
         )
     }
 
-    pub fn tokens_of_account(&self, owner: &Account) -> Vec<Icrc7Token> {
-        self.tokens_list_by_owner
-            .get(owner)
-            .map(|v| {
-                v.iter()
-                    .map(|id| self.tokens_list.get(id).unwrap().clone())
-                    .collect()
-            })
-            .unwrap_or(vec![])
+    // Up to `take` ids owned by `owner` greater than `prev`, in ascending order.
+    pub fn tokens_ids_of_account(&self, owner: &Account, prev: &Nat, take: usize) -> Vec<Nat> {
+        let start = OwnerTokenKey::new(owner, prev);
+        __OWNER_TOKENS.with_borrow(|index| {
+            index
+                .range((Bound::Excluded(start), Bound::Unbounded))
+                .take_while(|(key, _)| key.owner.0 == *owner)
+                .take(take)
+                .map(|(key, _)| key.token_id.0)
+                .collect()
+        })
     }
 
-    pub fn tokens_ids_of_account(&self, owner: &Account) -> Vec<Nat> {
-        self.tokens_list
-            .iter()
-            .filter(|(_, token)| &token.token_owner == owner)
-            .map(|(id, _)| id.clone())
-            .collect()
+    pub fn principal_has_tokens(&self, principal: &Principal) -> bool {
+        // Accounts sort by principal first, so the principal's default account starts its range.
+        let start = OwnerTokenKey::new(
+            &Account {
+                owner: *principal,
+                subaccount: None,
+            },
+            &Nat::from(0u64),
+        );
+        __OWNER_TOKENS.with_borrow(|index| {
+            index
+                .range(start..)
+                .next()
+                .is_some_and(|(key, _)| key.owner.0.owner == *principal)
+        })
+    }
+
+    // Up to `take` token ids greater than `prev`, in ascending order.
+    pub fn token_ids(&self, prev: &Nat, take: usize) -> Vec<Nat> {
+        let start = WrappedNat(prev.clone());
+        __TOKENS.with_borrow(|tokens| {
+            tokens
+                .range((Bound::Excluded(start), Bound::Unbounded))
+                .take(take)
+                .map(|(id, _)| id.0)
+                .collect()
+        })
+    }
+
+    pub fn token_exists(&self, token_id: &Nat) -> bool {
+        __TOKENS.with_borrow(|tokens| tokens.contains_key(&WrappedNat(token_id.clone())))
+    }
+
+    pub fn is_token_id_available(&self, token_id: &Nat) -> bool {
+        !self.token_exists(token_id) && self.get_burned_token(token_id).is_none()
+    }
+
+    pub fn prune_recent_mints(&mut self, now: TimestampNanos) {
+        let tx_window = self
+            .tx_window
+            .clone()
+            .map(|d| u64::try_from(d.0).unwrap())
+            .unwrap_or(icrc7::DEFAULT_TX_WINDOW);
+        let drift = self
+            .permitted_drift
+            .clone()
+            .map(|d| u64::try_from(d.0).unwrap())
+            .unwrap_or(icrc7::DEFAULT_PERMITTED_DRIFT);
+
+        self.recent_mints
+            .retain(|key, _| key.created_at_time >= now.saturating_sub(tx_window + drift));
     }
 
     pub fn total_supply(&self) -> Nat {
-        Nat::from(self.tokens_list.len() as u64)
+        Nat::from(__TOKENS.with_borrow(|tokens| tokens.len()))
+    }
+
+    // Moves the token ledger of a snapshot taken before it lived in stable memory.
+    pub fn migrate_legacy_tokens(&mut self) {
+        let legacy_tokens = std::mem::take(&mut self.legacy_tokens_list);
+
+        for token in legacy_tokens.values() {
+            self.add_token(token);
+        }
+    }
+}
+
+fn add_to_owner_index(owner: &Account, token_id: &Nat) {
+    let inserted = __OWNER_TOKENS
+        .with_borrow_mut(|index| index.insert(OwnerTokenKey::new(owner, token_id), ()))
+        .is_none();
+    if inserted {
+        __OWNER_BALANCES.with_borrow_mut(|balances| {
+            let key = WrappedAccount(*owner);
+            let balance = balances.get(&key).unwrap_or(0);
+            balances.insert(key, balance + 1);
+        });
+    }
+}
+
+fn remove_from_owner_index(owner: &Account, token_id: &Nat) {
+    let removed = __OWNER_TOKENS
+        .with_borrow_mut(|index| index.remove(&OwnerTokenKey::new(owner, token_id)))
+        .is_some();
+    if removed {
+        __OWNER_BALANCES.with_borrow_mut(|balances| {
+            let key = WrappedAccount(*owner);
+            match balances.get(&key).unwrap_or(0) {
+                0 | 1 => balances.remove(&key),
+                balance => balances.insert(key, balance - 1),
+            };
+        });
     }
 }
 
@@ -245,16 +602,49 @@ impl Clone for Data {
             tx_window: self.tx_window.clone(),
             permitted_drift: self.permitted_drift.clone(),
             max_canister_storage_threshold: self.max_canister_storage_threshold.clone(),
-            tokens_list: self.tokens_list.clone(),
-            tokens_list_by_owner: self.tokens_list_by_owner.clone(),
+            legacy_tokens_list: self.legacy_tokens_list.clone(),
             approval_init: self.approval_init.clone(),
             sub_canister_manager: self.sub_canister_manager.clone(),
             last_token_id: self.last_token_id.clone(),
             media_redirections: self.media_redirections.clone(),
+            recent_mints: self.recent_mints.clone(),
+            frozen_metadata: self.frozen_metadata.clone(),
+            metadata_validation: self.metadata_validation.clone(),
+            paused: self.paused,
+            public_mint: self.public_mint.clone(),
+            public_mint_purchases: self.public_mint_purchases.clone(),
+            public_mint_reserved: self.public_mint_reserved.clone(),
+            public_mint_unsettled: self.public_mint_unsettled.clone(),
+            public_mint_pending_refunds: self.public_mint_pending_refunds.clone(),
+            public_mint_phase_purchases: self.public_mint_phase_purchases.clone(),
+            allowlist_root: self.allowlist_root,
+            reveal: self.reveal.clone(),
+            marketplace: self.marketplace.clone(),
+            listings: self.listings.clone(),
+            marketplace_pending_payouts: self.marketplace_pending_payouts.clone(),
+            royalty_enforcement: self.royalty_enforcement,
+            offers: self.offers.clone(),
+            next_offer_id: self.next_offer_id.clone(),
+            token_account_ledgers: self.token_account_ledgers.clone(),
+            multisig: self.multisig.clone(),
+            proposals: self.proposals.clone(),
+            next_proposal_id: self.next_proposal_id.clone(),
+            timelock: self.timelock.clone(),
+            pending_operations: self.pending_operations.clone(),
+            next_operation_id: self.next_operation_id.clone(),
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MintDedupKey {
+    pub caller: Principal,
+    pub token_owner: Account,
+    pub token_id: Option<Nat>,
+    pub memo: Option<Vec<u8>>,
+    pub created_at_time: TimestampNanos,
+}
+
 #[derive(CandidType, Deserialize, Serialize, Debug, Clone)]
 pub struct InitApprovalsArg {
     pub max_approvals_per_token_or_collection: Option<Nat>,
@@ -264,7 +654,7 @@ pub struct InitApprovalsArg {
 #[derive(CandidType, Serialize)]
 pub struct Metrics {
     pub canister_info: CanisterInfo,
-    pub permissions: PermissionManager,
+    pub permissions: PermissionRegistry,
 }
 
 #[derive(CandidType, Deserialize, Serialize)]
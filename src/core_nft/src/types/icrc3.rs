@@ -1,5 +1,122 @@
+use bity_ic_icrc3::config::ICRC3Config;
+use bity_ic_icrc3::transaction::TransactionType;
+use bity_ic_types::TimestampSeconds;
+use candid::{CandidType, Nat};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
 use icrc_ledger_types::icrc3::archive::ICRC3ArchiveInfo;
-use icrc_ledger_types::icrc3::blocks::{GetBlocksRequest, GetBlocksResult};
+use icrc_ledger_types::icrc3::blocks::{GetBlocksRequest, GetBlocksResult, SupportedBlockType};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const EXTENSION_BLOCKS_URL: &str =
+    "https://github.com/ORIGYN-SA/nft/blob/main/src/core_nft/README.md";
+
+// Block types for the features this collection adds on top of ICRC-7/37, with the README anchor
+// documenting their schema.
+const EXTENSION_BLOCK_TYPES: &[(&str, &str)] = &[
+    ("offer", "offer-blocks"),
+    ("offer_accept", "offer-blocks"),
+    ("offer_cancel", "offer-blocks"),
+    ("offer_expire", "offer-blocks"),
+    ("set_user", "user-blocks"),
+    ("nest_attach", "nesting-blocks"),
+    ("nest_detach", "nesting-blocks"),
+    ("perm_grant", "administration-blocks"),
+    ("perm_revoke", "administration-blocks"),
+    ("role_update", "administration-blocks"),
+    ("coll_update", "administration-blocks"),
+    ("proposal_create", "proposal-blocks"),
+    ("proposal_approve", "proposal-blocks"),
+    ("proposal_execute", "proposal-blocks"),
+    ("proposal_expire", "proposal-blocks"),
+    ("op_schedule", "timelock-blocks"),
+    ("op_cancel", "timelock-blocks"),
+    ("op_execute", "timelock-blocks"),
+];
+
+pub fn extension_block_types() -> Vec<SupportedBlockType> {
+    EXTENSION_BLOCK_TYPES
+        .iter()
+        .map(|(block_type, anchor)| SupportedBlockType {
+            block_type: block_type.to_string(),
+            url: format!("{}#{}", EXTENSION_BLOCKS_URL, anchor),
+        })
+        .collect()
+}
+
+// Canisters upgraded from an older version keep the block types their ICRC-3 log was created with.
+pub fn register_extension_block_types(config: &mut ICRC3Config) {
+    for block_type in extension_block_types() {
+        if !config
+            .supported_blocks
+            .iter()
+            .any(|b| b.block_type == block_type.block_type)
+        {
+            config.supported_blocks.push(block_type);
+        }
+    }
+}
+
+// The ICRC-7/37 transaction types reject any other btype, so extension blocks carry their own
+// `tx` map.
+#[derive(Clone, Debug, CandidType, Serialize, Deserialize)]
+pub struct ExtensionTransaction {
+    pub btype: String,
+    pub timestamp: u64,
+    pub tx: BTreeMap<String, ICRC3Value>,
+}
+
+impl ExtensionTransaction {
+    pub fn new(btype: &str, timestamp: u64, mut tx: BTreeMap<String, ICRC3Value>) -> Self {
+        tx.insert("op".to_string(), ICRC3Value::Text(btype.to_string()));
+        Self {
+            btype: btype.to_string(),
+            timestamp,
+            tx,
+        }
+    }
+}
+
+impl TransactionType for ExtensionTransaction {
+    fn validate_transaction_fields(&self) -> Result<(), String> {
+        if !EXTENSION_BLOCK_TYPES
+            .iter()
+            .any(|(block_type, _)| *block_type == self.btype)
+        {
+            return Err("Invalid extension transaction type".to_string());
+        }
+        match self.tx.get("op") {
+            Some(ICRC3Value::Text(op)) if *op == self.btype => Ok(()),
+            _ => Err("btype and op must be the same".to_string()),
+        }
+    }
+
+    fn timestamp(&self) -> Option<TimestampSeconds> {
+        Some(self.timestamp)
+    }
+
+    fn block_type(&self) -> String {
+        self.btype.clone()
+    }
+
+    fn tx(&self) -> ICRC3Value {
+        ICRC3Value::Map(self.tx.clone())
+    }
+}
+
+impl From<ExtensionTransaction> for ICRC3Value {
+    fn from(tx: ExtensionTransaction) -> Self {
+        let mut map = BTreeMap::new();
+        map.insert("btype".to_string(), ICRC3Value::Text(tx.btype));
+        map.insert(
+            "timestamp".to_string(),
+            ICRC3Value::Nat(Nat::from(tx.timestamp)),
+        );
+        map.insert("tx".to_string(), ICRC3Value::Map(tx.tx));
+
+        ICRC3Value::Map(map)
+    }
+}
 
 pub mod icrc3_get_archives {
     use super::*;
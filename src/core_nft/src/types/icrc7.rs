@@ -186,3 +186,18 @@ pub mod icrc7_tx_window {
     pub type Args = ();
     pub type Response = Option<candid::Nat>;
 }
+
+pub mod icrc7_royalty_info {
+    use super::*;
+    use crate::types::royalties::RoyaltyPayout;
+
+    pub type Args0 = candid::Nat;
+    pub type Args1 = candid::Nat;
+
+    #[derive(CandidType, Deserialize, Clone, Debug)]
+    pub struct RoyaltyInfo {
+        pub ledger: Option<Principal>,
+        pub payouts: Vec<RoyaltyPayout>,
+    }
+    pub type Response = Option<RoyaltyInfo>;
+}
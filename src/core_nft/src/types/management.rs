@@ -1,4 +1,5 @@
-use crate::types::permissions::Permission;
+use crate::types::metadata::{MetadataValidation, MetadataValidationError};
+use crate::types::permissions::{Permission, PermissionHolder};
 use crate::types::value_custom::CustomValue;
 
 use bity_ic_storage_canister_api::types::storage::UploadState;
@@ -16,6 +17,9 @@ pub mod mint {
         pub token_owner: Account,
         pub memo: Option<serde_bytes::ByteBuf>,
         pub metadata: Vec<(String, ICRC3Value)>,
+        // Falls back to the next free sequential id when not set.
+        pub token_id: Option<Nat>,
+        pub created_at_time: Option<u64>,
     }
 
     #[derive(CandidType, Serialize, Deserialize, Clone)]
@@ -28,6 +32,12 @@ pub mod mint {
         ExceedMaxAllowedSupplyCap,
         TokenAlreadyExists,
         InvalidMemo,
+        InvalidTokenId,
+        TooOld,
+        CreatedInFuture { ledger_time: Nat },
+        InvalidMetadata(MetadataValidationError),
+        // Royalties, the transferable flag and the other reserved keys have their own endpoints.
+        ReservedMetadataKey(String),
         StorageCanisterError(String),
     }
     pub type Response = Result<Nat, MintError>;
@@ -43,10 +53,37 @@ pub mod burn_nft {
         ConcurrentManagementCall,
         TokenDoesNotExist,
         StorageCanisterError(String),
+        InvalidMemo,
+        TooOld,
+        CreatedInFuture { ledger_time: Nat },
+        // Children must be detached before their parent is burned.
+        HasChildren,
+        // The batch has more burns than `max_update_batch_size`.
+        ExceedMaxUpdateBatchSize,
     }
     pub type Response = Result<(), BurnNftError>;
 }
 
+pub mod burn_nfts {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct BurnArg {
+        pub token_id: Nat,
+        pub memo: Option<serde_bytes::ByteBuf>,
+        pub created_at_time: Option<u64>,
+    }
+
+    pub type Args = Vec<BurnArg>;
+    pub type BurnResult = Result<Nat, super::burn_nft::BurnNftError>;
+    pub type Response = Vec<Option<BurnResult>>;
+}
+
+pub mod admin_burn_nfts {
+    pub type Args = super::burn_nfts::Args;
+    pub type Response = super::burn_nfts::Response;
+}
+
 pub mod update_nft_metadata {
     use super::*;
 
@@ -59,10 +96,125 @@ pub mod update_nft_metadata {
     pub enum UpdateNftMetadataError {
         ConcurrentManagementCall,
         TokenDoesNotExist,
+        MetadataFrozen,
+        InvalidMetadata(MetadataValidationError),
+        // Royalties, the transferable flag and the other reserved keys have their own endpoints.
+        ReservedMetadataKey(String),
+        // The batch has more patches than `max_update_batch_size`.
+        ExceedMaxUpdateBatchSize,
+        // Replacements go through `schedule_operation` while a timelock is set; patches are refused.
+        TimelockRequired,
         StorageCanisterError(String),
     }
     pub type Response = Result<Nat, UpdateNftMetadataError>;
 }
+
+pub mod update_nft_metadata_patch {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone)]
+    pub struct MetadataPatch {
+        pub token_id: Nat,
+        pub set: Vec<(String, ICRC3Value)>,
+        pub unset: Vec<String>,
+    }
+
+    pub type Args = Vec<MetadataPatch>;
+    pub type PatchResult = Result<Nat, super::update_nft_metadata::UpdateNftMetadataError>;
+    pub type Response = Vec<Option<PatchResult>>;
+}
+pub mod freeze_metadata {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub enum Args {
+        Collection,
+        Token(Nat),
+        TokenKeys { token_id: Nat, keys: Vec<String> },
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum FreezeMetadataError {
+        ConcurrentManagementCall,
+        TokenDoesNotExist,
+        AlreadyFrozen,
+        StorageCanisterError(String),
+    }
+    pub type Response = Result<(), FreezeMetadataError>;
+}
+
+pub mod set_royalties {
+    use super::*;
+    use crate::types::royalties::Royalties;
+
+    // A missing token_id targets the collection defaults, missing royalties clears them.
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub token_id: Option<Nat>,
+        pub royalties: Option<Royalties>,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum SetRoyaltiesError {
+        ConcurrentManagementCall,
+        TokenDoesNotExist,
+        InvalidBasisPoints,
+        TooManyRecipients,
+        MetadataFrozen,
+        StorageCanisterError(String),
+    }
+    pub type Response = Result<(), SetRoyaltiesError>;
+}
+
+pub mod set_royalty_enforcement {
+    use super::*;
+
+    // When enabled, approved spenders can only move tokens through sale_transfer_from.
+    pub type Args = bool;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum SetRoyaltyEnforcementError {
+        ConcurrentManagementCall,
+        StorageCanisterError(String),
+    }
+    pub type Response = Result<(), SetRoyaltyEnforcementError>;
+}
+
+pub mod set_transferable {
+    use super::*;
+
+    // A missing token_id targets the collection default, a missing flag clears it.
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub token_id: Option<Nat>,
+        pub transferable: Option<bool>,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum SetTransferableError {
+        ConcurrentManagementCall,
+        TokenDoesNotExist,
+        MetadataFrozen,
+        StorageCanisterError(String),
+    }
+    pub type Response = Result<(), SetTransferableError>;
+}
+
+pub mod pause_collection {
+    use super::*;
+
+    pub type Args = ();
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum PauseCollectionError {
+        ConcurrentManagementCall,
+        AlreadyPaused,
+        NotPaused,
+        StorageCanisterError(String),
+        MultisigRequired,
+    }
+    pub type Response = Result<(), PauseCollectionError>;
+}
+
+pub mod unpause_collection {
+    pub use super::pause_collection::{Args, PauseCollectionError, Response};
+}
+
 pub mod get_upload_status {
     use super::*;
 
@@ -90,7 +242,7 @@ pub mod get_all_uploads {
 pub mod update_collection_metadata {
     use super::*;
 
-    #[derive(CandidType, Serialize, Deserialize, Clone)]
+    #[derive(CandidType, Serialize, Deserialize, Clone, Default)]
     pub struct Args {
         pub description: Option<String>,
         pub symbol: Option<String>,
@@ -107,10 +259,18 @@ pub mod update_collection_metadata {
         pub permitted_drift: Option<Nat>,
         pub max_canister_storage_threshold: Option<Nat>,
         pub collection_metadata: Option<HashMap<String, CustomValue>>,
+        pub metadata_validation: Option<MetadataValidation>,
     }
     #[derive(Serialize, Deserialize, CandidType, Debug)]
     pub enum UpdateCollectionMetadataError {
         ConcurrentManagementCall,
+        MetadataFrozen,
+        InvalidMetadata(MetadataValidationError),
+        // Royalties, the transferable flag and the other reserved keys have their own endpoints.
+        ReservedMetadataKey(String),
+        MultisigRequired,
+        // Supply cap changes go through `schedule_operation` while a timelock is set.
+        TimelockRequired,
         StorageCanisterError(String),
     }
     pub type Response = Result<(), UpdateCollectionMetadataError>;
@@ -254,12 +414,16 @@ pub mod grant_permission {
     pub struct Args {
         pub principal: Principal,
         pub permission: Permission,
+        // None grants the permission until it is revoked.
+        pub expires_at: Option<u64>,
     }
     pub type Response = Result<(), GrantPermissionError>;
 
     #[derive(Serialize, Deserialize, CandidType, Debug)]
     pub enum GrantPermissionError {
         ConcurrentManagementCall,
+        InvalidExpiry,
+        MultisigRequired,
         DefaultError(String),
     }
 }
@@ -277,10 +441,104 @@ pub mod revoke_permission {
     #[derive(Serialize, Deserialize, CandidType, Debug)]
     pub enum RevokePermissionError {
         ConcurrentManagementCall,
+        MultisigRequired,
         DefaultError(String),
     }
 }
 
+pub mod set_role {
+    use super::*;
+
+    // Creates the role, or replaces the permissions of an existing one.
+    #[derive(CandidType, Serialize, Deserialize, Clone)]
+    pub struct Args {
+        pub name: String,
+        pub permissions: Vec<Permission>,
+    }
+    pub type Response = Result<(), SetRoleError>;
+
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum SetRoleError {
+        ConcurrentManagementCall,
+        InvalidRoleName,
+        MultisigRequired,
+        StorageCanisterError(String),
+    }
+}
+
+pub mod delete_role {
+    use super::*;
+
+    pub type Args = String;
+    pub type Response = Result<(), DeleteRoleError>;
+
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum DeleteRoleError {
+        ConcurrentManagementCall,
+        RoleNotFound,
+        MultisigRequired,
+        StorageCanisterError(String),
+    }
+}
+
+pub mod get_roles {
+    use super::*;
+
+    pub type Args = ();
+    pub type Response = Vec<(String, Vec<Permission>)>;
+}
+
+pub mod grant_role {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone)]
+    pub struct Args {
+        pub principal: Principal,
+        pub role: String,
+        // None grants the role until it is revoked.
+        pub expires_at: Option<u64>,
+    }
+    pub type Response = Result<(), GrantRoleError>;
+
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum GrantRoleError {
+        ConcurrentManagementCall,
+        RoleNotFound,
+        InvalidExpiry,
+        MultisigRequired,
+        StorageCanisterError(String),
+    }
+}
+
+pub mod revoke_role {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone)]
+    pub struct Args {
+        pub principal: Principal,
+        pub role: String,
+    }
+    pub type Response = Result<(), RevokeRoleError>;
+
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum RevokeRoleError {
+        ConcurrentManagementCall,
+        MultisigRequired,
+        StorageCanisterError(String),
+    }
+}
+
+pub mod list_permission_holders {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone)]
+    pub struct Args {
+        pub prev: Option<Principal>,
+        pub take: Option<Nat>,
+    }
+    pub type Response = Vec<PermissionHolder>;
+}
+
 pub mod get_user_permissions {
     use super::*;
 
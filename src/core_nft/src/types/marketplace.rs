@@ -0,0 +1,187 @@
+use crate::types::icrc37::icrc37_transfer_from;
+
+use bity_ic_types::TimestampNanos;
+use candid::{CandidType, Nat, Principal};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
+use serde::{Deserialize, Serialize};
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MarketplaceConfig {
+    // ICRC-2 ledgers listings may be priced in.
+    pub ledgers: Vec<Principal>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Listing {
+    pub token_id: Nat,
+    pub seller: Account,
+    pub price: Nat,
+    pub ledger: Principal,
+    pub expires_at: Option<u64>,
+    pub created_at: u64,
+    // Set while a buyer's payment is in flight.
+    pub pending_purchase: bool,
+}
+
+impl Listing {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+// Proceeds or refunds the canister owes but could not transfer yet.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PendingPayout {
+    pub ledger: Principal,
+    // Escrow subaccount the funds are held in, if not the canister's default account.
+    #[serde(default)]
+    pub from_subaccount: Option<Subaccount>,
+    pub to: Account,
+    pub amount: Nat,
+}
+
+pub mod set_marketplace_config {
+    use super::*;
+
+    pub type Args = Option<MarketplaceConfig>;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum SetMarketplaceConfigError {
+        ConcurrentManagementCall,
+    }
+    pub type Response = Result<(), SetMarketplaceConfigError>;
+}
+
+pub mod get_marketplace_config {
+    use super::*;
+
+    pub type Args = ();
+    pub type Response = Option<MarketplaceConfig>;
+}
+
+pub mod list_token {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub token_id: Nat,
+        pub from_subaccount: Option<Subaccount>,
+        pub price: Nat,
+        pub ledger: Principal,
+        pub expires_at: Option<u64>,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum ListTokenError {
+        MarketplaceClosed,
+        CollectionPaused,
+        TokenDoesNotExist,
+        NotTokenOwner,
+        NonTransferable,
+        UnsupportedLedger,
+        InvalidPrice,
+        InvalidExpiry,
+        PurchaseInProgress,
+    }
+    pub type Response = Result<(), ListTokenError>;
+}
+
+pub mod cancel_listing {
+    use super::*;
+
+    pub type Args = Nat;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum CancelListingError {
+        ListingNotFound,
+        NotSeller,
+        PurchaseInProgress,
+    }
+    pub type Response = Result<(), CancelListingError>;
+}
+
+pub mod buy_token {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub token_id: Nat,
+        pub from_subaccount: Option<Subaccount>,
+        // Must match the listing, so a buyer never pays more than they agreed to.
+        pub price: Nat,
+        pub ledger: Principal,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum BuyTokenError {
+        MarketplaceClosed,
+        CollectionPaused,
+        AnonymousCaller,
+        ListingNotFound,
+        ListingExpired,
+        PurchaseInProgress,
+        CannotBuyOwnListing,
+        PriceMismatch { price: Nat, ledger: Principal },
+        PaymentFailed(TransferFromError),
+        LedgerCallFailed(String),
+        // The payment went through but the listing stopped being valid before the token moved.
+        ListingInvalidated { refund_block: Option<Nat> },
+    }
+    // Index of the 7xfer block moving the token to the buyer.
+    pub type Response = Result<Nat, BuyTokenError>;
+}
+
+pub mod get_listing {
+    use super::*;
+
+    pub type Args = Nat;
+    pub type Response = Option<Listing>;
+}
+
+pub mod get_listings {
+    use super::*;
+
+    pub type Args0 = Option<Nat>;
+    pub type Args1 = Option<Nat>;
+    pub type Response = Vec<Listing>;
+}
+
+pub mod settle_marketplace_payouts {
+    use super::*;
+
+    pub type Args = ();
+    // Payouts that still failed are kept for the next call.
+    pub type Response = Vec<PendingPayout>;
+}
+
+pub mod sale_transfer_from {
+    use super::*;
+
+    // An ICRC-37 transfer_from that also settles the sale: the price is pulled from the spender
+    // through ICRC-2, royalties are paid out and the seller receives the rest.
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub spender_subaccount: Option<Subaccount>,
+        pub from: Account,
+        pub to: Account,
+        pub token_id: Nat,
+        pub memo: Option<serde_bytes::ByteBuf>,
+        pub created_at_time: Option<TimestampNanos>,
+        pub price: Nat,
+        pub ledger: Principal,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum SaleTransferFromError {
+        CollectionPaused,
+        InvalidPrice,
+        UnsupportedLedger,
+        // Rejected before any payment was taken.
+        InvalidTransfer(icrc37_transfer_from::TransferFromError),
+        PaymentFailed(TransferFromError),
+        LedgerCallFailed(String),
+        // The payment went through but the token could not be moved, so it was refunded.
+        TransferFailed {
+            error: icrc37_transfer_from::TransferFromError,
+            refund_block: Option<Nat>,
+        },
+    }
+    // Index of the 37xfer block moving the token.
+    pub type Response = Result<Nat, SaleTransferFromError>;
+}
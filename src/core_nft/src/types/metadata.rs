@@ -1,14 +1,16 @@
 use crate::memory::VM;
+use crate::types::royalties::{ROYALTIES_METADATA_KEY, ROYALTY_ENFORCEMENT_METADATA_KEY};
 use crate::types::value_custom::CustomValue as Value;
 use crate::types::wrapped_types::WrappedNat;
 use crate::{memory::get_metadata_memory, utils::trace};
 
 use candid::{CandidType, Nat};
 use ic_stable_structures::{storable::Bound, StableBTreeMap, Storable};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
 use minicbor::{decode, encode, Decode as MinicborDecode, Encode as MinicborEncode};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 thread_local! {
     pub static __METADATA: std::cell::RefCell<Metadata> = std::cell::RefCell::new(init_metadata());
@@ -214,12 +216,18 @@ impl Metadata {
 
     pub fn delete_data(&mut self, nft_id: Option<Nat>, data_id: String) {
         trace(&format!("Deleting data: {:?}", data_id));
-        let mut metadata_data = self
-            .data
-            .get(&WrappedNat(nft_id.unwrap_or(Nat::from(0u64))))
-            .unwrap();
+        let nat_wrapper = WrappedNat(nft_id.unwrap_or(Nat::from(0u64)));
+
+        if let Some(mut metadata_data) = self.data.get(&nat_wrapper) {
+            metadata_data.data.remove(&data_id);
+            self.data.insert(nat_wrapper, metadata_data);
+        }
+    }
 
-        metadata_data.data.remove(&data_id);
+    pub fn delete_all_data(&mut self, nft_id: Option<Nat>) {
+        trace(&format!("Deleting all data for nft: {:?}", nft_id));
+        self.data
+            .remove(&WrappedNat(nft_id.unwrap_or(Nat::from(0u64))));
     }
 
     pub fn replace_all_data(&mut self, nft_id: Option<Nat>, datas: BTreeMap<String, Value>) {
@@ -232,3 +240,266 @@ impl Metadata {
         }
     }
 }
+
+pub const FROZEN_KEYS_METADATA_KEY: &str = "icrc7:frozen_keys";
+pub const FROZEN_ALL_KEYS: &str = "*";
+
+// Locks are irreversible: nothing in here is ever removed except for burned tokens.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct FrozenMetadata {
+    pub collection: bool,
+    pub tokens: BTreeSet<Nat>,
+    pub token_keys: BTreeMap<Nat, BTreeSet<String>>,
+}
+
+impl FrozenMetadata {
+    pub fn is_token_frozen(&self, token_id: &Nat) -> bool {
+        self.collection || self.tokens.contains(token_id)
+    }
+
+    pub fn is_key_frozen(&self, token_id: &Nat, key: &str) -> bool {
+        self.is_token_frozen(token_id)
+            || self
+                .token_keys
+                .get(token_id)
+                .is_some_and(|keys| keys.contains(key))
+    }
+
+    // A whole-token lock is reported as the single wildcard key.
+    pub fn frozen_keys(&self, token_id: &Nat) -> Vec<String> {
+        if self.is_token_frozen(token_id) {
+            return vec![FROZEN_ALL_KEYS.to_string()];
+        }
+
+        self.token_keys
+            .get(token_id)
+            .map(|keys| keys.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn remove_token(&mut self, token_id: &Nat) {
+        self.tokens.remove(token_id);
+        self.token_keys.remove(token_id);
+    }
+}
+
+pub const ICRC97_METADATA_KEY: &str = "icrc97:metadata";
+pub const TRANSFERABLE_METADATA_KEY: &str = "icrc7:transferable";
+pub const PAUSED_METADATA_KEY: &str = "icrc7:paused";
+
+// Keys only their dedicated endpoints write, or that are derived from the canister state.
+// Generic metadata writes can neither set nor unset them.
+pub const RESERVED_METADATA_KEYS: [&str; 5] = [
+    ROYALTIES_METADATA_KEY,
+    ROYALTY_ENFORCEMENT_METADATA_KEY,
+    TRANSFERABLE_METADATA_KEY,
+    FROZEN_KEYS_METADATA_KEY,
+    PAUSED_METADATA_KEY,
+];
+
+pub fn is_reserved_metadata_key(key: &str) -> bool {
+    RESERVED_METADATA_KEYS.contains(&key)
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetadataValueType {
+    Text,
+    Nat,
+    Int,
+    Blob,
+    Array,
+    Map,
+}
+
+impl MetadataValueType {
+    pub fn of(value: &ICRC3Value) -> Self {
+        match value {
+            ICRC3Value::Text(_) => MetadataValueType::Text,
+            ICRC3Value::Nat(_) => MetadataValueType::Nat,
+            ICRC3Value::Int(_) => MetadataValueType::Int,
+            ICRC3Value::Blob(_) => MetadataValueType::Blob,
+            ICRC3Value::Array(_) => MetadataValueType::Array,
+            ICRC3Value::Map(_) => MetadataValueType::Map,
+        }
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MetadataKeyRule {
+    pub value_type: Option<MetadataValueType>,
+    pub max_size: Option<Nat>,
+}
+
+// The default configuration accepts any metadata, which keeps validation opt-in.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MetadataValidation {
+    pub icrc97: bool,
+    pub max_value_size: Option<Nat>,
+    pub key_rules: HashMap<String, MetadataKeyRule>,
+}
+
+impl MetadataValidation {
+    // Encoding used by `coll_update` blocks.
+    pub fn to_value(&self) -> ICRC3Value {
+        let mut value = BTreeMap::new();
+        value.insert(
+            "icrc97".to_string(),
+            ICRC3Value::Text(self.icrc97.to_string()),
+        );
+        if let Some(max_value_size) = &self.max_value_size {
+            value.insert(
+                "max_value_size".to_string(),
+                ICRC3Value::Nat(max_value_size.clone()),
+            );
+        }
+        let key_rules = self
+            .key_rules
+            .iter()
+            .map(|(key, rule)| {
+                let mut rule_value = BTreeMap::new();
+                if let Some(value_type) = &rule.value_type {
+                    rule_value.insert(
+                        "value_type".to_string(),
+                        ICRC3Value::Text(format!("{:?}", value_type)),
+                    );
+                }
+                if let Some(max_size) = &rule.max_size {
+                    rule_value.insert("max_size".to_string(), ICRC3Value::Nat(max_size.clone()));
+                }
+                (key.clone(), ICRC3Value::Map(rule_value))
+            })
+            .collect();
+        value.insert("key_rules".to_string(), ICRC3Value::Map(key_rules));
+        ICRC3Value::Map(value)
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum MetadataValidationError {
+    InvalidIcrc97Metadata {
+        key: String,
+        reason: String,
+    },
+    InvalidValueType {
+        key: String,
+        expected: MetadataValueType,
+    },
+    ValueTooLarge {
+        key: String,
+        max_size: Nat,
+    },
+}
+
+impl MetadataValidation {
+    pub fn validate(
+        &self,
+        metadata: &[(String, ICRC3Value)],
+    ) -> Result<(), MetadataValidationError> {
+        for (key, value) in metadata {
+            self.validate_entry(key, value)?;
+        }
+        Ok(())
+    }
+
+    pub fn validate_entry(
+        &self,
+        key: &str,
+        value: &ICRC3Value,
+    ) -> Result<(), MetadataValidationError> {
+        let rule = self.key_rules.get(key);
+
+        if let Some(expected) = rule.and_then(|rule| rule.value_type) {
+            if MetadataValueType::of(value) != expected {
+                return Err(MetadataValidationError::InvalidValueType {
+                    key: key.to_string(),
+                    expected,
+                });
+            }
+        }
+
+        let max_size = rule
+            .and_then(|rule| rule.max_size.clone())
+            .or(self.max_value_size.clone());
+        if let Some(max_size) = max_size {
+            if max_size < value_size(value) {
+                return Err(MetadataValidationError::ValueTooLarge {
+                    key: key.to_string(),
+                    max_size,
+                });
+            }
+        }
+
+        if self.icrc97 {
+            validate_icrc97_entry(key, value).map_err(|reason| {
+                MetadataValidationError::InvalidIcrc97Metadata {
+                    key: key.to_string(),
+                    reason,
+                }
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+// Approximate encoded size: raw bytes for leaves, plus key lengths for maps.
+pub fn value_size(value: &ICRC3Value) -> usize {
+    match value {
+        ICRC3Value::Text(text) => text.len(),
+        ICRC3Value::Blob(blob) => blob.len(),
+        ICRC3Value::Nat(nat) => nat.0.to_bytes_le().len(),
+        ICRC3Value::Int(int) => int.0.to_signed_bytes_le().len(),
+        ICRC3Value::Array(values) => values.iter().map(value_size).sum(),
+        ICRC3Value::Map(map) => map.iter().map(|(k, v)| k.len() + value_size(v)).sum(),
+    }
+}
+
+// Mirrors the checks the cmdline runs on ICRC-97 JSON documents, applied to on-chain values.
+fn validate_icrc97_entry(key: &str, value: &ICRC3Value) -> Result<(), String> {
+    match key {
+        ICRC97_METADATA_KEY => {
+            let ICRC3Value::Array(urls) = value else {
+                return Err("must be an array of URLs".to_string());
+            };
+            if urls.is_empty() {
+                return Err("must contain at least one URL".to_string());
+            }
+            for url in urls {
+                let ICRC3Value::Text(url) = url else {
+                    return Err("must be an array of URLs".to_string());
+                };
+                url::Url::parse(url).map_err(|_| format!("invalid URL: {}", url))?;
+            }
+            Ok(())
+        }
+        "name" | "description" | "image" | "external_url" => match value {
+            ICRC3Value::Text(_) => Ok(()),
+            _ => Err("must be a text value".to_string()),
+        },
+        "attributes" => {
+            let ICRC3Value::Array(attributes) = value else {
+                return Err("must be an array".to_string());
+            };
+            for (i, attribute) in attributes.iter().enumerate() {
+                let ICRC3Value::Map(attribute) = attribute else {
+                    return Err(format!("attribute {} must be a map", i));
+                };
+                match attribute.get("trait_type") {
+                    Some(ICRC3Value::Text(_)) => {}
+                    Some(_) => return Err(format!("attribute {} 'trait_type' must be a text", i)),
+                    None => return Err(format!("attribute {} must have 'trait_type'", i)),
+                }
+                if !attribute.contains_key("value") {
+                    return Err(format!("attribute {} must have 'value'", i));
+                }
+                if let Some(display_type) = attribute.get("display_type") {
+                    if !matches!(display_type, ICRC3Value::Text(_)) {
+                        return Err(format!("attribute {} 'display_type' must be a text", i));
+                    }
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
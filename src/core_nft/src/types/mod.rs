@@ -6,10 +6,20 @@ pub mod icrc3;
 pub mod icrc37;
 pub mod icrc7;
 pub mod management;
+pub mod marketplace;
 pub mod metadata;
+pub mod multisig;
+pub mod nest;
 pub mod nft;
+pub mod offer;
 pub mod permissions;
+pub mod public_mint;
+pub mod reveal;
+pub mod royalties;
 pub mod sub_canister;
+pub mod timelock;
+pub mod token_account;
+pub mod user;
 pub mod value_custom;
 pub mod wrapped_types;
 
@@ -20,9 +30,19 @@ pub use icrc3::*;
 pub use icrc37::*;
 pub use icrc7::*;
 pub use management::*;
+pub use marketplace::*;
 pub use metadata::*;
+pub use multisig::*;
+pub use nest::*;
 pub use nft::*;
+pub use offer::*;
 pub use permissions::*;
+pub use public_mint::*;
+pub use reveal::*;
+pub use royalties::*;
 pub use sub_canister::*;
+pub use timelock::*;
+pub use token_account::*;
+pub use user::*;
 pub use value_custom::*;
 pub use wrapped_types::*;
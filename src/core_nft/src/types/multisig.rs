@@ -0,0 +1,181 @@
+use crate::types::management::{
+    delete_role, grant_permission, grant_role, revoke_permission, revoke_role, set_role,
+    update_collection_metadata,
+};
+use crate::types::timelock::TimelockedOperation;
+
+use candid::{CandidType, Nat, Principal};
+use serde::{Deserialize, Serialize};
+
+// While set, the management calls covered by `ProposalAction` only run once `threshold` of the
+// `signers` approve a proposal for them.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MultisigConfig {
+    pub signers: Vec<Principal>,
+    pub threshold: u32,
+    // Nanoseconds a proposal stays open for approvals.
+    pub proposal_duration: u64,
+}
+
+impl MultisigConfig {
+    pub fn is_valid(&self) -> bool {
+        let mut signers = self.signers.clone();
+        signers.sort();
+        signers.dedup();
+        signers.len() == self.signers.len()
+            && self.threshold > 0
+            && self.threshold as usize <= self.signers.len()
+            && self.proposal_duration > 0
+    }
+
+    pub fn is_signer(&self, principal: &Principal) -> bool {
+        self.signers.contains(principal)
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub enum ProposalAction {
+    GrantPermission(grant_permission::Args),
+    RevokePermission(revoke_permission::Args),
+    GrantRole(grant_role::Args),
+    RevokeRole(revoke_role::Args),
+    SetRole(set_role::Args),
+    DeleteRole(delete_role::Args),
+    UpdateCollectionMetadata(Box<update_collection_metadata::Args>),
+    // Upgrades every storage canister to the storage wasm bundled with this canister.
+    UpgradeStorageCanisters,
+    PauseCollection,
+    UnpauseCollection,
+    // None turns multisig off.
+    SetMultisigConfig(Option<MultisigConfig>),
+    // Queues the operation as `schedule_operation` would.
+    ScheduleOperation(TimelockedOperation),
+}
+
+impl ProposalAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProposalAction::GrantPermission(_) => "grant_permission",
+            ProposalAction::RevokePermission(_) => "revoke_permission",
+            ProposalAction::GrantRole(_) => "grant_role",
+            ProposalAction::RevokeRole(_) => "revoke_role",
+            ProposalAction::SetRole(_) => "set_role",
+            ProposalAction::DeleteRole(_) => "delete_role",
+            ProposalAction::UpdateCollectionMetadata(_) => "update_collection_metadata",
+            ProposalAction::UpgradeStorageCanisters => "upgrade_storage_canisters",
+            ProposalAction::PauseCollection => "pause_collection",
+            ProposalAction::UnpauseCollection => "unpause_collection",
+            ProposalAction::SetMultisigConfig(_) => "set_multisig_config",
+            ProposalAction::ScheduleOperation(_) => "schedule_operation",
+        }
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ProposalStatus {
+    Open,
+    // Reached the threshold and is being executed.
+    Approved,
+    Executed,
+    Failed(String),
+    Expired,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub struct Proposal {
+    pub proposal_id: Nat,
+    pub action: ProposalAction,
+    pub proposer: Principal,
+    pub approvals: Vec<Principal>,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub status: ProposalStatus,
+}
+
+impl Proposal {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at <= now
+    }
+
+    // Approvals from principals that are no longer signers do not count.
+    pub fn approval_count(&self, config: &MultisigConfig) -> usize {
+        self.approvals
+            .iter()
+            .filter(|signer| config.is_signer(signer))
+            .count()
+    }
+}
+
+pub mod set_multisig_config {
+    use super::*;
+
+    pub type Args = MultisigConfig;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum SetMultisigConfigError {
+        ConcurrentManagementCall,
+        InvalidConfig,
+        // Once multisig is on, its config can only change through a proposal.
+        MultisigRequired,
+    }
+    pub type Response = Result<(), SetMultisigConfigError>;
+}
+
+pub mod get_multisig_config {
+    use super::*;
+
+    pub type Args = ();
+    pub type Response = Option<MultisigConfig>;
+}
+
+pub mod create_proposal {
+    use super::*;
+
+    pub type Args = ProposalAction;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum CreateProposalError {
+        ConcurrentManagementCall,
+        MultisigNotConfigured,
+        NotSigner,
+        InvalidAction(String),
+        StorageCanisterError(String),
+    }
+    // Id of the new proposal. It is executed right away when the threshold is one.
+    pub type Response = Result<Nat, CreateProposalError>;
+}
+
+pub mod approve_proposal {
+    use super::*;
+
+    pub type Args = Nat;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum ApproveProposalError {
+        ConcurrentManagementCall,
+        MultisigNotConfigured,
+        NotSigner,
+        ProposalNotFound,
+        ProposalNotOpen,
+        ProposalExpired,
+        AlreadyApproved,
+        StorageCanisterError(String),
+    }
+    // Status of the proposal after the approval.
+    pub type Response = Result<ProposalStatus, ApproveProposalError>;
+}
+
+pub mod get_proposal {
+    use super::*;
+
+    pub type Args = Nat;
+    pub type Response = Option<Proposal>;
+}
+
+pub mod list_proposals {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub prev: Option<Nat>,
+        pub take: Option<Nat>,
+    }
+    pub type Response = Vec<Proposal>;
+}
@@ -0,0 +1,99 @@
+use candid::{CandidType, Nat, Principal};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use serde::{Deserialize, Serialize};
+
+// Bounds the parent chain walked when resolving root owners and detecting cycles.
+pub const MAX_NESTING_DEPTH: usize = 16;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum NestingError {
+    TokenDoesNotExist,
+    // The parent chain is longer than MAX_NESTING_DEPTH.
+    MaxDepthExceeded,
+}
+
+// A token attached to a parent token, from this collection or another ICRC-7 collection.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChildToken {
+    pub collection: Principal,
+    pub token_id: Nat,
+}
+
+pub mod attach_child {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub parent_id: Nat,
+        pub child: ChildToken,
+        // Subaccount holding the parent (or its root) and, for children of this collection,
+        // the child.
+        pub from_subaccount: Option<Subaccount>,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum AttachChildError {
+        CollectionPaused,
+        TokenDoesNotExist,
+        NotTokenOwner,
+        NonTransferable,
+        AlreadyAttached,
+        // The child is the parent itself or one of its ancestors.
+        CycleDetected,
+        MaxDepthExceeded,
+        // A child from another collection must be transferred to the parent's token account
+        // before it is attached.
+        ChildNotHeldByParent,
+        CollectionCallFailed(String),
+        StorageCanisterError(String),
+    }
+    // Index of the nest_attach block.
+    pub type Response = Result<Nat, AttachChildError>;
+}
+
+pub mod detach_child {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub parent_id: Nat,
+        pub child: ChildToken,
+        pub from_subaccount: Option<Subaccount>,
+        // Defaults to the caller's account.
+        pub to: Option<Account>,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum DetachChildError {
+        CollectionPaused,
+        TokenDoesNotExist,
+        NotTokenOwner,
+        MaxDepthExceeded,
+        ChildNotFound,
+        InvalidRecipient,
+        TransferFailed(String),
+        CollectionCallFailed(String),
+        StorageCanisterError(String),
+    }
+    // Index of the nest_detach block.
+    pub type Response = Result<Nat, DetachChildError>;
+}
+
+pub mod get_children {
+    use super::*;
+
+    pub type Args = Nat;
+    pub type Response = Vec<ChildToken>;
+}
+
+pub mod get_parent {
+    use super::*;
+
+    pub type Args = Nat;
+    pub type Response = Option<Nat>;
+}
+
+pub mod icrc7_root_owner_of {
+    use super::*;
+
+    pub type Args = Vec<Nat>;
+    pub type Response = Vec<Option<Account>>;
+}
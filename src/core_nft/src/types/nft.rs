@@ -1,11 +1,186 @@
+use crate::memory::{
+    get_burned_tokens_memory, get_owner_balances_memory, get_owner_tokens_memory,
+    get_token_children_memory, get_token_parents_memory, get_token_users_memory, get_tokens_memory,
+    VM,
+};
+use crate::types::wrapped_types::{WrappedAccount, WrappedChildTokens, WrappedNat};
 use crate::types::Metadata;
 use crate::utils::trace;
 
 use crate::types::value_custom::CustomValue as Value;
+use bity_ic_types::TimestampNanos;
 use candid::{CandidType, Nat};
+use ic_stable_structures::{storable::Bound, StableBTreeMap, Storable};
 use icrc_ledger_types::icrc::generic_value::ICRC3Value as Icrc3Value;
-use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use minicbor::{Decode, Encode};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+
+thread_local! {
+    pub static __TOKENS: std::cell::RefCell<Tokens> = std::cell::RefCell::new(init_tokens());
+    pub static __OWNER_TOKENS: std::cell::RefCell<OwnerTokens> = std::cell::RefCell::new(init_owner_tokens());
+    pub static __OWNER_BALANCES: std::cell::RefCell<OwnerBalances> = std::cell::RefCell::new(init_owner_balances());
+    pub static __BURNED_TOKENS: std::cell::RefCell<BurnedTokens> = std::cell::RefCell::new(init_burned_tokens());
+    pub static __TOKEN_USERS: std::cell::RefCell<TokenUsers> = std::cell::RefCell::new(init_token_users());
+    pub static __TOKEN_CHILDREN: std::cell::RefCell<TokenChildren> = std::cell::RefCell::new(init_token_children());
+    pub static __TOKEN_PARENTS: std::cell::RefCell<TokenParents> = std::cell::RefCell::new(init_token_parents());
+}
+
+// Map to store the token ledger: token_id -> owner
+pub type Tokens = StableBTreeMap<WrappedNat, WrappedAccount, VM>;
+
+pub fn init_tokens() -> Tokens {
+    let memory = get_tokens_memory();
+    StableBTreeMap::init(memory)
+}
+
+// Ordered by owner, then token id, so an owner's tokens are one contiguous range.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OwnerTokenKey {
+    #[n(0)]
+    pub owner: WrappedAccount,
+    #[n(1)]
+    pub token_id: WrappedNat,
+}
+
+impl OwnerTokenKey {
+    pub fn new(owner: &Account, token_id: &Nat) -> Self {
+        Self {
+            owner: WrappedAccount(*owner),
+            token_id: WrappedNat(token_id.clone()),
+        }
+    }
+}
+
+impl Storable for OwnerTokenKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut buffer = Vec::new();
+        minicbor::encode(self, &mut buffer).expect("failed to encode OwnerTokenKey");
+        Cow::Owned(buffer)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        minicbor::decode(&bytes).expect("failed to decode OwnerTokenKey")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Set to store the owner index: (owner, token_id)
+pub type OwnerTokens = StableBTreeMap<OwnerTokenKey, (), VM>;
+
+pub fn init_owner_tokens() -> OwnerTokens {
+    let memory = get_owner_tokens_memory();
+    StableBTreeMap::init(memory)
+}
+
+// Map to store token counts: owner -> balance
+pub type OwnerBalances = StableBTreeMap<WrappedAccount, u64, VM>;
+
+pub fn init_owner_balances() -> OwnerBalances {
+    let memory = get_owner_balances_memory();
+    StableBTreeMap::init(memory)
+}
+
+// Map to store the nesting index: parent token_id -> attached children
+pub type TokenChildren = StableBTreeMap<WrappedNat, WrappedChildTokens, VM>;
+
+pub fn init_token_children() -> TokenChildren {
+    let memory = get_token_children_memory();
+    StableBTreeMap::init(memory)
+}
+
+// Map to store the reverse nesting index for children of this collection: token_id -> parent
+pub type TokenParents = StableBTreeMap<WrappedNat, WrappedNat, VM>;
+
+pub fn init_token_parents() -> TokenParents {
+    let memory = get_token_parents_memory();
+    StableBTreeMap::init(memory)
+}
+
+// Subaccount of the collection canister that belongs to a token. It holds the token's children
+// and ICRC-1 balances, so they follow the token across transfers.
+pub fn token_subaccount(token_id: &Nat) -> Subaccount {
+    let mut hasher = Sha256::new();
+    hasher.update(b"token");
+    hasher.update(token_id.0.to_bytes_be());
+    hasher.finalize().into()
+}
+
+#[derive(Encode, Decode, CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BurnedToken {
+    #[n(0)]
+    pub from: WrappedAccount,
+    #[n(1)]
+    pub memo: Option<Vec<u8>>,
+    #[n(2)]
+    pub created_at_time: TimestampNanos,
+    #[n(3)]
+    pub burned_at: TimestampNanos,
+    #[n(4)]
+    pub transaction_id: u64,
+}
+
+impl Storable for BurnedToken {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut buffer = Vec::new();
+        minicbor::encode(self, &mut buffer).expect("failed to encode BurnedToken");
+        Cow::Owned(buffer)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        minicbor::decode(&bytes).expect("failed to decode BurnedToken")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Map to store burned tokens: token_id -> burn record
+pub type BurnedTokens = StableBTreeMap<WrappedNat, BurnedToken, VM>;
+
+pub fn init_burned_tokens() -> BurnedTokens {
+    let memory = get_burned_tokens_memory();
+    StableBTreeMap::init(memory)
+}
+
+// Account allowed to use a token without owning it, until expires_at.
+#[derive(Encode, Decode, CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct TokenUser {
+    #[n(0)]
+    pub user: WrappedAccount,
+    #[n(1)]
+    pub expires_at: TimestampNanos,
+}
+
+impl TokenUser {
+    pub fn is_expired(&self, now: TimestampNanos) -> bool {
+        self.expires_at <= now
+    }
+}
+
+impl Storable for TokenUser {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut buffer = Vec::new();
+        minicbor::encode(self, &mut buffer).expect("failed to encode TokenUser");
+        Cow::Owned(buffer)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        minicbor::decode(&bytes).expect("failed to decode TokenUser")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Map to store the user role: token_id -> user
+pub type TokenUsers = StableBTreeMap<WrappedNat, TokenUser, VM>;
+
+pub fn init_token_users() -> TokenUsers {
+    let memory = get_token_users_memory();
+    StableBTreeMap::init(memory)
+}
 
 #[derive(CandidType, Serialize, Deserialize, Clone)]
 pub struct Icrc7Token {
@@ -76,33 +251,27 @@ impl Icrc7Token {
         trace(&format!("nft replace_metadata - finished"));
     }
 
-    pub async fn remove_metadata(&mut self, tokens_metadata: &mut Metadata) {
+    pub fn remove_metadata(&mut self, tokens_metadata: &mut Metadata, keys: Vec<String>) {
         trace(&format!("nft remove_metadata"));
 
-        tokens_metadata.delete_data(Some(self.token_id.clone()), "metadata".into());
+        for key in keys {
+            tokens_metadata.delete_data(Some(self.token_id.clone()), key);
+        }
 
         trace(&format!("nft remove_metadata - finished"));
     }
 
-    pub async fn update_metadata(
+    pub fn update_metadata(
         &mut self,
         tokens_metadata: &mut Metadata,
         metadata: Icrc7TokenMetadata,
-    ) -> Result<Option<Icrc3Value>, String> {
+    ) {
         trace(&format!("nft update_metadata"));
 
-        for (key, value) in metadata.iter() {
-            tokens_metadata
-                .update_data(
-                    Some(self.token_id.clone()),
-                    key.clone(),
-                    Value(value.clone()),
-                )
-                .unwrap();
+        for (key, value) in metadata {
+            tokens_metadata.insert_data(Some(self.token_id.clone()), key, Value(value));
         }
 
         trace(&format!("nft update_metadata - finished"));
-
-        Ok(None)
     }
 }
@@ -0,0 +1,113 @@
+use candid::{CandidType, Nat, Principal};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Offer {
+    pub offer_id: Nat,
+    pub token_id: Nat,
+    pub buyer: Account,
+    // Amount held in escrow; the seller receives it minus the ledger fee.
+    pub amount: Nat,
+    pub ledger: Principal,
+    pub expires_at: u64,
+    pub created_at: u64,
+}
+
+impl Offer {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at <= now
+    }
+
+    pub fn escrow_subaccount(&self) -> Subaccount {
+        offer_escrow_subaccount(&self.offer_id)
+    }
+}
+
+// Each offer is escrowed in its own subaccount of the collection canister.
+pub fn offer_escrow_subaccount(offer_id: &Nat) -> Subaccount {
+    let mut hasher = Sha256::new();
+    hasher.update(b"offer");
+    hasher.update(offer_id.0.to_bytes_be());
+    hasher.finalize().into()
+}
+
+pub mod make_offer {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub token_id: Nat,
+        pub from_subaccount: Option<Subaccount>,
+        pub amount: Nat,
+        pub ledger: Principal,
+        pub expires_at: u64,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum MakeOfferError {
+        MarketplaceClosed,
+        CollectionPaused,
+        AnonymousCaller,
+        TokenDoesNotExist,
+        NonTransferable,
+        AlreadyTokenOwner,
+        UnsupportedLedger,
+        InvalidAmount,
+        InvalidExpiry,
+        PaymentFailed(TransferFromError),
+        LedgerCallFailed(String),
+    }
+    // Id of the new offer.
+    pub type Response = Result<Nat, MakeOfferError>;
+}
+
+pub mod cancel_offer {
+    use super::*;
+
+    pub type Args = Nat;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum CancelOfferError {
+        OfferNotFound,
+        NotBuyer,
+    }
+    // Index of the refund transfer, if it went through right away.
+    pub type Response = Result<Option<Nat>, CancelOfferError>;
+}
+
+pub mod accept_offer {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub offer_id: Nat,
+        pub from_subaccount: Option<Subaccount>,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum AcceptOfferError {
+        CollectionPaused,
+        OfferNotFound,
+        OfferExpired,
+        TokenDoesNotExist,
+        NotTokenOwner,
+        NonTransferable,
+        TransferFailed(String),
+    }
+    // Index of the 7xfer block moving the token to the buyer.
+    pub type Response = Result<Nat, AcceptOfferError>;
+}
+
+pub mod get_offer {
+    use super::*;
+
+    pub type Args = Nat;
+    pub type Response = Option<Offer>;
+}
+
+pub mod get_token_offers {
+    use super::*;
+
+    pub type Args = Nat;
+    pub type Response = Vec<Offer>;
+}
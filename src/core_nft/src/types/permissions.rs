@@ -1,8 +1,13 @@
-use candid::{CandidType, Principal};
+use bity_ic_types::TimestampNanos;
+use candid::{CandidType, Nat, Principal};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
 
-#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(
+    CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
 pub enum Permission {
     Minting,
     ManageAuthorities,
@@ -10,8 +15,61 @@ pub enum Permission {
     UpdateCollectionMetadata,
     ReadUploads,
     UpdateUploads,
+    Burning,
+    UpdateRoyalties,
+    Pause,
 }
 
+impl Permission {
+    pub fn to_value(&self) -> ICRC3Value {
+        ICRC3Value::Text(format!("{:?}", self))
+    }
+}
+
+// What a grant gives its holder: a single permission, or every permission of a named role.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GrantTarget {
+    Permission(Permission),
+    Role(String),
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Grant {
+    pub target: GrantTarget,
+    // None for grants that never expire.
+    pub expires_at: Option<TimestampNanos>,
+}
+
+impl Grant {
+    pub fn is_expired(&self, now: TimestampNanos) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    // Encoding used by the `perm_grant` and `perm_revoke` blocks.
+    pub fn to_value(&self) -> ICRC3Value {
+        let mut value = BTreeMap::new();
+        match &self.target {
+            GrantTarget::Permission(permission) => {
+                value.insert("perm".to_string(), permission.to_value());
+            }
+            GrantTarget::Role(role) => {
+                value.insert("role".to_string(), ICRC3Value::Text(role.clone()));
+            }
+        }
+        if let Some(expires_at) = self.expires_at {
+            value.insert("exp".to_string(), ICRC3Value::Nat(Nat::from(expires_at)));
+        }
+        ICRC3Value::Map(value)
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PermissionHolder {
+    pub principal: Principal,
+    pub grants: Vec<Grant>,
+}
+
+// Initial permissions, as passed in the init args.
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
 pub struct PermissionManager {
     pub user_permissions: HashMap<Principal, Vec<Permission>>,
@@ -23,32 +81,189 @@ impl PermissionManager {
     }
 
     pub fn default() -> Self {
-        Self {
-            user_permissions: HashMap::new(),
+        Self::new(HashMap::new())
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PermissionRegistry {
+    // Only set by snapshots taken before grants existed; `migrate_user_permissions` folds it
+    // into `grants`.
+    #[serde(default)]
+    pub user_permissions: HashMap<Principal, Vec<Permission>>,
+    #[serde(default)]
+    pub roles: BTreeMap<String, Vec<Permission>>,
+    #[serde(default)]
+    pub grants: BTreeMap<Principal, Vec<Grant>>,
+}
+
+impl From<PermissionManager> for PermissionRegistry {
+    fn from(manager: PermissionManager) -> Self {
+        let mut registry = Self {
+            user_permissions: manager.user_permissions,
+            ..Default::default()
+        };
+        registry.migrate_user_permissions();
+        registry
+    }
+}
+
+impl PermissionRegistry {
+    pub fn migrate_user_permissions(&mut self) {
+        for (principal, permissions) in std::mem::take(&mut self.user_permissions) {
+            for permission in permissions {
+                self.grant(principal, GrantTarget::Permission(permission), None);
+            }
         }
     }
 
-    pub fn get_permissions(&self, principal: &Principal) -> Option<&Vec<Permission>> {
-        self.user_permissions.get(principal)
+    // Permissions the principal holds right now, directly or through its roles.
+    pub fn get_permissions(&self, principal: &Principal, now: TimestampNanos) -> Vec<Permission> {
+        let mut permissions: Vec<Permission> = self
+            .grants
+            .get(principal)
+            .into_iter()
+            .flatten()
+            .filter(|grant| !grant.is_expired(now))
+            .flat_map(|grant| match &grant.target {
+                GrantTarget::Permission(permission) => vec![permission.clone()],
+                GrantTarget::Role(role) => self.roles.get(role).cloned().unwrap_or_default(),
+            })
+            .collect();
+        permissions.sort();
+        permissions.dedup();
+        permissions
     }
 
-    pub fn has_permission(&self, principal: &Principal, permission: &Permission) -> bool {
-        self.user_permissions
+    pub fn has_permission(
+        &self,
+        principal: &Principal,
+        permission: &Permission,
+        now: TimestampNanos,
+    ) -> bool {
+        self.grants
             .get(principal)
-            .map(|permissions| permissions.contains(permission))
-            .unwrap_or(false)
+            .into_iter()
+            .flatten()
+            .filter(|grant| !grant.is_expired(now))
+            .any(|grant| match &grant.target {
+                GrantTarget::Permission(p) => p == permission,
+                GrantTarget::Role(role) => self
+                    .roles
+                    .get(role)
+                    .is_some_and(|permissions| permissions.contains(permission)),
+            })
+    }
+
+    // Principals that currently hold `permission`.
+    pub fn holders_of(&self, permission: &Permission, now: TimestampNanos) -> Vec<Principal> {
+        self.grants
+            .keys()
+            .filter(|principal| self.has_permission(principal, permission, now))
+            .cloned()
+            .collect()
+    }
+
+    pub fn grant_of(&self, principal: &Principal, target: &GrantTarget) -> Option<Grant> {
+        self.grants
+            .get(principal)?
+            .iter()
+            .find(|grant| grant.target == *target)
+            .cloned()
+    }
+
+    // Granting a target the principal already holds only updates its expiry. Returns the grant
+    // it replaced.
+    pub fn grant(
+        &mut self,
+        principal: Principal,
+        target: GrantTarget,
+        expires_at: Option<TimestampNanos>,
+    ) -> Option<Grant> {
+        let grants = self.grants.entry(principal).or_default();
+        let grant = Grant { target, expires_at };
+        match grants.iter_mut().find(|g| g.target == grant.target) {
+            Some(existing) => Some(std::mem::replace(existing, grant)),
+            None => {
+                grants.push(grant);
+                None
+            }
+        }
+    }
+
+    // Returns the revoked grant, or None if the principal did not hold the target.
+    pub fn revoke(&mut self, principal: &Principal, target: &GrantTarget) -> Option<Grant> {
+        let grants = self.grants.get_mut(principal)?;
+        let index = grants.iter().position(|g| g.target == *target)?;
+        let revoked = grants.remove(index);
+        if grants.is_empty() {
+            self.grants.remove(principal);
+        }
+        Some(revoked)
     }
 
     pub fn grant_permission(&mut self, principal: Principal, permission: Permission) {
-        self.user_permissions
-            .entry(principal)
-            .or_insert_with(Vec::new)
-            .push(permission);
+        self.grant(principal, GrantTarget::Permission(permission), None);
     }
 
     pub fn revoke_permission(&mut self, principal: &Principal, permission: &Permission) {
-        if let Some(permissions) = self.user_permissions.get_mut(principal) {
-            permissions.retain(|p| p != permission);
+        self.revoke(principal, &GrantTarget::Permission(permission.clone()));
+    }
+
+    pub fn set_role(&mut self, name: String, mut permissions: Vec<Permission>) {
+        permissions.sort();
+        permissions.dedup();
+        self.roles.insert(name, permissions);
+    }
+
+    // Grants of a deleted role are revoked with it.
+    pub fn delete_role(&mut self, name: &str) -> Option<Vec<Permission>> {
+        let permissions = self.roles.remove(name)?;
+        let target = GrantTarget::Role(name.to_string());
+        for grants in self.grants.values_mut() {
+            grants.retain(|g| g.target != target);
+        }
+        self.grants.retain(|_, grants| !grants.is_empty());
+        Some(permissions)
+    }
+
+    // Up to `take` holders after `prev`, ordered by principal. Expired grants are left out.
+    pub fn list_holders(
+        &self,
+        prev: Option<Principal>,
+        take: usize,
+        now: TimestampNanos,
+    ) -> Vec<PermissionHolder> {
+        let start = match prev {
+            Some(prev) => Bound::Excluded(prev),
+            None => Bound::Unbounded,
+        };
+        self.grants
+            .range((start, Bound::Unbounded))
+            .filter_map(|(principal, grants)| {
+                let grants: Vec<Grant> = grants
+                    .iter()
+                    .filter(|grant| !grant.is_expired(now))
+                    .cloned()
+                    .collect();
+                (!grants.is_empty()).then_some(PermissionHolder {
+                    principal: *principal,
+                    grants,
+                })
+            })
+            .take(take)
+            .collect()
+    }
+
+    // Drops expired grants, returning how many were removed.
+    pub fn remove_expired_grants(&mut self, now: TimestampNanos) -> usize {
+        let mut removed = 0;
+        for grants in self.grants.values_mut() {
+            let len = grants.len();
+            grants.retain(|grant| !grant.is_expired(now));
+            removed += len - grants.len();
         }
+        self.grants.retain(|_, grants| !grants.is_empty());
+        removed
     }
 }
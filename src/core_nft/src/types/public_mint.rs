@@ -0,0 +1,208 @@
+use crate::types::metadata::MetadataValidationError;
+
+use candid::{CandidType, Nat, Principal};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::TransferError;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use sha2::{Digest, Sha256};
+
+// Domain separation keeps a leaf from ever being accepted as an inner node.
+const MERKLE_LEAF_PREFIX: u8 = 0;
+const MERKLE_NODE_PREFIX: u8 = 1;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PublicMintConfig {
+    // ICRC-2 ledger the buyers pay with.
+    pub ledger: Principal,
+    // Price of a single token, in the ledger's base units.
+    pub price: Nat,
+    pub treasury: Account,
+    pub max_per_account: Option<Nat>,
+    // Metadata every publicly minted token starts with.
+    pub metadata: Vec<(String, ICRC3Value)>,
+    // When set, minting is only open during one of the phases and closed outside of them.
+    #[serde(default)]
+    pub phases: Vec<MintPhase>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum MintPhaseKind {
+    Allowlist,
+    Public,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MintPhase {
+    pub name: String,
+    pub kind: MintPhaseKind,
+    // Nanosecond timestamps, start inclusive and end exclusive.
+    pub start: u64,
+    pub end: u64,
+    pub price: Nat,
+    pub max_per_account: Option<Nat>,
+}
+
+impl PublicMintConfig {
+    pub fn active_phase(&self, now: u64) -> Option<&MintPhase> {
+        self.phases
+            .iter()
+            .find(|phase| phase.start <= now && now < phase.end)
+    }
+
+    // Each phase needs a non-empty window and a unique name, and phases must not overlap.
+    pub fn validate_phases(&self) -> Result<(), String> {
+        let mut phases: Vec<&MintPhase> = self.phases.iter().collect();
+        phases.sort_by_key(|phase| phase.start);
+
+        for (index, phase) in phases.iter().enumerate() {
+            if phase.start >= phase.end {
+                return Err(format!("Phase {} ends before it starts", phase.name));
+            }
+            if phases[..index].iter().any(|other| other.name == phase.name) {
+                return Err(format!("Phase {} is defined twice", phase.name));
+            }
+            if index > 0 && phases[index - 1].end > phase.start {
+                return Err(format!(
+                    "Phase {} overlaps phase {}",
+                    phase.name,
+                    phases[index - 1].name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn allowlist_leaf(principal: &Principal) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([MERKLE_LEAF_PREFIX]);
+    hasher.update(principal.as_slice());
+    hasher.finalize().into()
+}
+
+// Sibling pairs are hashed in sorted order, so proofs do not carry left/right flags.
+pub fn allowlist_node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+
+    let mut hasher = Sha256::new();
+    hasher.update([MERKLE_NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+pub fn verify_allowlist_proof(root: &[u8; 32], principal: &Principal, proof: &[ByteBuf]) -> bool {
+    let mut node = allowlist_leaf(principal);
+
+    for sibling in proof {
+        let Ok(sibling) = <[u8; 32]>::try_from(sibling.as_slice()) else {
+            return false;
+        };
+        node = allowlist_node(&node, &sibling);
+    }
+
+    node == *root
+}
+
+#[allow(clippy::module_inception)]
+pub mod public_mint {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub quantity: Nat,
+        // Merkle proof of the caller's allowlist membership, required during allowlist phases.
+        pub proof: Option<Vec<ByteBuf>>,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum PublicMintError {
+        PublicMintClosed,
+        NotAllowlisted,
+        CollectionPaused,
+        AnonymousCaller,
+        InvalidQuantity,
+        ExceedMaxAllowedSupplyCap,
+        ExceedAccountLimit {
+            remaining: Nat,
+        },
+        PaymentFailed(TransferFromError),
+        LedgerCallFailed(String),
+        // The payment went through but the tokens could not be minted.
+        MintFailed {
+            reason: String,
+            refund_block: Option<Nat>,
+        },
+    }
+    pub type Response = Result<Vec<Nat>, PublicMintError>;
+}
+
+pub mod set_public_mint_config {
+    use super::*;
+
+    pub type Args = Option<PublicMintConfig>;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum SetPublicMintConfigError {
+        ConcurrentManagementCall,
+        InvalidMetadata(MetadataValidationError),
+        // Royalties, the transferable flag and the other reserved keys have their own endpoints.
+        ReservedMetadataKey(String),
+        InvalidPhases(String),
+    }
+    pub type Response = Result<(), SetPublicMintConfigError>;
+}
+
+pub mod set_allowlist_root {
+    use super::*;
+
+    pub type Args = Option<ByteBuf>;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum SetAllowlistRootError {
+        ConcurrentManagementCall,
+        InvalidRoot,
+    }
+    pub type Response = Result<(), SetAllowlistRootError>;
+}
+
+pub mod get_allowlist_root {
+    use super::*;
+
+    pub type Args = ();
+    pub type Response = Option<ByteBuf>;
+}
+
+pub mod get_public_mint_config {
+    use super::*;
+
+    pub type Args = ();
+    pub type Response = Option<PublicMintConfig>;
+}
+
+pub mod get_public_mint_purchases {
+    use super::*;
+
+    pub type Args = Principal;
+    pub type Response = Nat;
+}
+
+pub mod settle_public_mint {
+    use super::*;
+
+    pub type Args = ();
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum SettlePublicMintError {
+        ConcurrentManagementCall,
+        PublicMintNotConfigured,
+        LedgerCallFailed(String),
+        TransferFailed(TransferError),
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug, Default)]
+    pub struct SettlePublicMintResult {
+        pub refunded: Nat,
+        pub forwarded: Nat,
+    }
+    pub type Response = Result<SettlePublicMintResult, SettlePublicMintError>;
+}
@@ -0,0 +1,123 @@
+use crate::types::metadata::MetadataValidationError;
+
+use candid::{CandidType, Nat};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+pub const REVEAL_COMMITMENT_METADATA_KEY: &str = "icrc7:reveal_commitment";
+pub const REVEAL_SALT_METADATA_KEY: &str = "icrc7:reveal_salt";
+pub const REVEALED_METADATA_KEY: &str = "icrc7:revealed";
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RevealCommitment {
+    pub commitment: ByteBuf,
+    pub committed_at: u64,
+    // Published on reveal so anyone can recompute the commitment.
+    pub salt: Option<ByteBuf>,
+    pub revealed_at: Option<u64>,
+}
+
+impl RevealCommitment {
+    // Exposed as collection metadata and used as the payload of the reveal ICRC-3 blocks.
+    pub fn metadata(&self) -> BTreeMap<String, ICRC3Value> {
+        let mut metadata = BTreeMap::new();
+        metadata.insert(
+            REVEAL_COMMITMENT_METADATA_KEY.to_string(),
+            ICRC3Value::Blob(self.commitment.clone()),
+        );
+        metadata.insert(
+            REVEALED_METADATA_KEY.to_string(),
+            ICRC3Value::Text(self.revealed_at.is_some().to_string()),
+        );
+        if let Some(salt) = &self.salt {
+            metadata.insert(
+                REVEAL_SALT_METADATA_KEY.to_string(),
+                ICRC3Value::Blob(salt.clone()),
+            );
+        }
+        metadata
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RevealEntry {
+    pub token_id: Nat,
+    pub metadata: Vec<(String, ICRC3Value)>,
+}
+
+impl RevealEntry {
+    // ICRC-3 representation-independent hash of {"tid": Nat, "metadata": Map}.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut map = BTreeMap::new();
+        map.insert("tid".to_string(), ICRC3Value::Nat(self.token_id.clone()));
+        map.insert(
+            "metadata".to_string(),
+            ICRC3Value::Map(self.metadata.iter().cloned().collect()),
+        );
+        ICRC3Value::Map(map).hash()
+    }
+}
+
+// sha256(salt || hash(entry_1) || ... || hash(entry_n)) with the entries sorted by token id.
+pub fn reveal_commitment(salt: &[u8], entries: &[RevealEntry]) -> [u8; 32] {
+    let mut entries: Vec<&RevealEntry> = entries.iter().collect();
+    entries.sort_by(|a, b| a.token_id.cmp(&b.token_id));
+
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    for entry in entries {
+        hasher.update(entry.hash());
+    }
+    hasher.finalize().into()
+}
+
+pub mod commit_reveal {
+    use super::*;
+
+    pub type Args = ByteBuf;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum CommitRevealError {
+        ConcurrentManagementCall,
+        InvalidCommitment,
+        AlreadyCommitted,
+        StorageCanisterError(String),
+    }
+    pub type Response = Result<(), CommitRevealError>;
+}
+
+#[allow(clippy::module_inception)]
+pub mod reveal {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub salt: ByteBuf,
+        pub tokens: Vec<RevealEntry>,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum RevealError {
+        ConcurrentManagementCall,
+        NotCommitted,
+        AlreadyRevealed,
+        DuplicateToken(Nat),
+        CommitmentMismatch,
+        MetadataFrozen(Nat),
+        InvalidMetadata(MetadataValidationError),
+        // Royalties, the transferable flag and the other reserved keys have their own endpoints.
+        ReservedMetadataKey(String),
+        // Reveals replace token metadata, so they are not allowed while a timelock is set.
+        TimelockRequired,
+        StorageCanisterError(String),
+    }
+    pub type Response = Result<(), RevealError>;
+}
+
+pub mod get_reveal_commitment {
+    use super::*;
+
+    pub type Args = ();
+    pub type Response = Option<RevealCommitment>;
+}
@@ -0,0 +1,149 @@
+use candid::{CandidType, Nat, Principal};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use std::collections::BTreeMap;
+
+pub const ROYALTIES_METADATA_KEY: &str = "icrc7:royalties";
+pub const ROYALTY_ENFORCEMENT_METADATA_KEY: &str = "icrc7:royalty_enforcement";
+pub const MAX_ROYALTY_BASIS_POINTS: u16 = 10_000;
+pub const MAX_ROYALTY_RECIPIENTS: usize = 10;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RoyaltyRecipient {
+    pub account: Account,
+    pub basis_points: u16,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Royalties {
+    pub recipients: Vec<RoyaltyRecipient>,
+    pub ledger: Option<Principal>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RoyaltyPayout {
+    pub account: Account,
+    pub basis_points: u16,
+    pub amount: Nat,
+}
+
+impl Royalties {
+    pub fn total_basis_points(&self) -> u32 {
+        self.recipients
+            .iter()
+            .map(|recipient| recipient.basis_points as u32)
+            .sum()
+    }
+
+    // Amounts are rounded down and capped by what is left of the sale price, so the payouts never
+    // exceed it.
+    pub fn payouts(&self, sale_price: &Nat) -> Vec<RoyaltyPayout> {
+        let mut remaining = sale_price.clone();
+        self.recipients
+            .iter()
+            .map(|recipient| {
+                let amount = (sale_price.clone() * Nat::from(recipient.basis_points)
+                    / Nat::from(MAX_ROYALTY_BASIS_POINTS))
+                .min(remaining.clone());
+                remaining -= amount.clone();
+                RoyaltyPayout {
+                    account: recipient.account,
+                    basis_points: recipient.basis_points,
+                    amount,
+                }
+            })
+            .collect()
+    }
+
+    pub fn to_value(&self) -> ICRC3Value {
+        let recipients = self
+            .recipients
+            .iter()
+            .map(|recipient| {
+                let mut map = BTreeMap::new();
+                map.insert("account".to_string(), account_to_value(&recipient.account));
+                map.insert(
+                    "basis_points".to_string(),
+                    ICRC3Value::Nat(Nat::from(recipient.basis_points)),
+                );
+                ICRC3Value::Map(map)
+            })
+            .collect();
+
+        let mut map = BTreeMap::new();
+        map.insert("recipients".to_string(), ICRC3Value::Array(recipients));
+        if let Some(ledger) = self.ledger {
+            map.insert(
+                "ledger".to_string(),
+                ICRC3Value::Blob(ByteBuf::from(ledger.as_slice().to_vec())),
+            );
+        }
+        ICRC3Value::Map(map)
+    }
+
+    pub fn from_value(value: &ICRC3Value) -> Option<Self> {
+        let ICRC3Value::Map(map) = value else {
+            return None;
+        };
+
+        let ICRC3Value::Array(values) = map.get("recipients")? else {
+            return None;
+        };
+        let mut recipients = Vec::with_capacity(values.len());
+        for value in values {
+            let ICRC3Value::Map(recipient) = value else {
+                return None;
+            };
+            let ICRC3Value::Nat(basis_points) = recipient.get("basis_points")? else {
+                return None;
+            };
+            recipients.push(RoyaltyRecipient {
+                account: account_from_value(recipient.get("account")?)?,
+                basis_points: u16::try_from(basis_points.0.clone()).ok()?,
+            });
+        }
+
+        let ledger = match map.get("ledger") {
+            Some(ICRC3Value::Blob(ledger)) => Some(Principal::try_from_slice(ledger).ok()?),
+            Some(_) => return None,
+            None => None,
+        };
+
+        let royalties = Royalties { recipients, ledger };
+        if royalties.total_basis_points() > MAX_ROYALTY_BASIS_POINTS as u32 {
+            return None;
+        }
+        Some(royalties)
+    }
+}
+
+// Accounts use the ICRC-3 representation: [owner] or [owner, subaccount].
+pub fn account_to_value(account: &Account) -> ICRC3Value {
+    let mut parts = vec![ICRC3Value::Blob(ByteBuf::from(
+        account.owner.as_slice().to_vec(),
+    ))];
+    if let Some(subaccount) = account.subaccount {
+        parts.push(ICRC3Value::Blob(ByteBuf::from(subaccount.to_vec())));
+    }
+    ICRC3Value::Array(parts)
+}
+
+pub fn account_from_value(value: &ICRC3Value) -> Option<Account> {
+    let ICRC3Value::Array(parts) = value else {
+        return None;
+    };
+
+    let owner = match parts.first()? {
+        ICRC3Value::Blob(owner) => Principal::try_from_slice(owner).ok()?,
+        _ => return None,
+    };
+    let subaccount = match parts.get(1) {
+        Some(ICRC3Value::Blob(subaccount)) => Some(subaccount.as_slice().try_into().ok()?),
+        Some(_) => return None,
+        None => None,
+    };
+
+    Some(Account { owner, subaccount })
+}
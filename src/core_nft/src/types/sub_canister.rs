@@ -157,6 +157,19 @@ impl StorageSubCanisterManager {
     pub fn list_canisters_ids(&self) -> Vec<Principal> {
         self.sub_canister_manager.list_canisters_ids()
     }
+
+    // Upgrades every storage canister to `wasm`, which later storage canisters are created with.
+    pub async fn upgrade_canisters(
+        &mut self,
+        wasm: Vec<u8>,
+        upgrade_args: ArgsStorage,
+    ) -> Result<(), Vec<String>> {
+        self.sub_canister_manager.wasm = wasm;
+        self.upgrade_args = upgrade_args.clone();
+        self.sub_canister_manager
+            .update_canisters(upgrade_args)
+            .await
+    }
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
@@ -0,0 +1,122 @@
+use crate::types::management::update_nft_metadata;
+use crate::types::permissions::Permission;
+
+use candid::{CandidType, Nat, Principal};
+use serde::{Deserialize, Serialize};
+
+// While set, the calls covered by `TimelockedOperation` are queued and only run `delay`
+// nanoseconds later. Any guardian can cancel them in the meantime.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TimelockConfig {
+    pub delay: u64,
+    pub guardians: Vec<Principal>,
+}
+
+impl TimelockConfig {
+    pub fn is_valid(&self) -> bool {
+        let mut guardians = self.guardians.clone();
+        guardians.sort();
+        guardians.dedup();
+        guardians.len() == self.guardians.len() && !self.guardians.is_empty() && self.delay > 0
+    }
+
+    pub fn is_guardian(&self, principal: &Principal) -> bool {
+        self.guardians.contains(principal)
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub enum TimelockedOperation {
+    SetSupplyCap(Nat),
+    // Full metadata replacement, as done by `update_nft_metadata`.
+    ReplaceTokenMetadata(update_nft_metadata::Args),
+    // None turns the timelock off.
+    SetTimelockConfig(Option<TimelockConfig>),
+}
+
+impl TimelockedOperation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimelockedOperation::SetSupplyCap(_) => "set_supply_cap",
+            TimelockedOperation::ReplaceTokenMetadata(_) => "replace_token_metadata",
+            TimelockedOperation::SetTimelockConfig(_) => "set_timelock_config",
+        }
+    }
+
+    // Permission the caller of `schedule_operation` needs for this operation.
+    pub fn permission(&self) -> Permission {
+        match self {
+            TimelockedOperation::SetSupplyCap(_) => Permission::UpdateCollectionMetadata,
+            TimelockedOperation::ReplaceTokenMetadata(_) => Permission::UpdateMetadata,
+            TimelockedOperation::SetTimelockConfig(_) => Permission::ManageAuthorities,
+        }
+    }
+
+    // Operations replacing a call that multisig covers must go through a proposal as well.
+    pub fn requires_multisig(&self) -> bool {
+        !matches!(self, TimelockedOperation::ReplaceTokenMetadata(_))
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub struct PendingOperation {
+    pub operation_id: Nat,
+    pub operation: TimelockedOperation,
+    pub scheduled_by: Principal,
+    pub scheduled_at: u64,
+    pub executes_at: u64,
+}
+
+impl PendingOperation {
+    pub fn is_due(&self, now: u64) -> bool {
+        self.executes_at <= now
+    }
+}
+
+pub mod get_timelock_config {
+    use super::*;
+
+    pub type Args = ();
+    pub type Response = Option<TimelockConfig>;
+}
+
+pub mod schedule_operation {
+    use super::*;
+
+    pub type Args = TimelockedOperation;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum ScheduleOperationError {
+        ConcurrentManagementCall,
+        Unauthorized,
+        InvalidOperation(String),
+        MultisigRequired,
+        StorageCanisterError(String),
+    }
+    // Id of the queued operation. Without a timelock it is executed right away.
+    pub type Response = Result<Nat, ScheduleOperationError>;
+}
+
+pub mod cancel_operation {
+    use super::*;
+
+    pub type Args = Nat;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum CancelOperationError {
+        ConcurrentManagementCall,
+        NotGuardian,
+        OperationNotFound,
+        StorageCanisterError(String),
+    }
+    pub type Response = Result<(), CancelOperationError>;
+}
+
+pub mod pending_operations {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub prev: Option<Nat>,
+        pub take: Option<Nat>,
+    }
+    pub type Response = Vec<PendingOperation>;
+}
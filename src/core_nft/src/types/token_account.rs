@@ -0,0 +1,59 @@
+use candid::{CandidType, Nat, Principal};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc1::transfer::{Memo, TransferError};
+use serde::{Deserialize, Serialize};
+
+pub mod set_token_account_ledgers {
+    use super::*;
+
+    // ICRC-1 ledgers token accounts can be withdrawn from.
+    pub type Args = Vec<Principal>;
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum SetTokenAccountLedgersError {
+        ConcurrentManagementCall,
+    }
+    pub type Response = Result<(), SetTokenAccountLedgersError>;
+}
+
+pub mod get_token_account_ledgers {
+    use super::*;
+
+    pub type Args = ();
+    pub type Response = Vec<Principal>;
+}
+
+pub mod get_token_account {
+    use super::*;
+
+    pub type Args = Nat;
+    // None if the token does not exist.
+    pub type Response = Option<Account>;
+}
+
+pub mod token_account_transfer {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub token_id: Nat,
+        // Subaccount holding the token, or the root of its tree if it is nested.
+        pub from_subaccount: Option<Subaccount>,
+        pub ledger: Principal,
+        pub to: Account,
+        pub amount: Nat,
+        pub memo: Option<Memo>,
+        pub created_at_time: Option<u64>,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum TokenAccountTransferError {
+        CollectionPaused,
+        TokenDoesNotExist,
+        NotTokenOwner,
+        MaxDepthExceeded,
+        UnsupportedLedger,
+        TransferFailed(TransferError),
+        LedgerCallFailed(String),
+    }
+    // Index of the transfer on the ledger.
+    pub type Response = Result<Nat, TokenAccountTransferError>;
+}
@@ -0,0 +1,52 @@
+use crate::types::nft::TokenUser;
+
+use candid::{CandidType, Nat};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use serde::{Deserialize, Serialize};
+
+// Account allowed to use a token it does not own, e.g. for a rental, until expires_at.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct UserRole {
+    pub user: Account,
+    pub expires_at: u64,
+}
+
+impl From<TokenUser> for UserRole {
+    fn from(token_user: TokenUser) -> Self {
+        Self {
+            user: token_user.user.0,
+            expires_at: token_user.expires_at,
+        }
+    }
+}
+
+pub mod set_user {
+    use super::*;
+
+    #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+    pub struct Args {
+        pub token_id: Nat,
+        pub spender_subaccount: Option<Subaccount>,
+        // None clears the current user.
+        pub user: Option<Account>,
+        pub expires_at: u64,
+    }
+    #[derive(Serialize, Deserialize, CandidType, Debug)]
+    pub enum SetUserError {
+        CollectionPaused,
+        TokenDoesNotExist,
+        Unauthorized,
+        InvalidUser,
+        InvalidExpiry,
+        StorageCanisterError(String),
+    }
+    // Index of the set_user block.
+    pub type Response = Result<Nat, SetUserError>;
+}
+
+pub mod icrc7_user_of {
+    use super::*;
+
+    pub type Args = Vec<Nat>;
+    pub type Response = Vec<Option<UserRole>>;
+}
@@ -1,11 +1,12 @@
 use crate::types::icrc37::{Approval, TokenApprovalValue};
+use crate::types::nest::ChildToken;
 
 use candid::{CandidType, Nat, Principal};
 use ic_stable_structures::{storable::Bound, Storable};
 use icrc_ledger_types::icrc1::account::Account;
 use minicbor::Encoder;
 use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, collections::HashMap};
+use std::{borrow::Cow, collections::HashMap, str::FromStr};
 
 #[derive(Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
 pub struct WrappedNat(pub Nat);
@@ -165,3 +166,63 @@ impl<'b, C> minicbor::Decode<'b, C> for WrappedApprovalValue {
         Ok(WrappedApprovalValue(new_map))
     }
 }
+
+#[derive(Clone, Debug, Default)]
+pub struct WrappedChildTokens(pub Vec<ChildToken>);
+
+impl Storable for WrappedChildTokens {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut buffer = Vec::new();
+        minicbor::encode(self, &mut buffer).expect("failed to encode WrappedChildTokens");
+        Cow::Owned(buffer)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        minicbor::decode(&bytes).expect("failed to decode WrappedChildTokens")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Token ids of other collections are not bounded to u64, so they are stored as decimal strings.
+impl<C> minicbor::Encode<C> for WrappedChildTokens {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut Encoder<W>,
+        _ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        e.array(self.0.len() as u64)?;
+        for child in self.0.iter() {
+            e.array(2)?;
+            e.bytes(child.collection.as_slice())?;
+            e.str(&child.token_id.0.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+impl<'b, C> minicbor::Decode<'b, C> for WrappedChildTokens {
+    fn decode(
+        d: &mut minicbor::Decoder<'b>,
+        _ctx: &mut C,
+    ) -> Result<Self, minicbor::decode::Error> {
+        let len = d.array()?.unwrap();
+        let mut children = Vec::with_capacity(len as usize);
+
+        for _ in 0..len {
+            if d.array()? != Some(2) {
+                return Err(minicbor::decode::Error::message(
+                    "expected array of length 2",
+                ));
+            }
+            let collection = Principal::from_slice(d.bytes()?);
+            let token_id = Nat::from_str(d.str()?)
+                .map_err(|_| minicbor::decode::Error::message("invalid token id"))?;
+            children.push(ChildToken {
+                collection,
+                token_id,
+            });
+        }
+        Ok(WrappedChildTokens(children))
+    }
+}
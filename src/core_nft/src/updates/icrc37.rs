@@ -6,6 +6,10 @@ pub use crate::types::icrc37::{
 use crate::types::nft;
 use crate::types::wrapped_types::{WrappedAccount, WrappedApprovalValue, WrappedNat};
 use crate::types::{__COLLECTION_APPROVALS, __TOKEN_APPROVALS};
+use crate::utils::{
+    is_collection_paused, is_token_transferable, non_transferable_message,
+    COLLECTION_PAUSED_MESSAGE, ROYALTY_ENFORCEMENT_MESSAGE,
+};
 
 use bity_ic_icrc3::{
     transaction::{ICRC37Transaction, ICRC37TransactionData},
@@ -49,6 +53,13 @@ fn verify_approval_timing(created_at_time: u64, current_time: u64) -> Result<(),
 
 #[update]
 fn icrc37_approve_tokens(args: icrc37_approve_tokens::Args) -> icrc37_approve_tokens::Response {
+    if is_collection_paused() {
+        return Err(icrc37_approve_tokens::ApproveTokenError::GenericError {
+            error_code: Nat::from(0u64),
+            message: COLLECTION_PAUSED_MESSAGE.to_string(),
+        });
+    }
+
     let caller = ic_cdk::api::msg_caller();
 
     let mut results = Vec::with_capacity(args.len());
@@ -106,6 +117,13 @@ fn approve_token(
         return ApproveTokenResult::Err(ApproveTokenError::NonExistingTokenId);
     }
 
+    if !is_token_transferable(&arg.token_id) {
+        return ApproveTokenResult::Err(ApproveTokenError::GenericError {
+            error_code: Nat::from(0u64),
+            message: non_transferable_message(&arg.token_id),
+        });
+    }
+
     if owner != Some(from_account.clone()) {
         return ApproveTokenResult::Err(ApproveTokenError::Unauthorized);
     }
@@ -215,6 +233,15 @@ fn approve_token(
 fn icrc37_approve_collection(
     args: icrc37_approve_collection::Args,
 ) -> icrc37_approve_collection::Response {
+    if is_collection_paused() {
+        return Err(
+            icrc37_approve_collection::ApproveCollectionError::GenericError {
+                error_code: Nat::from(0u64),
+                message: COLLECTION_PAUSED_MESSAGE.to_string(),
+            },
+        );
+    }
+
     let caller = ic_cdk::api::msg_caller();
 
     let mut results = Vec::with_capacity(args.len());
@@ -272,13 +299,7 @@ fn approve_collection(
         return ApproveCollectionResult::Err(ApproveCollectionError::InvalidSpender);
     }
 
-    let has_nfts = read_state(|state| {
-        state
-            .data
-            .tokens_list
-            .iter()
-            .any(|(_, token)| token.token_owner.owner == caller)
-    });
+    let has_nfts = read_state(|state| state.data.principal_has_tokens(&caller));
 
     if !has_nfts {
         return ApproveCollectionResult::Err(ApproveCollectionError::GenericError {
@@ -645,11 +666,34 @@ fn revoke_collection_approvals(
 
 #[update]
 fn icrc37_transfer_from(args: icrc37_transfer_from::Args) -> icrc37_transfer_from::Response {
+    if is_collection_paused() {
+        return Err(icrc37_transfer_from::TransferFromError::GenericError {
+            error_code: Nat::from(0u64),
+            message: COLLECTION_PAUSED_MESSAGE.to_string(),
+        });
+    }
+
     let caller = ic_cdk::api::msg_caller();
+    let royalty_enforcement = read_state(|state| state.data.royalty_enforcement);
 
     let mut results = Vec::with_capacity(args.len());
 
     for arg in args {
+        // Owners moving their own tokens are not selling them.
+        let spender_account = Account {
+            owner: caller,
+            subaccount: arg.spender_subaccount,
+        };
+        if royalty_enforcement && spender_account != arg.from {
+            results.push(Some(icrc37_transfer_from::TransferFromResult::Err(
+                icrc37_transfer_from::TransferFromError::GenericError {
+                    error_code: Nat::from(0u64),
+                    message: ROYALTY_ENFORCEMENT_MESSAGE.to_string(),
+                },
+            )));
+            continue;
+        }
+
         let current_time = ic_cdk::api::time();
         let result = transfer_from(arg, caller, current_time);
         results.push(Some(result));
@@ -658,7 +702,7 @@ fn icrc37_transfer_from(args: icrc37_transfer_from::Args) -> icrc37_transfer_fro
     Ok(results)
 }
 
-fn transfer_from(
+pub(crate) fn transfer_from(
     arg: icrc37_transfer_from::TransferFromArg,
     caller: Principal,
     current_time: u64,
@@ -688,13 +732,20 @@ fn transfer_from(
     }
 
     let mut nft: nft::Icrc7Token =
-        match mutate_state(|state| state.data.tokens_list.get(&arg.token_id).cloned()) {
+        match read_state(|state| state.data.get_token_by_id(&arg.token_id)) {
             Some(token) => token,
             None => {
                 return TransferFromResult::Err(TransferFromError::NonExistingTokenId);
             }
         };
 
+    if !is_token_transferable(&arg.token_id) {
+        return TransferFromResult::Err(TransferFromError::GenericError {
+            error_code: Nat::from(0u64),
+            message: non_transferable_message(&arg.token_id),
+        });
+    }
+
     if arg.from == arg.to {
         return TransferFromResult::Err(TransferFromError::InvalidRecipient);
     }
@@ -811,24 +862,10 @@ fn transfer_from(
         },
     };
 
-    let previous_owner = nft.token_owner.clone();
-
     nft.transfer(arg.to.clone());
 
     mutate_state(|state| {
         state.data.update_token_by_id(&nft.token_id, &nft);
-        state
-            .data
-            .tokens_list_by_owner
-            .entry(arg.to.clone())
-            .or_insert(vec![])
-            .push(nft.token_id.clone());
-        state
-            .data
-            .tokens_list_by_owner
-            .entry(previous_owner)
-            .or_insert(vec![])
-            .retain(|id| *id != nft.token_id.clone());
     });
 
     __TOKEN_APPROVALS.with_borrow_mut(|token_approvals| {
@@ -849,3 +886,33 @@ fn transfer_from(
 
     TransferFromResult::Ok(Nat::from(index))
 }
+
+// Whether spender holds a live token or collection approval for token_id; unlike
+// transfer_from, expired approvals are left in place.
+pub(crate) fn is_approved_spender(
+    token_id: &Nat,
+    owner: &Account,
+    spender: &Account,
+    current_time: u64,
+) -> bool {
+    let is_live = |approval: &Approval| {
+        approval
+            .expires_at
+            .is_none_or(|expires_at| expires_at > current_time)
+    };
+
+    let has_token_approval = __TOKEN_APPROVALS.with_borrow(|token_approvals| {
+        token_approvals
+            .get(&WrappedNat::from(token_id.clone()))
+            .and_then(|approvals| approvals.0.get(&WrappedAccount::from(*spender)).cloned())
+            .is_some_and(|approval| is_live(&approval))
+    });
+
+    has_token_approval
+        || __COLLECTION_APPROVALS.with_borrow(|collection_approvals| {
+            collection_approvals
+                .get(&WrappedAccount::from(*owner))
+                .and_then(|approvals| approvals.0.get(&WrappedAccount::from(*spender)).cloned())
+                .is_some_and(|approval| is_live(&approval))
+        })
+}
@@ -1,12 +1,18 @@
 use crate::utils::check_memo;
 use crate::utils::trace;
+use crate::utils::{
+    check_created_at_time, is_collection_paused, is_token_transferable, non_transferable_message,
+    CreatedAtTimeError, COLLECTION_PAUSED_MESSAGE,
+};
 use crate::{
     state::{icrc3_add_transaction, mutate_state, read_state},
-    types::icrc7,
+    types::{icrc7, nft},
 };
 use bity_ic_icrc3::transaction::{ICRC7Transaction, ICRC7TransactionData};
 use candid::{Nat, Principal};
 use ic_cdk_macros::update;
+use icrc_ledger_types::icrc1::account::Account;
+use serde_bytes::ByteBuf;
 
 use crate::guards::guard_sliding_window;
 
@@ -18,9 +24,16 @@ fn transfer_nft(arg: &icrc7::TransferArg) -> Result<Nat, icrc7::icrc7_transfer::
         }
     })?;
 
-    let mut nft = mutate_state(|state| state.data.tokens_list.get(&arg.token_id).cloned())
+    let nft = read_state(|state| state.data.get_token_by_id(&arg.token_id))
         .ok_or(icrc7::icrc7_transfer::TransferError::NonExistingTokenId)?;
 
+    if !is_token_transferable(&arg.token_id) {
+        return Err(icrc7::icrc7_transfer::TransferError::GenericError {
+            error_code: Nat::from(0u64),
+            message: non_transferable_message(&arg.token_id),
+        });
+    }
+
     check_memo(arg.memo.clone()).map_err(|e| {
         icrc7::icrc7_transfer::TransferError::GenericError {
             error_code: Nat::from(0u64),
@@ -39,65 +52,46 @@ fn transfer_nft(arg: &icrc7::TransferArg) -> Result<Nat, icrc7::icrc7_transfer::
     let time = arg.created_at_time.unwrap_or(current_time);
     trace(&format!("time: {:?}", time));
 
-    let (permitted_drift, tx_window) = read_state(|state| {
-        (
-            state.data.permitted_drift.clone(),
-            state.data.tx_window.clone(),
-        )
-    });
-
-    let drift = permitted_drift
-        .map(|d| u64::try_from(d.0).unwrap())
-        .unwrap_or(icrc7::DEFAULT_PERMITTED_DRIFT);
-
-    if time > current_time + drift {
-        return Err(icrc7::icrc7_transfer::TransferError::CreatedInFuture {
-            ledger_time: Nat::from(current_time),
-        });
-    }
-
-    let tx_window = tx_window
-        .map(|d| u64::try_from(d.0).unwrap())
-        .unwrap_or(icrc7::DEFAULT_TX_WINDOW);
+    check_created_at_time(time, current_time).map_err(|e| match e {
+        CreatedAtTimeError::TooOld => icrc7::icrc7_transfer::TransferError::TooOld,
+        CreatedAtTimeError::CreatedInFuture { ledger_time } => {
+            icrc7::icrc7_transfer::TransferError::CreatedInFuture {
+                ledger_time: Nat::from(ledger_time),
+            }
+        }
+    })?;
 
-    if time < current_time.saturating_sub(tx_window + drift) {
-        return Err(icrc7::icrc7_transfer::TransferError::TooOld);
-    }
+    commit_transfer(nft, arg.to, arg.memo.clone(), time, current_time)
+}
 
+// Logs the 7xfer block and moves the token; callers are responsible for the authorization checks.
+pub(crate) fn commit_transfer(
+    mut nft: nft::Icrc7Token,
+    to: Account,
+    memo: Option<ByteBuf>,
+    created_at_time: u64,
+    current_time: u64,
+) -> Result<Nat, icrc7::icrc7_transfer::TransferError> {
     let transaction = ICRC7Transaction::new(
         "7xfer".to_string(),
         current_time,
         ICRC7TransactionData {
             op: "7xfer".to_string(),
-            tid: Some(arg.token_id.clone()),
+            tid: Some(nft.token_id.clone()),
             from: Some(nft.token_owner.clone()),
-            to: Some(arg.to.clone()),
+            to: Some(to),
             meta: None,
-            memo: arg.memo.clone(),
-            created_at_time: Some(Nat::from(time)),
+            memo,
+            created_at_time: Some(Nat::from(created_at_time)),
         },
     );
 
-    let previous_owner = nft.token_owner.clone();
-
     // this is safe to do this as they is no await in the method, meaning state is committed at the end of the icrc7_transfer method.
     match icrc3_add_transaction(transaction.clone()) {
         Ok(transaction_id) => {
-            nft.transfer(arg.to.clone());
+            nft.transfer(to);
             mutate_state(|state| {
                 state.data.update_token_by_id(&nft.token_id, &nft);
-                state
-                    .data
-                    .tokens_list_by_owner
-                    .entry(arg.to.clone())
-                    .or_insert(vec![])
-                    .push(nft.token_id.clone());
-                state
-                    .data
-                    .tokens_list_by_owner
-                    .entry(previous_owner)
-                    .or_insert(vec![])
-                    .retain(|id| *id != nft.token_id.clone());
             });
             Ok(Nat::from(transaction_id))
         }
@@ -141,6 +135,15 @@ pub async fn icrc7_transfer(args: icrc7::icrc7_transfer::Args) -> icrc7::icrc7_t
         ))];
     }
 
+    if is_collection_paused() {
+        return vec![Some(Err(
+            icrc7::icrc7_transfer::TransferError::GenericError {
+                error_code: Nat::from(0u64),
+                message: COLLECTION_PAUSED_MESSAGE.to_string(),
+            },
+        ))];
+    }
+
     if ic_cdk::api::msg_caller() == Principal::anonymous() {
         return vec![Some(Err(
             icrc7::icrc7_transfer::TransferError::GenericError {
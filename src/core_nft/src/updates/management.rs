@@ -1,20 +1,38 @@
 use crate::guards::{
-    caller_has_manage_authorities_permission, caller_has_minting_permission,
-    caller_has_read_uploads_permission, caller_has_update_collection_metadata_permission,
-    caller_has_update_metadata_permission, caller_has_update_uploads_permission, GuardManagement,
+    caller_has_burning_permission, caller_has_manage_authorities_permission,
+    caller_has_minting_permission, caller_has_pause_permission, caller_has_read_uploads_permission,
+    caller_has_update_collection_metadata_permission, caller_has_update_metadata_permission,
+    caller_has_update_royalties_permission, caller_has_update_uploads_permission, GuardManagement,
+};
+use crate::state::{
+    icrc3_add_transaction, mutate_state, read_state, InternalFilestorageData, MintDedupKey,
 };
-use crate::state::{icrc3_add_transaction, mutate_state, read_state, InternalFilestorageData};
 use crate::types::http::add_redirection;
-use crate::types::metadata::__METADATA;
+use crate::types::icrc3::ExtensionTransaction;
+use crate::types::metadata::{
+    is_reserved_metadata_key, FrozenMetadata, __METADATA, FROZEN_KEYS_METADATA_KEY,
+    PAUSED_METADATA_KEY, TRANSFERABLE_METADATA_KEY,
+};
+use crate::types::royalties::{
+    account_to_value, MAX_ROYALTY_BASIS_POINTS, MAX_ROYALTY_RECIPIENTS, ROYALTIES_METADATA_KEY,
+    ROYALTY_ENFORCEMENT_METADATA_KEY,
+};
 use crate::types::sub_canister::StorageCanister;
+use crate::types::value_custom::CustomValue;
+use crate::types::wrapped_types::WrappedAccount;
 use crate::types::{icrc7, management, nft};
-use crate::utils::{check_memo, trace};
+use crate::utils::{
+    check_created_at_time, check_memo, is_multisig_enabled, is_timelock_enabled,
+    set_collection_metadata, trace, update_token_transaction, CollectionMetadataError,
+    CreatedAtTimeError,
+};
 
 pub use crate::types::management::{
-    cancel_upload, finalize_upload, get_user_permissions, grant_permission, has_permission,
-    init_upload, revoke_permission, store_chunk,
+    cancel_upload, delete_role, finalize_upload, get_roles, get_user_permissions, grant_permission,
+    grant_role, has_permission, init_upload, list_permission_holders, revoke_permission,
+    revoke_role, set_role, store_chunk,
 };
-pub use crate::types::permissions::Permission;
+pub use crate::types::permissions::{Grant, GrantTarget, Permission};
 use bity_ic_icrc3::transaction::{ICRC7Transaction, ICRC7TransactionData};
 use bity_ic_storage_canister_api::types::storage::UploadState;
 pub use candid::{Nat, Principal};
@@ -23,7 +41,7 @@ use ic_cdk_macros::{query, update};
 use icrc_ledger_types::icrc::generic_value::ICRC3Value as Icrc3Value;
 use icrc_ledger_types::icrc1::account::Account;
 use std::collections::BTreeMap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[update(guard = "caller_has_update_collection_metadata_permission")]
 pub async fn update_collection_metadata(
@@ -33,6 +51,83 @@ pub async fn update_collection_metadata(
     let _guard_principal = GuardManagement::new(caller)
         .map_err(|_| management::update_collection_metadata::UpdateCollectionMetadataError::ConcurrentManagementCall)?;
 
+    if is_multisig_enabled() {
+        return Err(
+            management::update_collection_metadata::UpdateCollectionMetadataError::MultisigRequired,
+        );
+    }
+
+    update_collection_metadata_impl(caller, req)
+}
+
+// Shared by `update_collection_metadata` and approved multisig proposals.
+pub(crate) fn update_collection_metadata_impl(
+    caller: Principal,
+    req: management::update_collection_metadata::Args,
+) -> management::update_collection_metadata::Response {
+    if req.supply_cap.is_some() && is_timelock_enabled() {
+        return Err(
+            management::update_collection_metadata::UpdateCollectionMetadataError::TimelockRequired,
+        );
+    }
+
+    apply_collection_metadata_update(caller, req)
+}
+
+// Also runs the timelocked supply cap changes, which skip the timelock check above.
+pub(crate) fn apply_collection_metadata_update(
+    caller: Principal,
+    req: management::update_collection_metadata::Args,
+) -> management::update_collection_metadata::Response {
+    use management::update_collection_metadata::UpdateCollectionMetadataError;
+
+    if read_state(|state| state.data.frozen_metadata.collection) {
+        let rewrites_metadata = req.description.is_some()
+            || req.symbol.is_some()
+            || req.name.is_some()
+            || req.logo.is_some();
+
+        if rewrites_metadata {
+            return Err(UpdateCollectionMetadataError::MetadataFrozen);
+        }
+    }
+
+    // Custom keys are checked and written first, so a rejected key leaves the rest untouched.
+    if let Some(collection_metadata) = req.collection_metadata.clone() {
+        set_collection_metadata(caller, collection_metadata).map_err(|e| match e {
+            CollectionMetadataError::InvalidMetadata(e) => {
+                UpdateCollectionMetadataError::InvalidMetadata(e)
+            }
+            CollectionMetadataError::ReservedKey(key) => {
+                UpdateCollectionMetadataError::ReservedMetadataKey(key)
+            }
+            CollectionMetadataError::MetadataFrozen => {
+                UpdateCollectionMetadataError::MetadataFrozen
+            }
+            CollectionMetadataError::StorageCanisterError(e) => {
+                UpdateCollectionMetadataError::StorageCanisterError(e)
+            }
+        })?;
+    }
+
+    let (before, after) = collection_config_changes(&req);
+    if !after.is_empty() {
+        let mut tx = BTreeMap::new();
+        tx.insert("from".to_string(), account_to_value(&Account::from(caller)));
+        tx.insert("before".to_string(), Icrc3Value::Map(before));
+        tx.insert("after".to_string(), Icrc3Value::Map(after));
+        icrc3_add_transaction(ExtensionTransaction::new(
+            "coll_update",
+            ic_cdk::api::time(),
+            tx,
+        ))
+        .map_err(|e| {
+            management::update_collection_metadata::UpdateCollectionMetadataError::StorageCanisterError(
+                e.to_string(),
+            )
+        })?;
+    }
+
     if let Some(description) = req.description {
         mutate_state(|state| {
             state.data.description = Some(description);
@@ -117,9 +212,124 @@ pub async fn update_collection_metadata(
         });
     }
 
+    if let Some(metadata_validation) = req.metadata_validation {
+        mutate_state(|state| {
+            state.data.metadata_validation = metadata_validation;
+        });
+    }
+
     Ok(())
 }
 
+// Before and after values of the configuration fields `req` changes. Fields that were unset have
+// no before value. Collection metadata entries are logged by `set_collection_metadata` instead.
+fn collection_config_changes(
+    req: &management::update_collection_metadata::Args,
+) -> (BTreeMap<String, Icrc3Value>, BTreeMap<String, Icrc3Value>) {
+    let text = |value: &String| Icrc3Value::Text(value.clone());
+    let nat = |value: &Nat| Icrc3Value::Nat(value.clone());
+    let flag = |value: &bool| Icrc3Value::Text(value.to_string());
+
+    read_state(|state| {
+        let data = &state.data;
+        let changes: Vec<(&str, Option<Icrc3Value>, Option<Icrc3Value>)> = vec![
+            (
+                "description",
+                data.description.as_ref().map(text),
+                req.description.as_ref().map(text),
+            ),
+            (
+                "symbol",
+                Some(text(&data.symbol)),
+                req.symbol.as_ref().map(text),
+            ),
+            ("name", Some(text(&data.name)), req.name.as_ref().map(text)),
+            (
+                "logo",
+                data.logo.as_ref().map(text),
+                req.logo.as_ref().map(text),
+            ),
+            (
+                "supply_cap",
+                data.supply_cap.as_ref().map(nat),
+                req.supply_cap.as_ref().map(nat),
+            ),
+            (
+                "max_query_batch_size",
+                data.max_query_batch_size.as_ref().map(nat),
+                req.max_query_batch_size.as_ref().map(nat),
+            ),
+            (
+                "max_update_batch_size",
+                data.max_update_batch_size.as_ref().map(nat),
+                req.max_update_batch_size.as_ref().map(nat),
+            ),
+            (
+                "max_take_value",
+                data.max_take_value.as_ref().map(nat),
+                req.max_take_value.as_ref().map(nat),
+            ),
+            (
+                "default_take_value",
+                data.default_take_value.as_ref().map(nat),
+                req.default_take_value.as_ref().map(nat),
+            ),
+            (
+                "max_memo_size",
+                data.max_memo_size.as_ref().map(nat),
+                req.max_memo_size.as_ref().map(nat),
+            ),
+            (
+                "atomic_batch_transfers",
+                data.atomic_batch_transfers.as_ref().map(flag),
+                req.atomic_batch_transfers.as_ref().map(flag),
+            ),
+            (
+                "tx_window",
+                data.tx_window.as_ref().map(nat),
+                req.tx_window.as_ref().map(nat),
+            ),
+            (
+                "permitted_drift",
+                data.permitted_drift.as_ref().map(nat),
+                req.permitted_drift.as_ref().map(nat),
+            ),
+            (
+                "max_canister_storage_threshold",
+                data.max_canister_storage_threshold.as_ref().map(nat),
+                req.max_canister_storage_threshold.as_ref().map(nat),
+            ),
+            (
+                "metadata_validation",
+                Some(data.metadata_validation.to_value()),
+                req.metadata_validation
+                    .as_ref()
+                    .map(|validation| validation.to_value()),
+            ),
+        ];
+
+        let mut before = BTreeMap::new();
+        let mut after = BTreeMap::new();
+        for (field, previous, new) in changes {
+            let Some(new) = new else { continue };
+            if previous.as_ref() == Some(&new) {
+                continue;
+            }
+            if let Some(previous) = previous {
+                before.insert(field.to_string(), previous);
+            }
+            after.insert(field.to_string(), new);
+        }
+        (before, after)
+    })
+}
+
+// A mint request already handled, either by an earlier call or earlier in the same batch.
+enum MintDuplicate {
+    Logged(Nat),
+    InBatch(usize),
+}
+
 #[update(guard = "caller_has_minting_permission")]
 pub fn mint(req: management::mint::Args) -> management::mint::Response {
     trace("Minting NFT batch");
@@ -140,8 +350,94 @@ pub fn mint(req: management::mint::Args) -> management::mint::Response {
         return Err(management::mint::MintError::ExceedMaxAllowedSupplyCap);
     }
 
+    let timestamp = ic_cdk::api::time();
+
+    for mint_request in &req.mint_requests {
+        match check_memo(mint_request.memo.clone()) {
+            Ok(_) => {}
+            Err(_) => {
+                return Err(management::mint::MintError::InvalidMemo);
+            }
+        }
+
+        if let Some((key, _)) = mint_request
+            .metadata
+            .iter()
+            .find(|(key, _)| is_reserved_metadata_key(key))
+        {
+            return Err(management::mint::MintError::ReservedMetadataKey(
+                key.clone(),
+            ));
+        }
+
+        read_state(|state| {
+            state
+                .data
+                .metadata_validation
+                .validate(&mint_request.metadata)
+        })
+        .map_err(management::mint::MintError::InvalidMetadata)?;
+
+        if let Some(created_at_time) = mint_request.created_at_time {
+            check_created_at_time(created_at_time, timestamp).map_err(|e| match e {
+                CreatedAtTimeError::TooOld => management::mint::MintError::TooOld,
+                CreatedAtTimeError::CreatedInFuture { ledger_time } => {
+                    management::mint::MintError::CreatedInFuture {
+                        ledger_time: Nat::from(ledger_time),
+                    }
+                }
+            })?;
+        }
+    }
+
+    // Requests replayed within the transaction window, or repeated within this batch, resolve to
+    // the tokens they already minted.
+    let (duplicates, dedup_keys) = mutate_state(|state| {
+        state.data.prune_recent_mints(timestamp);
+
+        let dedup_keys: Vec<Option<MintDedupKey>> = req
+            .mint_requests
+            .iter()
+            .map(|mint_request| {
+                mint_request
+                    .created_at_time
+                    .map(|created_at_time| MintDedupKey {
+                        caller,
+                        token_owner: mint_request.token_owner.clone(),
+                        token_id: mint_request.token_id.clone(),
+                        memo: mint_request.memo.as_ref().map(|m| m.to_vec()),
+                        created_at_time,
+                    })
+            })
+            .collect();
+        let duplicates: Vec<Option<MintDuplicate>> = dedup_keys
+            .iter()
+            .enumerate()
+            .map(|(index, key)| {
+                let key = key.as_ref()?;
+                if let Some(token_id) = state.data.recent_mints.get(key) {
+                    return Some(MintDuplicate::Logged(token_id.clone()));
+                }
+                dedup_keys[..index]
+                    .iter()
+                    .position(|earlier| earlier.as_ref() == Some(key))
+                    .map(MintDuplicate::InBatch)
+            })
+            .collect();
+
+        (duplicates, dedup_keys)
+    });
+
+    let pending_requests: Vec<management::mint::MintRequest> = req
+        .mint_requests
+        .iter()
+        .zip(duplicates.iter())
+        .filter(|(_, duplicate)| duplicate.is_none())
+        .map(|(mint_request, _)| mint_request.clone())
+        .collect();
+
     let current_token_id = read_state(|state| state.data.last_token_id.clone());
-    let token_list = read_state(|state| state.data.tokens_list.clone());
+    let total_supply = read_state(|state| state.data.total_supply());
     let supply_cap = read_state(|state| {
         state
             .data
@@ -150,26 +446,52 @@ pub fn mint(req: management::mint::Args) -> management::mint::Response {
             .unwrap_or(Nat::from(icrc7::DEFAULT_MAX_SUPPLY_CAP))
     });
 
-    if token_list.len() + req.mint_requests.len() > supply_cap {
+    let reserved = read_state(|state| state.data.public_mint_reserved.clone());
+
+    if total_supply + reserved + Nat::from(pending_requests.len() as u64) > supply_cap {
         return Err(management::mint::MintError::ExceedMaxAllowedSupplyCap);
     }
 
-    for mint_request in &req.mint_requests {
-        match check_memo(mint_request.memo.clone()) {
-            Ok(_) => {}
-            Err(_) => {
-                return Err(management::mint::MintError::InvalidMemo);
+    let new_token_ids = mint_tokens(&pending_requests, timestamp)?;
+
+    let mut new_token_ids = new_token_ids.into_iter();
+    let mut token_ids: Vec<Nat> = Vec::with_capacity(duplicates.len());
+    for duplicate in duplicates {
+        let token_id = match duplicate {
+            Some(MintDuplicate::Logged(token_id)) => token_id,
+            Some(MintDuplicate::InBatch(index)) => token_ids[index].clone(),
+            None => new_token_ids.next().unwrap(),
+        };
+        token_ids.push(token_id);
+    }
+
+    mutate_state(|state| {
+        for (key, token_id) in dedup_keys.into_iter().zip(token_ids.iter()) {
+            if let Some(key) = key {
+                state.data.recent_mints.insert(key, token_id.clone());
             }
         }
-    }
+    });
+
+    trace(&format!(
+        "Successfully minted {} NFTs",
+        pending_requests.len()
+    ));
+    Ok(token_ids.first().cloned().unwrap_or(current_token_id))
+}
+
+// Mints the requests as new tokens and logs them, returning the allocated ids in request order.
+pub(crate) fn mint_tokens(
+    mint_requests: &[management::mint::MintRequest],
+    timestamp: u64,
+) -> Result<Vec<Nat>, management::mint::MintError> {
+    let current_token_id = read_state(|state| state.data.last_token_id.clone());
+    let (new_token_ids, next_token_id) = allocate_token_ids(mint_requests, &current_token_id)?;
 
     let mut new_tokens = Vec::new();
     let mut transactions = Vec::new();
-    let timestamp = ic_cdk::api::time();
-
-    for (i, mint_request) in req.mint_requests.iter().enumerate() {
-        let token_id = current_token_id.clone() + Nat::from(i as u64);
 
+    for (mint_request, token_id) in mint_requests.iter().zip(new_token_ids.iter()) {
         let mut new_token =
             nft::Icrc7Token::new(token_id.clone(), mint_request.token_owner.clone());
         __METADATA.with_borrow_mut(|m| new_token.add_metadata(m, mint_request.metadata.clone()));
@@ -184,11 +506,11 @@ pub fn mint(req: management::mint::Args) -> management::mint::Response {
                 to: Some(mint_request.token_owner.clone()),
                 meta: None,
                 memo: mint_request.memo.clone(),
-                created_at_time: Some(Nat::from(timestamp)),
+                created_at_time: Some(Nat::from(mint_request.created_at_time.unwrap_or(timestamp))),
             },
         );
 
-        new_tokens.push((token_id, new_token, mint_request.token_owner.clone()));
+        new_tokens.push(new_token);
         transactions.push(transaction);
     }
 
@@ -204,26 +526,54 @@ pub fn mint(req: management::mint::Args) -> management::mint::Response {
     }
 
     mutate_state(|state| {
-        let new_last_token_id =
-            current_token_id.clone() + Nat::from(req.mint_requests.len() as u64);
-        state.data.last_token_id = new_last_token_id;
+        state.data.last_token_id = next_token_id;
 
-        for (token_id, new_token, token_owner) in new_tokens {
-            state.data.tokens_list.insert(token_id.clone(), new_token);
-            state
-                .data
-                .tokens_list_by_owner
-                .entry(token_owner)
-                .or_insert(vec![])
-                .push(token_id);
+        for new_token in new_tokens {
+            state.data.add_token(&new_token);
         }
     });
 
-    trace(&format!(
-        "Successfully minted {} NFTs",
-        req.mint_requests.len()
-    ));
-    Ok(current_token_id.clone())
+    Ok(new_token_ids)
+}
+
+// Explicit ids are reserved first so that sequential ids never collide with them.
+fn allocate_token_ids(
+    mint_requests: &[management::mint::MintRequest],
+    current_token_id: &Nat,
+) -> Result<(Vec<Nat>, Nat), management::mint::MintError> {
+    let mut reserved = HashSet::new();
+
+    for token_id in mint_requests.iter().filter_map(|r| r.token_id.as_ref()) {
+        if *token_id == 0u64 || u64::try_from(token_id.0.clone()).is_err() {
+            return Err(management::mint::MintError::InvalidTokenId);
+        }
+
+        if !read_state(|state| state.data.is_token_id_available(token_id))
+            || !reserved.insert(token_id.clone())
+        {
+            return Err(management::mint::MintError::TokenAlreadyExists);
+        }
+    }
+
+    let mut next_token_id = current_token_id.clone();
+    let mut token_ids = Vec::with_capacity(mint_requests.len());
+
+    for mint_request in mint_requests {
+        match &mint_request.token_id {
+            Some(token_id) => token_ids.push(token_id.clone()),
+            None => {
+                while reserved.contains(&next_token_id)
+                    || !read_state(|state| state.data.is_token_id_available(&next_token_id))
+                {
+                    next_token_id += Nat::from(1u64);
+                }
+                token_ids.push(next_token_id.clone());
+                next_token_id += Nat::from(1u64);
+            }
+        }
+    }
+
+    Ok((token_ids, next_token_id))
 }
 
 #[update(guard = "caller_has_update_metadata_permission")]
@@ -236,18 +586,62 @@ pub fn update_nft_metadata(
         management::update_nft_metadata::UpdateNftMetadataError::ConcurrentManagementCall
     })?;
 
+    if is_timelock_enabled() {
+        return Err(management::update_nft_metadata::UpdateNftMetadataError::TimelockRequired);
+    }
+
+    replace_token_metadata(caller, req)
+}
+
+pub(crate) fn replace_token_metadata(
+    caller: Principal,
+    req: management::update_nft_metadata::Args,
+) -> management::update_nft_metadata::Response {
     let token_name_hash = req.token_id;
+    let mut metadata = req.metadata;
 
-    let token_list = read_state(|state| state.data.tokens_list.clone());
+    if let Some((key, _)) = metadata
+        .iter()
+        .find(|(key, _)| is_reserved_metadata_key(key))
+    {
+        return Err(
+            management::update_nft_metadata::UpdateNftMetadataError::ReservedMetadataKey(
+                key.clone(),
+            ),
+        );
+    }
 
-    match token_list.contains_key(&token_name_hash.clone()) {
-        true => {
-            let mut token = token_list.get(&token_name_hash.clone()).unwrap().clone();
+    read_state(|state| state.data.metadata_validation.validate(&metadata))
+        .map_err(management::update_nft_metadata::UpdateNftMetadataError::InvalidMetadata)?;
 
+    match read_state(|state| state.data.get_token_by_id(&token_name_hash)) {
+        Some(mut token) => {
             let previous_metadata =
                 __METADATA.with_borrow(|m| m.get_all_data(Some(token_name_hash.clone())));
 
-            __METADATA.with_borrow_mut(|m| token.replace_metadata(m, req.metadata));
+            // Reserved keys survive the replacement; only their own endpoints change them.
+            metadata.extend(
+                previous_metadata
+                    .iter()
+                    .flatten()
+                    .filter(|(key, _)| is_reserved_metadata_key(key))
+                    .map(|(key, value)| (key.clone(), value.0.clone())),
+            );
+
+            if !read_state(|state| {
+                can_replace_metadata(
+                    &state.data.frozen_metadata,
+                    &token_name_hash,
+                    previous_metadata.as_ref().ok(),
+                    &metadata,
+                )
+            }) {
+                return Err(
+                    management::update_nft_metadata::UpdateNftMetadataError::MetadataFrozen,
+                );
+            }
+
+            __METADATA.with_borrow_mut(|m| token.replace_metadata(m, metadata));
 
             let new_metadata =
                 __METADATA.with_borrow(|m| m.get_all_data(Some(token_name_hash.clone())));
@@ -285,7 +679,7 @@ pub fn update_nft_metadata(
                     op: "7update_token".to_string(),
                     tid: Some(token_name_hash.clone()),
                     from: Some(Account {
-                        owner: ic_cdk::api::msg_caller(),
+                        owner: caller,
                         subaccount: None,
                     }),
                     to: None,
@@ -304,18 +698,12 @@ pub fn update_nft_metadata(
                 }
             };
 
-            mutate_state(|state| {
-                state
-                    .data
-                    .tokens_list
-                    .insert(token_name_hash.clone(), token);
-            });
             trace(&format!(
                 "Updated NFT metadata for token: {:?}",
                 token_name_hash.clone()
             ));
         }
-        false => {
+        None => {
             return Err(management::update_nft_metadata::UpdateNftMetadataError::TokenDoesNotExist);
         }
     }
@@ -323,51 +711,680 @@ pub fn update_nft_metadata(
     Ok(token_name_hash.clone())
 }
 
-#[update]
-pub fn burn_nft(token_id: Nat) -> management::burn_nft::Response {
+#[update(guard = "caller_has_update_metadata_permission")]
+pub fn freeze_metadata(
+    req: management::freeze_metadata::Args,
+) -> management::freeze_metadata::Response {
+    use management::freeze_metadata::{Args, FreezeMetadataError};
+
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal =
+        GuardManagement::new(caller).map_err(|_| FreezeMetadataError::ConcurrentManagementCall)?;
+
+    let token_id = match &req {
+        Args::Collection => Nat::from(0u64),
+        Args::Token(token_id) | Args::TokenKeys { token_id, .. } => token_id.clone(),
+    };
+
+    if !matches!(req, Args::Collection) && !read_state(|state| state.data.token_exists(&token_id)) {
+        return Err(FreezeMetadataError::TokenDoesNotExist);
+    }
+
+    let mut frozen_metadata = read_state(|state| state.data.frozen_metadata.clone());
+    let previous_keys = frozen_metadata.frozen_keys(&token_id);
+
+    match req {
+        Args::Collection => {
+            if frozen_metadata.collection {
+                return Err(FreezeMetadataError::AlreadyFrozen);
+            }
+            frozen_metadata.collection = true;
+        }
+        Args::Token(_) => {
+            if frozen_metadata.is_token_frozen(&token_id) {
+                return Err(FreezeMetadataError::AlreadyFrozen);
+            }
+            frozen_metadata.tokens.insert(token_id.clone());
+            frozen_metadata.token_keys.remove(&token_id);
+        }
+        Args::TokenKeys { keys, .. } => {
+            let new_keys: Vec<String> = keys
+                .into_iter()
+                .filter(|key| !frozen_metadata.is_key_frozen(&token_id, key))
+                .collect();
+            if new_keys.is_empty() {
+                return Err(FreezeMetadataError::AlreadyFrozen);
+            }
+            frozen_metadata
+                .token_keys
+                .entry(token_id.clone())
+                .or_default()
+                .extend(new_keys);
+        }
+    }
+
+    let frozen_keys_value =
+        |keys: Vec<String>| Icrc3Value::Array(keys.into_iter().map(Icrc3Value::Text).collect());
+
+    let mut previous_values = BTreeMap::new();
+    if !previous_keys.is_empty() {
+        previous_values.insert(
+            FROZEN_KEYS_METADATA_KEY.to_string(),
+            frozen_keys_value(previous_keys),
+        );
+    }
+    let mut new_values = BTreeMap::new();
+    new_values.insert(
+        FROZEN_KEYS_METADATA_KEY.to_string(),
+        frozen_keys_value(frozen_metadata.frozen_keys(&token_id)),
+    );
+
+    let transaction = update_token_transaction(
+        token_id.clone(),
+        caller,
+        previous_values,
+        new_values,
+        ic_cdk::api::time(),
+    );
+
+    icrc3_add_transaction(transaction)
+        .map_err(|e| FreezeMetadataError::StorageCanisterError(e.to_string()))?;
+
+    mutate_state(|state| state.data.frozen_metadata = frozen_metadata);
+
+    trace(&format!("Froze metadata for token: {:?}", token_id));
+    Ok(())
+}
+
+#[update(guard = "caller_has_update_royalties_permission")]
+pub fn set_royalties(req: management::set_royalties::Args) -> management::set_royalties::Response {
+    use management::set_royalties::SetRoyaltiesError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal =
+        GuardManagement::new(caller).map_err(|_| SetRoyaltiesError::ConcurrentManagementCall)?;
+
+    let token_id = req.token_id.clone().unwrap_or(Nat::from(0u64));
+    if req.token_id.is_some() && !read_state(|state| state.data.token_exists(&token_id)) {
+        return Err(SetRoyaltiesError::TokenDoesNotExist);
+    }
+
+    if let Some(royalties) = &req.royalties {
+        if royalties.recipients.len() > MAX_ROYALTY_RECIPIENTS {
+            return Err(SetRoyaltiesError::TooManyRecipients);
+        }
+        if royalties
+            .recipients
+            .iter()
+            .any(|recipient| recipient.basis_points == 0)
+            || royalties.total_basis_points() > MAX_ROYALTY_BASIS_POINTS as u32
+        {
+            return Err(SetRoyaltiesError::InvalidBasisPoints);
+        }
+    }
+
+    if read_state(|state| {
+        state
+            .data
+            .frozen_metadata
+            .is_key_frozen(&token_id, ROYALTIES_METADATA_KEY)
+    }) {
+        return Err(SetRoyaltiesError::MetadataFrozen);
+    }
+
+    let previous_value = __METADATA
+        .with_borrow(|m| m.get_data(Some(token_id.clone()), ROYALTIES_METADATA_KEY.to_string()))
+        .ok()
+        .map(|v| v.0);
+    let new_value = req.royalties.as_ref().map(|royalties| royalties.to_value());
+
+    if previous_value == new_value {
+        return Ok(());
+    }
+
+    let mut previous_values = BTreeMap::new();
+    if let Some(previous_value) = previous_value {
+        previous_values.insert(ROYALTIES_METADATA_KEY.to_string(), previous_value);
+    }
+    let mut new_values = BTreeMap::new();
+    if let Some(new_value) = new_value.clone() {
+        new_values.insert(ROYALTIES_METADATA_KEY.to_string(), new_value);
+    }
+
+    let transaction = update_token_transaction(
+        token_id.clone(),
+        caller,
+        previous_values,
+        new_values,
+        ic_cdk::api::time(),
+    );
+
+    icrc3_add_transaction(transaction)
+        .map_err(|e| SetRoyaltiesError::StorageCanisterError(e.to_string()))?;
+
+    __METADATA.with_borrow_mut(|m| match new_value {
+        Some(new_value) => m.insert_data(
+            Some(token_id.clone()),
+            ROYALTIES_METADATA_KEY.to_string(),
+            CustomValue(new_value),
+        ),
+        None => m.delete_data(Some(token_id.clone()), ROYALTIES_METADATA_KEY.to_string()),
+    });
+
+    trace(&format!("Updated royalties for token: {:?}", token_id));
+    Ok(())
+}
+
+#[update(guard = "caller_has_update_royalties_permission")]
+pub fn set_royalty_enforcement(
+    enforced: management::set_royalty_enforcement::Args,
+) -> management::set_royalty_enforcement::Response {
+    use management::set_royalty_enforcement::SetRoyaltyEnforcementError;
+
     let caller = ic_cdk::api::msg_caller();
     let _guard_principal = GuardManagement::new(caller)
-        .map_err(|_| management::burn_nft::BurnNftError::ConcurrentManagementCall)?;
+        .map_err(|_| SetRoyaltyEnforcementError::ConcurrentManagementCall)?;
+
+    let previous = read_state(|state| state.data.royalty_enforcement);
+    if previous == enforced {
+        return Ok(());
+    }
+
+    let mut previous_values = BTreeMap::new();
+    previous_values.insert(
+        ROYALTY_ENFORCEMENT_METADATA_KEY.to_string(),
+        Icrc3Value::Text(previous.to_string()),
+    );
+    let mut new_values = BTreeMap::new();
+    new_values.insert(
+        ROYALTY_ENFORCEMENT_METADATA_KEY.to_string(),
+        Icrc3Value::Text(enforced.to_string()),
+    );
+
+    let transaction = update_token_transaction(
+        Nat::from(0u64),
+        caller,
+        previous_values,
+        new_values,
+        ic_cdk::api::time(),
+    );
+
+    icrc3_add_transaction(transaction)
+        .map_err(|e| SetRoyaltyEnforcementError::StorageCanisterError(e.to_string()))?;
+
+    mutate_state(|state| state.data.royalty_enforcement = enforced);
+
+    trace(&format!("Royalty enforcement: {}", enforced));
+    Ok(())
+}
+
+#[update(guard = "caller_has_update_metadata_permission")]
+pub fn set_transferable(
+    req: management::set_transferable::Args,
+) -> management::set_transferable::Response {
+    use management::set_transferable::SetTransferableError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal =
+        GuardManagement::new(caller).map_err(|_| SetTransferableError::ConcurrentManagementCall)?;
+
+    let token_id = req.token_id.clone().unwrap_or(Nat::from(0u64));
+    if req.token_id.is_some() && !read_state(|state| state.data.token_exists(&token_id)) {
+        return Err(SetTransferableError::TokenDoesNotExist);
+    }
+
+    if read_state(|state| {
+        state
+            .data
+            .frozen_metadata
+            .is_key_frozen(&token_id, TRANSFERABLE_METADATA_KEY)
+    }) {
+        return Err(SetTransferableError::MetadataFrozen);
+    }
+
+    let previous_value = __METADATA
+        .with_borrow(|m| {
+            m.get_data(
+                Some(token_id.clone()),
+                TRANSFERABLE_METADATA_KEY.to_string(),
+            )
+        })
+        .ok()
+        .map(|v| v.0);
+    let new_value = req
+        .transferable
+        .map(|transferable| Icrc3Value::Text(transferable.to_string()));
+
+    if previous_value == new_value {
+        return Ok(());
+    }
+
+    let mut previous_values = BTreeMap::new();
+    if let Some(previous_value) = previous_value {
+        previous_values.insert(TRANSFERABLE_METADATA_KEY.to_string(), previous_value);
+    }
+    let mut new_values = BTreeMap::new();
+    if let Some(new_value) = new_value.clone() {
+        new_values.insert(TRANSFERABLE_METADATA_KEY.to_string(), new_value);
+    }
+
+    let transaction = update_token_transaction(
+        token_id.clone(),
+        caller,
+        previous_values,
+        new_values,
+        ic_cdk::api::time(),
+    );
+
+    icrc3_add_transaction(transaction)
+        .map_err(|e| SetTransferableError::StorageCanisterError(e.to_string()))?;
+
+    __METADATA.with_borrow_mut(|m| match new_value {
+        Some(new_value) => m.insert_data(
+            Some(token_id.clone()),
+            TRANSFERABLE_METADATA_KEY.to_string(),
+            CustomValue(new_value),
+        ),
+        None => m.delete_data(
+            Some(token_id.clone()),
+            TRANSFERABLE_METADATA_KEY.to_string(),
+        ),
+    });
+
+    trace(&format!(
+        "Updated transferable flag for token: {:?}",
+        token_id
+    ));
+    Ok(())
+}
+
+#[update(guard = "caller_has_pause_permission")]
+pub fn pause_collection() -> management::pause_collection::Response {
+    pause_endpoint(true)
+}
+
+#[update(guard = "caller_has_pause_permission")]
+pub fn unpause_collection() -> management::unpause_collection::Response {
+    pause_endpoint(false)
+}
+
+fn pause_endpoint(paused: bool) -> management::pause_collection::Response {
+    use management::pause_collection::PauseCollectionError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal =
+        GuardManagement::new(caller).map_err(|_| PauseCollectionError::ConcurrentManagementCall)?;
+
+    if is_multisig_enabled() {
+        return Err(PauseCollectionError::MultisigRequired);
+    }
 
-    let token = match read_state(|state| state.data.tokens_list.get(&token_id).cloned()) {
+    set_collection_paused(caller, paused)
+}
+
+// Shared by the pause endpoints and approved multisig proposals.
+pub(crate) fn set_collection_paused(
+    caller: Principal,
+    paused: bool,
+) -> management::pause_collection::Response {
+    use management::pause_collection::PauseCollectionError;
+
+    match (read_state(|state| state.data.paused), paused) {
+        (true, true) => return Err(PauseCollectionError::AlreadyPaused),
+        (false, false) => return Err(PauseCollectionError::NotPaused),
+        _ => {}
+    }
+
+    let mut previous_values = BTreeMap::new();
+    previous_values.insert(
+        PAUSED_METADATA_KEY.to_string(),
+        Icrc3Value::Text((!paused).to_string()),
+    );
+    let mut new_values = BTreeMap::new();
+    new_values.insert(
+        PAUSED_METADATA_KEY.to_string(),
+        Icrc3Value::Text(paused.to_string()),
+    );
+
+    let transaction = update_token_transaction(
+        Nat::from(0u64),
+        caller,
+        previous_values,
+        new_values,
+        ic_cdk::api::time(),
+    );
+
+    icrc3_add_transaction(transaction)
+        .map_err(|e| PauseCollectionError::StorageCanisterError(e.to_string()))?;
+
+    mutate_state(|state| state.data.paused = paused);
+
+    trace(&format!("Collection paused: {}", paused));
+    Ok(())
+}
+
+// A full replace is only allowed when it leaves every frozen key untouched.
+pub(crate) fn can_replace_metadata(
+    frozen_metadata: &FrozenMetadata,
+    token_id: &Nat,
+    previous_metadata: Option<&BTreeMap<String, CustomValue>>,
+    new_metadata: &[(String, Icrc3Value)],
+) -> bool {
+    if frozen_metadata.is_token_frozen(token_id) {
+        return false;
+    }
+
+    frozen_metadata.frozen_keys(token_id).iter().all(|key| {
+        let previous_value = previous_metadata
+            .and_then(|metadata| metadata.get(key))
+            .map(|v| &v.0);
+        let new_value = new_metadata
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v);
+        previous_value == new_value
+    })
+}
+
+fn patch_token_metadata(
+    patch: management::update_nft_metadata_patch::MetadataPatch,
+    caller: Principal,
+) -> management::update_nft_metadata_patch::PatchResult {
+    let mut token = read_state(|state| state.data.get_token_by_id(&patch.token_id))
+        .ok_or(management::update_nft_metadata::UpdateNftMetadataError::TokenDoesNotExist)?;
+
+    if let Some(key) = patch
+        .set
+        .iter()
+        .map(|(key, _)| key)
+        .chain(patch.unset.iter())
+        .find(|key| is_reserved_metadata_key(key))
+    {
+        return Err(
+            management::update_nft_metadata::UpdateNftMetadataError::ReservedMetadataKey(
+                key.clone(),
+            ),
+        );
+    }
+
+    read_state(|state| state.data.metadata_validation.validate(&patch.set))
+        .map_err(management::update_nft_metadata::UpdateNftMetadataError::InvalidMetadata)?;
+
+    let current_metadata = __METADATA
+        .with_borrow(|m| m.get_all_data(Some(patch.token_id.clone())))
+        .unwrap_or_default();
+
+    let mut previous_values = BTreeMap::new();
+    let mut new_values = BTreeMap::new();
+    let mut to_set = Vec::new();
+    let mut to_unset = Vec::new();
+
+    for (key, value) in patch.set {
+        let previous_value = current_metadata.get(&key).map(|v| v.0.clone());
+        if previous_value.as_ref() == Some(&value) {
+            continue;
+        }
+        if let Some(previous_value) = previous_value {
+            previous_values.insert(key.clone(), previous_value);
+        }
+        new_values.insert(key.clone(), value.clone());
+        to_set.push((key, value));
+    }
+
+    for key in patch.unset {
+        if new_values.contains_key(&key) {
+            continue;
+        }
+        if let Some(previous_value) = current_metadata.get(&key) {
+            previous_values.insert(key.clone(), previous_value.0.clone());
+            to_unset.push(key);
+        }
+    }
+
+    if to_set.is_empty() && to_unset.is_empty() {
+        return Ok(patch.token_id);
+    }
+
+    if read_state(|state| {
+        previous_values.keys().chain(new_values.keys()).any(|key| {
+            state
+                .data
+                .frozen_metadata
+                .is_key_frozen(&patch.token_id, key)
+        })
+    }) {
+        return Err(management::update_nft_metadata::UpdateNftMetadataError::MetadataFrozen);
+    }
+
+    let transaction = update_token_transaction(
+        patch.token_id.clone(),
+        caller,
+        previous_values,
+        new_values,
+        ic_cdk::api::time(),
+    );
+
+    icrc3_add_transaction(transaction).map_err(|e| {
+        management::update_nft_metadata::UpdateNftMetadataError::StorageCanisterError(e.to_string())
+    })?;
+
+    __METADATA.with_borrow_mut(|m| {
+        token.update_metadata(m, to_set);
+        token.remove_metadata(m, to_unset);
+    });
+
+    trace(&format!(
+        "Patched NFT metadata for token: {:?}",
+        patch.token_id
+    ));
+    Ok(patch.token_id)
+}
+
+#[update(guard = "caller_has_update_metadata_permission")]
+pub fn update_nft_metadata_patch(
+    req: management::update_nft_metadata_patch::Args,
+) -> management::update_nft_metadata_patch::Response {
+    if req.is_empty() {
+        return vec![];
+    }
+
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = match GuardManagement::new(caller) {
+        Ok(guard) => guard,
+        Err(_) => {
+            return vec![Some(Err(
+                management::update_nft_metadata::UpdateNftMetadataError::ConcurrentManagementCall,
+            ))];
+        }
+    };
+
+    if is_timelock_enabled() {
+        return vec![Some(Err(
+            management::update_nft_metadata::UpdateNftMetadataError::TimelockRequired,
+        ))];
+    }
+
+    let max_batch_size = read_state(|state| {
+        usize::try_from(
+            state
+                .data
+                .max_update_batch_size
+                .clone()
+                .unwrap_or(Nat::from(icrc7::DEFAULT_MAX_UPDATE_BATCH_SIZE))
+                .0,
+        )
+        .unwrap()
+    });
+
+    if req.len() > max_batch_size {
+        return vec![Some(Err(
+            management::update_nft_metadata::UpdateNftMetadataError::ExceedMaxUpdateBatchSize,
+        ))];
+    }
+
+    req.into_iter()
+        .map(|patch| patch_token_metadata(patch, caller))
+        .map(Some)
+        .collect()
+}
+
+fn burn_token(
+    arg: &management::burn_nfts::BurnArg,
+    caller: Principal,
+    as_admin: bool,
+) -> management::burn_nfts::BurnResult {
+    check_memo(arg.memo.clone()).map_err(|_| management::burn_nft::BurnNftError::InvalidMemo)?;
+
+    let current_time = ic_cdk::api::time();
+    let created_at_time = arg.created_at_time.unwrap_or(current_time);
+
+    check_created_at_time(created_at_time, current_time).map_err(|e| match e {
+        CreatedAtTimeError::TooOld => management::burn_nft::BurnNftError::TooOld,
+        CreatedAtTimeError::CreatedInFuture { ledger_time } => {
+            management::burn_nft::BurnNftError::CreatedInFuture {
+                ledger_time: Nat::from(ledger_time),
+            }
+        }
+    })?;
+
+    let token = match read_state(|state| state.data.get_token_by_id(&arg.token_id)) {
         Some(token) => token,
         None => {
-            return Err(management::burn_nft::BurnNftError::TokenDoesNotExist);
+            // A retried burn with the same memo and created_at_time returns the original block.
+            let burned_token = read_state(|state| state.data.get_burned_token(&arg.token_id));
+            return match burned_token {
+                Some(burned_token)
+                    if arg.created_at_time == Some(burned_token.created_at_time)
+                        && arg.memo.as_ref().map(|m| m.to_vec()) == burned_token.memo
+                        && (as_admin || burned_token.from.0.owner == caller) =>
+                {
+                    Ok(Nat::from(burned_token.transaction_id))
+                }
+                _ => Err(management::burn_nft::BurnNftError::TokenDoesNotExist),
+            };
         }
     };
 
-    if caller != token.token_owner.owner {
+    if !as_admin && caller != token.token_owner.owner {
         return Err(management::burn_nft::BurnNftError::NotTokenOwner);
     }
 
+    if !read_state(|state| state.data.children_of(&arg.token_id)).is_empty() {
+        return Err(management::burn_nft::BurnNftError::HasChildren);
+    }
+
     let transaction = ICRC7Transaction::new(
         "7burn".to_string(),
-        ic_cdk::api::time(),
+        current_time,
         ICRC7TransactionData {
             op: "7burn".to_string(),
-            tid: Some(token_id.clone()),
+            tid: Some(arg.token_id.clone()),
             from: Some(token.token_owner.clone()),
             to: None,
             meta: None,
-            memo: None,
-            created_at_time: Some(Nat::from(ic_cdk::api::time())),
+            memo: arg.memo.clone(),
+            created_at_time: Some(Nat::from(created_at_time)),
         },
     );
 
-    match icrc3_add_transaction(transaction) {
-        Ok(_) => {}
+    let transaction_id = match icrc3_add_transaction(transaction) {
+        Ok(transaction_id) => transaction_id,
+        Err(bity_ic_icrc3::types::Icrc3Error::DuplicateTransaction { duplicate_of }) => {
+            return Ok(Nat::from(duplicate_of));
+        }
         Err(e) => {
             return Err(management::burn_nft::BurnNftError::StorageCanisterError(
                 e.to_string(),
             ));
         }
-    }
+    };
 
     mutate_state(|state| {
-        state.data.tokens_list.remove(&token_id);
+        state.data.burn_token(
+            &arg.token_id,
+            nft::BurnedToken {
+                from: WrappedAccount::from(token.token_owner),
+                memo: arg.memo.as_ref().map(|m| m.to_vec()),
+                created_at_time,
+                burned_at: current_time,
+                transaction_id,
+            },
+        );
+        state.sliding_window_guards.remove(&arg.token_id);
     });
 
-    Ok(())
+    trace(&format!("Burned NFT: {:?}", arg.token_id));
+    Ok(Nat::from(transaction_id))
+}
+
+fn burn_tokens(
+    args: management::burn_nfts::Args,
+    as_admin: bool,
+) -> management::burn_nfts::Response {
+    if args.is_empty() {
+        return vec![];
+    }
+
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = match GuardManagement::new(caller) {
+        Ok(guard) => guard,
+        Err(_) => {
+            return vec![Some(Err(
+                management::burn_nft::BurnNftError::ConcurrentManagementCall,
+            ))];
+        }
+    };
+
+    let max_batch_size = read_state(|state| {
+        usize::try_from(
+            state
+                .data
+                .max_update_batch_size
+                .clone()
+                .unwrap_or(Nat::from(icrc7::DEFAULT_MAX_UPDATE_BATCH_SIZE))
+                .0,
+        )
+        .unwrap()
+    });
+
+    if args.len() > max_batch_size {
+        return vec![Some(Err(
+            management::burn_nft::BurnNftError::ExceedMaxUpdateBatchSize,
+        ))];
+    }
+
+    args.iter()
+        .map(|arg| burn_token(arg, caller, as_admin))
+        .map(Some)
+        .collect()
+}
+
+#[update]
+pub fn burn_nft(token_id: management::burn_nft::Args) -> management::burn_nft::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller)
+        .map_err(|_| management::burn_nft::BurnNftError::ConcurrentManagementCall)?;
+
+    let arg = management::burn_nfts::BurnArg {
+        token_id,
+        memo: None,
+        created_at_time: None,
+    };
+
+    burn_token(&arg, caller, false).map(|_| ())
+}
+
+#[update]
+pub fn burn_nfts(args: management::burn_nfts::Args) -> management::burn_nfts::Response {
+    burn_tokens(args, false)
+}
+
+#[update(guard = "caller_has_burning_permission")]
+pub fn admin_burn_nfts(
+    args: management::admin_burn_nfts::Args,
+) -> management::admin_burn_nfts::Response {
+    burn_tokens(args, true)
 }
 
 #[update(guard = "caller_has_update_uploads_permission")]
@@ -637,36 +1654,329 @@ pub async fn cancel_upload(data: cancel_upload::Args) -> cancel_upload::Response
     Ok(cancel_upload::CancelUploadResp {})
 }
 
-#[update(guard = "caller_has_manage_authorities_permission")]
-pub fn grant_permission(args: grant_permission::Args) -> grant_permission::Response {
-    let caller = ic_cdk::api::msg_caller();
-    let _guard_principal = GuardManagement::new(caller)
-        .map_err(|_| grant_permission::GrantPermissionError::ConcurrentManagementCall)?;
+// Logs a `perm_grant` or `perm_revoke` block; `before` and `after` are the principal's grant of the
+// same permission or role around the change.
+fn log_permission_change(
+    btype: &str,
+    caller: Principal,
+    principal: Principal,
+    before: Option<&Grant>,
+    after: Option<&Grant>,
+) -> Result<(), String> {
+    let mut tx = BTreeMap::new();
+    tx.insert("from".to_string(), account_to_value(&Account::from(caller)));
+    tx.insert(
+        "to".to_string(),
+        account_to_value(&Account::from(principal)),
+    );
+    if let Some(before) = before {
+        tx.insert("before".to_string(), before.to_value());
+    }
+    if let Some(after) = after {
+        tx.insert("after".to_string(), after.to_value());
+    }
+
+    icrc3_add_transaction(ExtensionTransaction::new(btype, ic_cdk::api::time(), tx))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
 
+// Grants that would not change anything are not logged.
+fn grant_and_log(
+    caller: Principal,
+    principal: Principal,
+    target: GrantTarget,
+    expires_at: Option<u64>,
+) -> Result<(), String> {
+    let before = read_state(|state| state.data.permissions.grant_of(&principal, &target));
+    let after = Grant { target, expires_at };
+    if before.as_ref() == Some(&after) {
+        return Ok(());
+    }
+
+    log_permission_change(
+        "perm_grant",
+        caller,
+        principal,
+        before.as_ref(),
+        Some(&after),
+    )?;
     mutate_state(|state| {
         state
             .data
             .permissions
-            .grant_permission(args.principal, args.permission);
+            .grant(principal, after.target, after.expires_at);
     });
+    Ok(())
+}
 
+fn revoke_and_log(
+    caller: Principal,
+    principal: Principal,
+    target: GrantTarget,
+) -> Result<(), String> {
+    let Some(before) = read_state(|state| state.data.permissions.grant_of(&principal, &target))
+    else {
+        return Ok(());
+    };
+
+    log_permission_change("perm_revoke", caller, principal, Some(&before), None)?;
+    mutate_state(|state| {
+        state.data.permissions.revoke(&principal, &target);
+    });
     Ok(())
 }
 
+fn log_role_change(
+    caller: Principal,
+    name: &str,
+    before: Option<&Vec<Permission>>,
+    after: Option<&Vec<Permission>>,
+) -> Result<(), String> {
+    let permissions_value = |permissions: &Vec<Permission>| {
+        Icrc3Value::Array(permissions.iter().map(Permission::to_value).collect())
+    };
+
+    let mut tx = BTreeMap::new();
+    tx.insert("from".to_string(), account_to_value(&Account::from(caller)));
+    tx.insert("role".to_string(), Icrc3Value::Text(name.to_string()));
+    if let Some(before) = before {
+        tx.insert("before".to_string(), permissions_value(before));
+    }
+    if let Some(after) = after {
+        tx.insert("after".to_string(), permissions_value(after));
+    }
+
+    icrc3_add_transaction(ExtensionTransaction::new(
+        "role_update",
+        ic_cdk::api::time(),
+        tx,
+    ))
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+#[update(guard = "caller_has_manage_authorities_permission")]
+pub fn grant_permission(args: grant_permission::Args) -> grant_permission::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller)
+        .map_err(|_| grant_permission::GrantPermissionError::ConcurrentManagementCall)?;
+
+    if is_multisig_enabled() {
+        return Err(grant_permission::GrantPermissionError::MultisigRequired);
+    }
+
+    grant_permission_impl(caller, args)
+}
+
+pub(crate) fn grant_permission_impl(
+    caller: Principal,
+    args: grant_permission::Args,
+) -> grant_permission::Response {
+    let now = ic_cdk::api::time();
+    if args.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(grant_permission::GrantPermissionError::InvalidExpiry);
+    }
+
+    grant_and_log(
+        caller,
+        args.principal,
+        GrantTarget::Permission(args.permission),
+        args.expires_at,
+    )
+    .map_err(grant_permission::GrantPermissionError::DefaultError)
+}
+
 #[update(guard = "caller_has_manage_authorities_permission")]
 pub fn revoke_permission(args: revoke_permission::Args) -> revoke_permission::Response {
     let caller = ic_cdk::api::msg_caller();
     let _guard_principal = GuardManagement::new(caller)
         .map_err(|_| revoke_permission::RevokePermissionError::ConcurrentManagementCall)?;
 
+    if is_multisig_enabled() {
+        return Err(revoke_permission::RevokePermissionError::MultisigRequired);
+    }
+
+    revoke_permission_impl(caller, args)
+}
+
+pub(crate) fn revoke_permission_impl(
+    caller: Principal,
+    args: revoke_permission::Args,
+) -> revoke_permission::Response {
+    revoke_and_log(
+        caller,
+        args.principal,
+        GrantTarget::Permission(args.permission),
+    )
+    .map_err(revoke_permission::RevokePermissionError::DefaultError)
+}
+
+#[update(guard = "caller_has_manage_authorities_permission")]
+pub fn set_role(args: set_role::Args) -> set_role::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller)
+        .map_err(|_| set_role::SetRoleError::ConcurrentManagementCall)?;
+
+    if is_multisig_enabled() {
+        return Err(set_role::SetRoleError::MultisigRequired);
+    }
+
+    set_role_impl(caller, args)
+}
+
+pub(crate) fn set_role_impl(caller: Principal, args: set_role::Args) -> set_role::Response {
+    if args.name.trim().is_empty() {
+        return Err(set_role::SetRoleError::InvalidRoleName);
+    }
+
+    let mut permissions = args.permissions;
+    permissions.sort();
+    permissions.dedup();
+
+    let before = read_state(|state| state.data.permissions.roles.get(&args.name).cloned());
+    if before.as_ref() == Some(&permissions) {
+        return Ok(());
+    }
+
+    log_role_change(caller, &args.name, before.as_ref(), Some(&permissions))
+        .map_err(set_role::SetRoleError::StorageCanisterError)?;
     mutate_state(|state| {
+        state.data.permissions.set_role(args.name, permissions);
+    });
+
+    Ok(())
+}
+
+#[update(guard = "caller_has_manage_authorities_permission")]
+pub fn delete_role(name: delete_role::Args) -> delete_role::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller)
+        .map_err(|_| delete_role::DeleteRoleError::ConcurrentManagementCall)?;
+
+    if is_multisig_enabled() {
+        return Err(delete_role::DeleteRoleError::MultisigRequired);
+    }
+
+    delete_role_impl(caller, name)
+}
+
+pub(crate) fn delete_role_impl(
+    caller: Principal,
+    name: delete_role::Args,
+) -> delete_role::Response {
+    let before = read_state(|state| state.data.permissions.roles.get(&name).cloned())
+        .ok_or(delete_role::DeleteRoleError::RoleNotFound)?;
+
+    log_role_change(caller, &name, Some(&before), None)
+        .map_err(delete_role::DeleteRoleError::StorageCanisterError)?;
+    mutate_state(|state| {
+        state.data.permissions.delete_role(&name);
+    });
+
+    Ok(())
+}
+
+#[query(guard = "caller_has_manage_authorities_permission")]
+pub fn get_roles() -> get_roles::Response {
+    read_state(|state| {
         state
             .data
             .permissions
-            .revoke_permission(&args.principal, &args.permission);
-    });
+            .roles
+            .iter()
+            .map(|(name, permissions)| (name.clone(), permissions.clone()))
+            .collect()
+    })
+}
 
-    Ok(())
+#[update(guard = "caller_has_manage_authorities_permission")]
+pub fn grant_role(args: grant_role::Args) -> grant_role::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller)
+        .map_err(|_| grant_role::GrantRoleError::ConcurrentManagementCall)?;
+
+    if is_multisig_enabled() {
+        return Err(grant_role::GrantRoleError::MultisigRequired);
+    }
+
+    grant_role_impl(caller, args)
+}
+
+pub(crate) fn grant_role_impl(caller: Principal, args: grant_role::Args) -> grant_role::Response {
+    let now = ic_cdk::api::time();
+    if args.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(grant_role::GrantRoleError::InvalidExpiry);
+    }
+
+    if !read_state(|state| state.data.permissions.roles.contains_key(&args.role)) {
+        return Err(grant_role::GrantRoleError::RoleNotFound);
+    }
+
+    grant_and_log(
+        caller,
+        args.principal,
+        GrantTarget::Role(args.role),
+        args.expires_at,
+    )
+    .map_err(grant_role::GrantRoleError::StorageCanisterError)
+}
+
+#[update(guard = "caller_has_manage_authorities_permission")]
+pub fn revoke_role(args: revoke_role::Args) -> revoke_role::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller)
+        .map_err(|_| revoke_role::RevokeRoleError::ConcurrentManagementCall)?;
+
+    if is_multisig_enabled() {
+        return Err(revoke_role::RevokeRoleError::MultisigRequired);
+    }
+
+    revoke_role_impl(caller, args)
+}
+
+pub(crate) fn revoke_role_impl(
+    caller: Principal,
+    args: revoke_role::Args,
+) -> revoke_role::Response {
+    revoke_and_log(caller, args.principal, GrantTarget::Role(args.role))
+        .map_err(revoke_role::RevokeRoleError::StorageCanisterError)
+}
+
+#[query(guard = "caller_has_manage_authorities_permission")]
+pub fn list_permission_holders(
+    args: list_permission_holders::Args,
+) -> list_permission_holders::Response {
+    let max_take_value = read_state(|state| state.data.max_take_value.clone())
+        .unwrap_or(Nat::from(icrc7::DEFAULT_MAX_TAKE_VALUE));
+    if let Some(take) = &args.take {
+        if take.0 > max_take_value.0 {
+            ic_cdk::trap(format!(
+                "max_take_value exceeded. Limit is {}. Retry with a smaller take value.",
+                max_take_value.0
+            ));
+        }
+    }
+
+    read_state(|state| {
+        let take = usize::try_from(
+            args.take
+                .unwrap_or_else(|| {
+                    state
+                        .data
+                        .default_take_value
+                        .clone()
+                        .unwrap_or(Nat::from(icrc7::DEFAULT_TAKE_VALUE))
+                })
+                .0,
+        )
+        .unwrap_or(icrc7::DEFAULT_TAKE_VALUE);
+
+        state
+            .data
+            .permissions
+            .list_holders(args.prev, take, ic_cdk::api::time())
+    })
 }
 
 #[query(guard = "caller_has_manage_authorities_permission")]
@@ -675,22 +1985,22 @@ pub fn get_user_permissions(args: get_user_permissions::Args) -> get_user_permis
         state
             .data
             .permissions
-            .get_permissions(&args.principal)
-            .cloned()
+            .get_permissions(&args.principal, ic_cdk::api::time())
     });
-    match permissions {
-        Some(permissions) => Ok(permissions),
-        None => Err(get_user_permissions::GetUserPermissionsError::UserNotFound),
+    if permissions.is_empty() {
+        return Err(get_user_permissions::GetUserPermissionsError::UserNotFound);
     }
+    Ok(permissions)
 }
 
 #[query(guard = "caller_has_manage_authorities_permission")]
 pub fn has_permission(args: has_permission::Args) -> has_permission::Response {
     let has_permission = read_state(|state| {
-        state
-            .data
-            .permissions
-            .has_permission(&args.principal, &args.permission)
+        state.data.permissions.has_permission(
+            &args.principal,
+            &args.permission,
+            ic_cdk::api::time(),
+        )
     });
 
     Ok(has_permission)
@@ -0,0 +1,531 @@
+use crate::guards::{caller_has_update_collection_metadata_permission, GuardManagement};
+use crate::ledger;
+use crate::state::{mutate_state, read_state};
+use crate::types::marketplace::{Listing, PendingPayout};
+use crate::types::{icrc37, icrc7, marketplace};
+use crate::updates::icrc37::{is_approved_spender, transfer_from};
+use crate::updates::icrc7::commit_transfer;
+use crate::utils::{get_royalties, is_collection_paused, is_token_transferable, trace};
+
+use candid::{Nat, Principal};
+use ic_cdk_macros::{query, update};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc1::transfer::TransferArg;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromArgs;
+
+#[update(guard = "caller_has_update_collection_metadata_permission")]
+pub fn set_marketplace_config(
+    config: marketplace::set_marketplace_config::Args,
+) -> marketplace::set_marketplace_config::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller).map_err(|_| {
+        marketplace::set_marketplace_config::SetMarketplaceConfigError::ConcurrentManagementCall
+    })?;
+
+    mutate_state(|state| state.data.marketplace = config);
+    Ok(())
+}
+
+#[query]
+pub fn get_marketplace_config() -> marketplace::get_marketplace_config::Response {
+    read_state(|state| state.data.marketplace.clone())
+}
+
+#[update]
+pub fn list_token(req: marketplace::list_token::Args) -> marketplace::list_token::Response {
+    use marketplace::list_token::ListTokenError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let now = ic_cdk::api::time();
+
+    let config = read_state(|state| state.data.marketplace.clone())
+        .ok_or(ListTokenError::MarketplaceClosed)?;
+
+    if is_collection_paused() {
+        return Err(ListTokenError::CollectionPaused);
+    }
+
+    let seller = Account {
+        owner: caller,
+        subaccount: req.from_subaccount,
+    };
+    let owner = read_state(|state| state.data.owner_of(&req.token_id))
+        .ok_or(ListTokenError::TokenDoesNotExist)?;
+
+    if owner != seller {
+        return Err(ListTokenError::NotTokenOwner);
+    }
+
+    if !is_token_transferable(&req.token_id) {
+        return Err(ListTokenError::NonTransferable);
+    }
+
+    if !config.ledgers.contains(&req.ledger) || !is_sale_ledger_allowed(&req.token_id, req.ledger) {
+        return Err(ListTokenError::UnsupportedLedger);
+    }
+
+    if req.price == 0u64 {
+        return Err(ListTokenError::InvalidPrice);
+    }
+
+    if req.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(ListTokenError::InvalidExpiry);
+    }
+
+    mutate_state(|state| {
+        if state
+            .data
+            .listings
+            .get(&req.token_id)
+            .is_some_and(|listing| listing.pending_purchase)
+        {
+            return Err(ListTokenError::PurchaseInProgress);
+        }
+
+        state.data.listings.insert(
+            req.token_id.clone(),
+            Listing {
+                token_id: req.token_id,
+                seller,
+                price: req.price,
+                ledger: req.ledger,
+                expires_at: req.expires_at,
+                created_at: now,
+                pending_purchase: false,
+            },
+        );
+        Ok(())
+    })
+}
+
+#[update]
+pub fn cancel_listing(
+    token_id: marketplace::cancel_listing::Args,
+) -> marketplace::cancel_listing::Response {
+    use marketplace::cancel_listing::CancelListingError;
+
+    let caller = ic_cdk::api::msg_caller();
+
+    mutate_state(|state| {
+        let listing = state
+            .data
+            .listings
+            .get(&token_id)
+            .ok_or(CancelListingError::ListingNotFound)?;
+
+        if listing.seller.owner != caller {
+            return Err(CancelListingError::NotSeller);
+        }
+
+        if listing.pending_purchase {
+            return Err(CancelListingError::PurchaseInProgress);
+        }
+
+        state.data.listings.remove(&token_id);
+        Ok(())
+    })
+}
+
+#[update]
+pub async fn buy_token(req: marketplace::buy_token::Args) -> marketplace::buy_token::Response {
+    use marketplace::buy_token::BuyTokenError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let now = ic_cdk::api::time();
+
+    if caller == Principal::anonymous() {
+        return Err(BuyTokenError::AnonymousCaller);
+    }
+
+    if read_state(|state| state.data.marketplace.is_none()) {
+        return Err(BuyTokenError::MarketplaceClosed);
+    }
+
+    if is_collection_paused() {
+        return Err(BuyTokenError::CollectionPaused);
+    }
+
+    let buyer = Account {
+        owner: caller,
+        subaccount: req.from_subaccount,
+    };
+
+    // The listing is locked for the duration of the payment so it cannot be sold twice.
+    let listing = mutate_state(|state| {
+        let listing = state
+            .data
+            .listings
+            .get_mut(&req.token_id)
+            .ok_or(BuyTokenError::ListingNotFound)?;
+
+        if listing.pending_purchase {
+            return Err(BuyTokenError::PurchaseInProgress);
+        }
+
+        if listing.is_expired(now) {
+            state.data.listings.remove(&req.token_id);
+            return Err(BuyTokenError::ListingExpired);
+        }
+
+        if listing.seller.owner == caller {
+            return Err(BuyTokenError::CannotBuyOwnListing);
+        }
+
+        if listing.price != req.price || listing.ledger != req.ledger {
+            return Err(BuyTokenError::PriceMismatch {
+                price: listing.price.clone(),
+                ledger: listing.ledger,
+            });
+        }
+
+        listing.pending_purchase = true;
+        Ok(listing.clone())
+    })?;
+
+    let payment = ledger::icrc2_transfer_from(
+        listing.ledger,
+        TransferFromArgs {
+            spender_subaccount: None,
+            from: buyer,
+            to: Account {
+                owner: ic_cdk::api::canister_self(),
+                subaccount: None,
+            },
+            amount: listing.price.clone(),
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        },
+    )
+    .await;
+
+    let payment_error = match payment {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => Some(BuyTokenError::PaymentFailed(e)),
+        Err(e) => Some(BuyTokenError::LedgerCallFailed(e)),
+    };
+
+    if let Some(error) = payment_error {
+        unlock_listing(&listing);
+        return Err(error);
+    }
+
+    let transfer = match read_state(|state| {
+        let still_listed = state.data.listings.get(&listing.token_id) == Some(&listing);
+        let owner = state.data.owner_of(&listing.token_id);
+        (still_listed && owner == Some(listing.seller) && !state.data.paused)
+            .then(|| state.data.get_token_by_id(&listing.token_id))
+            .flatten()
+    }) {
+        Some(nft) if is_token_transferable(&listing.token_id) => {
+            let timestamp = ic_cdk::api::time();
+            commit_transfer(nft, buyer, None, timestamp, timestamp).ok()
+        }
+        _ => None,
+    };
+
+    let Some(transfer_block) = transfer else {
+        unlock_listing(&listing);
+        let refund_block = pay_out(listing.ledger, None, buyer, listing.price.clone()).await;
+        return Err(BuyTokenError::ListingInvalidated { refund_block });
+    };
+
+    trace(&format!(
+        "Sold token {} to {} for {}",
+        listing.token_id, caller, listing.price
+    ));
+
+    pay_sale_proceeds(
+        listing.ledger,
+        None,
+        &listing.token_id,
+        listing.seller,
+        listing.price,
+    )
+    .await;
+
+    Ok(transfer_block)
+}
+
+#[update]
+pub async fn sale_transfer_from(
+    req: marketplace::sale_transfer_from::Args,
+) -> marketplace::sale_transfer_from::Response {
+    use icrc37::icrc37_transfer_from::TransferFromError;
+    use marketplace::sale_transfer_from::SaleTransferFromError;
+
+    let caller = ic_cdk::api::msg_caller();
+
+    if is_collection_paused() {
+        return Err(SaleTransferFromError::CollectionPaused);
+    }
+
+    if req.price == 0u64 {
+        return Err(SaleTransferFromError::InvalidPrice);
+    }
+
+    // Only ledgers the collection trusts may settle a sale, so royalties are paid in real funds.
+    let royalty_ledger = get_royalties(&req.token_id).and_then(|royalties| royalties.ledger);
+    let marketplace_ledger = read_state(|state| {
+        state
+            .data
+            .marketplace
+            .as_ref()
+            .is_some_and(|config| config.ledgers.contains(&req.ledger))
+    });
+    if !(marketplace_ledger || royalty_ledger == Some(req.ledger))
+        || !is_sale_ledger_allowed(&req.token_id, req.ledger)
+    {
+        return Err(SaleTransferFromError::UnsupportedLedger);
+    }
+
+    let payer = Account {
+        owner: caller,
+        subaccount: req.spender_subaccount,
+    };
+
+    // Checked before the payment is taken and again once it has landed, as the token may have
+    // moved or the approval been revoked in between.
+    let check_authorized = || match read_state(|state| state.data.owner_of(&req.token_id)) {
+        None => Err(TransferFromError::NonExistingTokenId),
+        Some(owner) if owner != req.from => Err(TransferFromError::Unauthorized),
+        Some(owner)
+            if payer != owner
+                && !is_approved_spender(&req.token_id, &owner, &payer, ic_cdk::api::time()) =>
+        {
+            Err(TransferFromError::Unauthorized)
+        }
+        Some(_) => Ok(()),
+    };
+    check_authorized().map_err(SaleTransferFromError::InvalidTransfer)?;
+
+    let payment = ledger::icrc2_transfer_from(
+        req.ledger,
+        TransferFromArgs {
+            spender_subaccount: None,
+            from: payer,
+            to: Account {
+                owner: ic_cdk::api::canister_self(),
+                subaccount: None,
+            },
+            amount: req.price.clone(),
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        },
+    )
+    .await;
+
+    match payment {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => return Err(SaleTransferFromError::PaymentFailed(e)),
+        Err(e) => return Err(SaleTransferFromError::LedgerCallFailed(e)),
+    }
+
+    let transfer = if is_collection_paused() {
+        icrc37::icrc37_transfer_from::TransferFromResult::Err(TransferFromError::GenericError {
+            error_code: Nat::from(0u64),
+            message: crate::utils::COLLECTION_PAUSED_MESSAGE.to_string(),
+        })
+    } else if let Err(error) = check_authorized() {
+        icrc37::icrc37_transfer_from::TransferFromResult::Err(error)
+    } else {
+        transfer_from(
+            icrc37::icrc37_transfer_from::TransferFromArg {
+                spender_subaccount: req.spender_subaccount,
+                from: req.from,
+                to: req.to,
+                token_id: req.token_id.clone(),
+                memo: req.memo,
+                created_at_time: req.created_at_time,
+            },
+            caller,
+            ic_cdk::api::time(),
+        )
+    };
+
+    let transfer_block = match transfer {
+        icrc37::icrc37_transfer_from::TransferFromResult::Ok(block) => block,
+        icrc37::icrc37_transfer_from::TransferFromResult::Err(error) => {
+            let refund_block = pay_out(req.ledger, None, payer, req.price).await;
+            return Err(SaleTransferFromError::TransferFailed {
+                error,
+                refund_block,
+            });
+        }
+    };
+
+    trace(&format!(
+        "Sold token {} to {} for {} through transfer_from",
+        req.token_id, req.to, req.price
+    ));
+
+    pay_sale_proceeds(req.ledger, None, &req.token_id, req.from, req.price).await;
+
+    Ok(transfer_block)
+}
+
+#[query]
+pub fn get_listing(token_id: marketplace::get_listing::Args) -> marketplace::get_listing::Response {
+    let now = ic_cdk::api::time();
+    read_state(|state| {
+        state
+            .data
+            .listings
+            .get(&token_id)
+            .filter(|listing| !listing.is_expired(now))
+            .cloned()
+    })
+}
+
+#[query]
+pub fn get_listings(
+    prev: marketplace::get_listings::Args0,
+    take: marketplace::get_listings::Args1,
+) -> marketplace::get_listings::Response {
+    let now = ic_cdk::api::time();
+    read_state(|state| {
+        let max_take_value = state
+            .data
+            .max_take_value
+            .clone()
+            .unwrap_or(Nat::from(icrc7::DEFAULT_MAX_TAKE_VALUE));
+        let take = take
+            .unwrap_or_else(|| {
+                state
+                    .data
+                    .default_take_value
+                    .clone()
+                    .unwrap_or(Nat::from(icrc7::DEFAULT_TAKE_VALUE))
+            })
+            .min(max_take_value);
+        let take = usize::try_from(take.0).unwrap_or(icrc7::DEFAULT_TAKE_VALUE);
+
+        let listings = match prev {
+            Some(prev) => state
+                .data
+                .listings
+                .range((std::ops::Bound::Excluded(prev), std::ops::Bound::Unbounded)),
+            None => state.data.listings.range(..),
+        };
+
+        listings
+            .map(|(_, listing)| listing)
+            .filter(|listing| !listing.is_expired(now))
+            .take(take)
+            .cloned()
+            .collect()
+    })
+}
+
+#[update(guard = "caller_has_update_collection_metadata_permission")]
+pub async fn settle_marketplace_payouts() -> marketplace::settle_marketplace_payouts::Response {
+    let payouts = mutate_state(|state| std::mem::take(&mut state.data.marketplace_pending_payouts));
+
+    for payout in payouts {
+        pay_out(
+            payout.ledger,
+            payout.from_subaccount,
+            payout.to,
+            payout.amount,
+        )
+        .await;
+    }
+
+    read_state(|state| state.data.marketplace_pending_payouts.clone())
+}
+
+fn unlock_listing(listing: &Listing) {
+    mutate_state(|state| {
+        if let Some(current) = state.data.listings.get_mut(&listing.token_id) {
+            if current.seller == listing.seller {
+                current.pending_purchase = false;
+            }
+        }
+    });
+}
+
+// With royalty enforcement on, a sale must be priced in the ledger the royalties ask for.
+pub(crate) fn is_sale_ledger_allowed(token_id: &Nat, ledger: Principal) -> bool {
+    if !read_state(|state| state.data.royalty_enforcement) {
+        return true;
+    }
+    get_royalties(token_id)
+        .and_then(|royalties| royalties.ledger)
+        .is_none_or(|royalty_ledger| royalty_ledger == ledger)
+}
+
+// Pays each royalty recipient their share of the price and the seller the remainder. Shares that
+// would not cover the ledger fee go to the seller instead of staying in escrow.
+pub(crate) async fn pay_sale_proceeds(
+    ledger: Principal,
+    from_subaccount: Option<Subaccount>,
+    token_id: &Nat,
+    seller: Account,
+    price: Nat,
+) {
+    let payouts = get_royalties(token_id)
+        .map(|royalties| royalties.payouts(&price))
+        .unwrap_or_default();
+    let fee = ledger::icrc1_fee(ledger).await.ok();
+
+    let mut seller_amount = price;
+    for payout in payouts {
+        if fee.as_ref().is_some_and(|fee| payout.amount <= *fee) {
+            continue;
+        }
+        seller_amount -= payout.amount.clone();
+        pay_out(ledger, from_subaccount, payout.account, payout.amount).await;
+    }
+
+    pay_out(ledger, from_subaccount, seller, seller_amount).await;
+}
+
+// Sends the amount minus the ledger fee, keeping it as a pending payout if the transfer fails.
+// Amounts that do not cover the fee stay with the canister.
+pub(crate) async fn pay_out(
+    ledger: Principal,
+    from_subaccount: Option<Subaccount>,
+    to: Account,
+    amount: Nat,
+) -> Option<Nat> {
+    let result = match ledger::icrc1_fee(ledger).await {
+        Ok(fee) if amount > fee => ledger::icrc1_transfer(
+            ledger,
+            TransferArg {
+                from_subaccount,
+                to,
+                fee: None,
+                created_at_time: None,
+                memo: None,
+                amount: amount.clone() - fee,
+            },
+        )
+        .await
+        .and_then(|result| result.map_err(|e| format!("{:?}", e))),
+        Ok(fee) => {
+            trace(&format!(
+                "Marketplace payout of {} to {} does not cover the {} fee",
+                amount, to, fee
+            ));
+            return None;
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(block) => Some(block),
+        Err(e) => {
+            trace(&format!("Marketplace payout to {} failed: {}", to, e));
+            mutate_state(|state| {
+                state.data.marketplace_pending_payouts.push(PendingPayout {
+                    ledger,
+                    from_subaccount,
+                    to,
+                    amount,
+                })
+            });
+            None
+        }
+    }
+}
@@ -1,7 +1,25 @@
 mod icrc37;
 pub mod icrc7;
 pub mod management;
+pub mod marketplace;
+pub mod multisig;
+pub mod nest;
+pub mod offer;
+pub mod public_mint;
+pub mod reveal;
+pub mod timelock;
+pub mod token_account;
+pub mod user;
 
 pub use icrc37::*;
 pub use icrc7::*;
 pub use management::*;
+pub use marketplace::*;
+pub use multisig::*;
+pub use nest::*;
+pub use offer::*;
+pub use public_mint::*;
+pub use reveal::*;
+pub use timelock::*;
+pub use token_account::*;
+pub use user::*;
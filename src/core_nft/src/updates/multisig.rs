@@ -0,0 +1,339 @@
+use crate::guards::{caller_has_manage_authorities_permission, GuardManagement};
+use crate::state::{icrc3_add_transaction, mutate_state, read_state, UpgradeArgs, STORAGE_WASM};
+use crate::types::icrc3::ExtensionTransaction;
+use crate::types::icrc7;
+use crate::types::multisig::{self, Proposal, ProposalAction, ProposalStatus};
+use crate::types::royalties::account_to_value;
+use crate::types::sub_canister::ArgsStorage;
+use crate::updates::management::{
+    delete_role_impl, grant_permission_impl, grant_role_impl, revoke_permission_impl,
+    revoke_role_impl, set_collection_paused, set_role_impl, update_collection_metadata_impl,
+};
+use crate::updates::timelock::schedule_operation_impl;
+use crate::utils::trace;
+
+use candid::{Nat, Principal};
+use ic_cdk_macros::{query, update};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+#[update(guard = "caller_has_manage_authorities_permission")]
+pub fn set_multisig_config(
+    config: multisig::set_multisig_config::Args,
+) -> multisig::set_multisig_config::Response {
+    use multisig::set_multisig_config::SetMultisigConfigError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller)
+        .map_err(|_| SetMultisigConfigError::ConcurrentManagementCall)?;
+
+    if read_state(|state| state.data.multisig.is_some()) {
+        return Err(SetMultisigConfigError::MultisigRequired);
+    }
+    if !config.is_valid() {
+        return Err(SetMultisigConfigError::InvalidConfig);
+    }
+
+    mutate_state(|state| state.data.multisig = Some(config));
+    Ok(())
+}
+
+#[query]
+pub fn get_multisig_config() -> multisig::get_multisig_config::Response {
+    read_state(|state| state.data.multisig.clone())
+}
+
+#[update]
+pub async fn create_proposal(
+    action: multisig::create_proposal::Args,
+) -> multisig::create_proposal::Response {
+    use multisig::create_proposal::CreateProposalError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal =
+        GuardManagement::new(caller).map_err(|_| CreateProposalError::ConcurrentManagementCall)?;
+
+    let config = read_state(|state| state.data.multisig.clone())
+        .ok_or(CreateProposalError::MultisigNotConfigured)?;
+    if !config.is_signer(&caller) {
+        return Err(CreateProposalError::NotSigner);
+    }
+    if let ProposalAction::SetMultisigConfig(Some(new_config)) = &action {
+        if !new_config.is_valid() {
+            return Err(CreateProposalError::InvalidAction(
+                "Invalid multisig config".to_string(),
+            ));
+        }
+    }
+
+    let now = ic_cdk::api::time();
+    let proposal = Proposal {
+        proposal_id: read_state(|state| state.data.next_proposal_id.clone()),
+        action,
+        proposer: caller,
+        approvals: vec![caller],
+        created_at: now,
+        expires_at: now.saturating_add(config.proposal_duration),
+        status: ProposalStatus::Open,
+    };
+
+    let mut tx = proposal_block(&proposal);
+    tx.insert("from".to_string(), account_to_value(&Account::from(caller)));
+    tx.insert(
+        "exp".to_string(),
+        ICRC3Value::Nat(Nat::from(proposal.expires_at)),
+    );
+    icrc3_add_transaction(ExtensionTransaction::new("proposal_create", now, tx))
+        .map_err(|e| CreateProposalError::StorageCanisterError(e.to_string()))?;
+
+    let proposal_id = proposal.proposal_id.clone();
+    let approved = proposal.approval_count(&config) >= config.threshold as usize;
+    mutate_state(|state| {
+        state.data.next_proposal_id += Nat::from(1u64);
+        state.data.proposals.insert(proposal_id.clone(), proposal);
+    });
+
+    if approved {
+        execute_proposal(caller, &proposal_id).await;
+    }
+
+    Ok(proposal_id)
+}
+
+#[update]
+pub async fn approve_proposal(
+    proposal_id: multisig::approve_proposal::Args,
+) -> multisig::approve_proposal::Response {
+    use multisig::approve_proposal::ApproveProposalError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal =
+        GuardManagement::new(caller).map_err(|_| ApproveProposalError::ConcurrentManagementCall)?;
+
+    let config = read_state(|state| state.data.multisig.clone())
+        .ok_or(ApproveProposalError::MultisigNotConfigured)?;
+    if !config.is_signer(&caller) {
+        return Err(ApproveProposalError::NotSigner);
+    }
+
+    let now = ic_cdk::api::time();
+    let proposal = read_state(|state| state.data.proposals.get(&proposal_id).cloned())
+        .ok_or(ApproveProposalError::ProposalNotFound)?;
+    if proposal.status != ProposalStatus::Open {
+        return Err(ApproveProposalError::ProposalNotOpen);
+    }
+    if proposal.is_expired(now) {
+        expire_proposals();
+        return Err(ApproveProposalError::ProposalExpired);
+    }
+    if proposal.approvals.contains(&caller) {
+        return Err(ApproveProposalError::AlreadyApproved);
+    }
+
+    let mut tx = proposal_block(&proposal);
+    tx.insert("from".to_string(), account_to_value(&Account::from(caller)));
+    icrc3_add_transaction(ExtensionTransaction::new("proposal_approve", now, tx))
+        .map_err(|e| ApproveProposalError::StorageCanisterError(e.to_string()))?;
+
+    let approval_count = mutate_state(|state| {
+        let proposal = state.data.proposals.get_mut(&proposal_id)?;
+        proposal.approvals.push(caller);
+        Some(proposal.approval_count(&config))
+    })
+    .unwrap_or_default();
+
+    if approval_count >= config.threshold as usize {
+        execute_proposal(caller, &proposal_id).await;
+    }
+
+    Ok(read_state(|state| {
+        state
+            .data
+            .proposals
+            .get(&proposal_id)
+            .map(|proposal| proposal.status.clone())
+            .unwrap_or(ProposalStatus::Open)
+    }))
+}
+
+#[query]
+pub fn get_proposal(proposal_id: multisig::get_proposal::Args) -> multisig::get_proposal::Response {
+    read_state(|state| state.data.proposals.get(&proposal_id).cloned())
+}
+
+#[query]
+pub fn list_proposals(args: multisig::list_proposals::Args) -> multisig::list_proposals::Response {
+    let max_take_value = read_state(|state| state.data.max_take_value.clone())
+        .unwrap_or(Nat::from(icrc7::DEFAULT_MAX_TAKE_VALUE));
+    if let Some(take) = &args.take {
+        if take.0 > max_take_value.0 {
+            ic_cdk::trap(format!(
+                "max_take_value exceeded. Limit is {}. Retry with a smaller take value.",
+                max_take_value.0
+            ));
+        }
+    }
+
+    read_state(|state| {
+        let take = usize::try_from(
+            args.take
+                .unwrap_or_else(|| {
+                    state
+                        .data
+                        .default_take_value
+                        .clone()
+                        .unwrap_or(Nat::from(icrc7::DEFAULT_TAKE_VALUE))
+                })
+                .0,
+        )
+        .unwrap_or(icrc7::DEFAULT_TAKE_VALUE);
+
+        let start = match args.prev {
+            Some(prev) => Bound::Excluded(prev),
+            None => Bound::Unbounded,
+        };
+        state
+            .data
+            .proposals
+            .range((start, Bound::Unbounded))
+            .take(take)
+            .map(|(_, proposal)| proposal.clone())
+            .collect()
+    })
+}
+
+// Open proposals past their deadline can no longer be approved.
+pub(crate) fn expire_proposals() {
+    let now = ic_cdk::api::time();
+    let expired: Vec<Proposal> = mutate_state(|state| {
+        state
+            .data
+            .proposals
+            .values_mut()
+            .filter(|proposal| proposal.status == ProposalStatus::Open && proposal.is_expired(now))
+            .map(|proposal| {
+                proposal.status = ProposalStatus::Expired;
+                proposal.clone()
+            })
+            .collect()
+    });
+
+    for proposal in &expired {
+        log_proposal_block("proposal_expire", proposal_block(proposal));
+    }
+}
+
+async fn execute_proposal(executor: Principal, proposal_id: &Nat) {
+    let Some(proposal) = mutate_state(|state| {
+        let proposal = state.data.proposals.get_mut(proposal_id)?;
+        proposal.status = ProposalStatus::Approved;
+        Some(proposal.clone())
+    }) else {
+        return;
+    };
+
+    let status = match execute_action(executor, proposal.action.clone()).await {
+        Ok(()) => ProposalStatus::Executed,
+        Err(e) => ProposalStatus::Failed(e),
+    };
+
+    let mut tx = proposal_block(&proposal);
+    tx.insert(
+        "from".to_string(),
+        account_to_value(&Account::from(executor)),
+    );
+    if let ProposalStatus::Failed(e) = &status {
+        tx.insert("err".to_string(), ICRC3Value::Text(e.clone()));
+    }
+
+    mutate_state(|state| {
+        if let Some(proposal) = state.data.proposals.get_mut(proposal_id) {
+            proposal.status = status;
+        }
+    });
+    log_proposal_block("proposal_execute", tx);
+}
+
+// The executor stands in for the caller of the underlying management call.
+async fn execute_action(executor: Principal, action: ProposalAction) -> Result<(), String> {
+    match action {
+        ProposalAction::GrantPermission(args) => {
+            grant_permission_impl(executor, args).map_err(|e| format!("{:?}", e))
+        }
+        ProposalAction::RevokePermission(args) => {
+            revoke_permission_impl(executor, args).map_err(|e| format!("{:?}", e))
+        }
+        ProposalAction::GrantRole(args) => {
+            grant_role_impl(executor, args).map_err(|e| format!("{:?}", e))
+        }
+        ProposalAction::RevokeRole(args) => {
+            revoke_role_impl(executor, args).map_err(|e| format!("{:?}", e))
+        }
+        ProposalAction::SetRole(args) => {
+            set_role_impl(executor, args).map_err(|e| format!("{:?}", e))
+        }
+        ProposalAction::DeleteRole(name) => {
+            delete_role_impl(executor, name).map_err(|e| format!("{:?}", e))
+        }
+        ProposalAction::UpdateCollectionMetadata(args) => {
+            update_collection_metadata_impl(executor, *args).map_err(|e| format!("{:?}", e))
+        }
+        ProposalAction::UpgradeStorageCanisters => upgrade_storage_canisters().await,
+        ProposalAction::PauseCollection => {
+            set_collection_paused(executor, true).map_err(|e| format!("{:?}", e))
+        }
+        ProposalAction::UnpauseCollection => {
+            set_collection_paused(executor, false).map_err(|e| format!("{:?}", e))
+        }
+        ProposalAction::SetMultisigConfig(config) => {
+            mutate_state(|state| state.data.multisig = config);
+            Ok(())
+        }
+        ProposalAction::ScheduleOperation(operation) => {
+            schedule_operation_impl(executor, operation)
+                .map(|_| ())
+                .map_err(|e| format!("{:?}", e))
+        }
+    }
+}
+
+async fn upgrade_storage_canisters() -> Result<(), String> {
+    let (mut sub_canister_manager, upgrade_args) = read_state(|state| {
+        (
+            state.data.sub_canister_manager.clone(),
+            ArgsStorage::Upgrade(UpgradeArgs {
+                version: state.env.version(),
+                commit_hash: state.env.commit_hash().to_string(),
+            }),
+        )
+    });
+
+    let result = sub_canister_manager
+        .upgrade_canisters(STORAGE_WASM.to_vec(), upgrade_args)
+        .await;
+
+    mutate_state(|state| state.data.sub_canister_manager = sub_canister_manager);
+    result.map_err(|errors| errors.join("; "))
+}
+
+fn proposal_block(proposal: &Proposal) -> BTreeMap<String, ICRC3Value> {
+    let mut tx = BTreeMap::new();
+    tx.insert(
+        "pid".to_string(),
+        ICRC3Value::Nat(proposal.proposal_id.clone()),
+    );
+    tx.insert(
+        "action".to_string(),
+        ICRC3Value::Text(proposal.action.name().to_string()),
+    );
+    tx
+}
+
+fn log_proposal_block(btype: &str, tx: BTreeMap<String, ICRC3Value>) {
+    let timestamp = ic_cdk::api::time();
+    if let Err(e) = icrc3_add_transaction(ExtensionTransaction::new(btype, timestamp, tx)) {
+        trace(&format!("Failed to log {} block: {}", btype, e));
+    }
+}
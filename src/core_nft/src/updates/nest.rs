@@ -0,0 +1,279 @@
+use crate::ledger;
+use crate::state::{icrc3_add_transaction, mutate_state, read_state};
+use crate::types::icrc3::ExtensionTransaction;
+use crate::types::icrc7;
+use crate::types::nest;
+use crate::types::nest::{ChildToken, NestingError, MAX_NESTING_DEPTH};
+use crate::types::nft::token_subaccount;
+use crate::types::royalties::account_to_value;
+use crate::updates::icrc7::commit_transfer;
+use crate::utils::{is_collection_paused, is_token_transferable, token_account, trace};
+
+use candid::{Nat, Principal};
+use ic_cdk_macros::{query, update};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use std::collections::BTreeMap;
+
+enum ParentError {
+    TokenDoesNotExist,
+    NotTokenOwner,
+    MaxDepthExceeded,
+}
+
+// The parent is controlled by whoever owns the root of its tree.
+fn check_parent(parent_id: &Nat, caller: &Account) -> Result<(), ParentError> {
+    match read_state(|state| state.data.root_owner_of(parent_id)) {
+        Err(NestingError::TokenDoesNotExist) => Err(ParentError::TokenDoesNotExist),
+        Err(NestingError::MaxDepthExceeded) => Err(ParentError::MaxDepthExceeded),
+        Ok(root_owner) if root_owner != *caller => Err(ParentError::NotTokenOwner),
+        Ok(_) => Ok(()),
+    }
+}
+
+#[update]
+pub async fn attach_child(req: nest::attach_child::Args) -> nest::attach_child::Response {
+    use nest::attach_child::AttachChildError;
+
+    let caller = Account {
+        owner: ic_cdk::api::msg_caller(),
+        subaccount: req.from_subaccount,
+    };
+    let child = req.child;
+
+    let check_attachable = || -> Result<(), AttachChildError> {
+        check_parent(&req.parent_id, &caller).map_err(|e| match e {
+            ParentError::TokenDoesNotExist => AttachChildError::TokenDoesNotExist,
+            ParentError::NotTokenOwner => AttachChildError::NotTokenOwner,
+            ParentError::MaxDepthExceeded => AttachChildError::MaxDepthExceeded,
+        })?;
+        if read_state(|state| state.data.children_of(&req.parent_id)).contains(&child) {
+            return Err(AttachChildError::AlreadyAttached);
+        }
+        Ok(())
+    };
+
+    if is_collection_paused() {
+        return Err(AttachChildError::CollectionPaused);
+    }
+
+    check_attachable()?;
+
+    let ancestors = read_state(|state| state.data.ancestors_of(&req.parent_id))
+        .map_err(|_| AttachChildError::MaxDepthExceeded)?;
+    if ancestors.len() + 1 >= MAX_NESTING_DEPTH {
+        return Err(AttachChildError::MaxDepthExceeded);
+    }
+
+    let is_local = child.collection == ic_cdk::api::canister_self();
+    let parent_account = token_account(&req.parent_id);
+
+    if is_local {
+        if child.token_id == req.parent_id || ancestors.contains(&child.token_id) {
+            return Err(AttachChildError::CycleDetected);
+        }
+
+        // The child's own children move down with it, so its subtree counts towards the depth.
+        let max_height = MAX_NESTING_DEPTH - ancestors.len() - 2;
+        read_state(|state| state.data.subtree_height(&child.token_id, max_height))
+            .map_err(|_| AttachChildError::MaxDepthExceeded)?;
+
+        let owner = read_state(|state| state.data.owner_of(&child.token_id))
+            .ok_or(AttachChildError::TokenDoesNotExist)?;
+        if owner != caller {
+            return Err(AttachChildError::NotTokenOwner);
+        }
+
+        if !is_token_transferable(&child.token_id) {
+            return Err(AttachChildError::NonTransferable);
+        }
+    } else {
+        let owners = ledger::icrc7_owner_of(child.collection, vec![child.token_id.clone()])
+            .await
+            .map_err(AttachChildError::CollectionCallFailed)?;
+        if owners.into_iter().next().flatten() != Some(parent_account) {
+            return Err(AttachChildError::ChildNotHeldByParent);
+        }
+
+        // The parent may have moved or been nested elsewhere while the call was in flight.
+        check_attachable()?;
+    }
+
+    let now = ic_cdk::api::time();
+
+    if is_local {
+        let nft = read_state(|state| state.data.get_token_by_id(&child.token_id))
+            .ok_or(AttachChildError::TokenDoesNotExist)?;
+        commit_transfer(nft, parent_account, None, now, now)
+            .map_err(|e| AttachChildError::StorageCanisterError(format!("{:?}", e)))?;
+    }
+
+    mutate_state(|state| state.data.add_child(&req.parent_id, child.clone()));
+
+    let mut tx = nest_block(&req.parent_id, &child);
+    tx.insert("from".to_string(), account_to_value(&caller));
+    let index =
+        log_nest_block("nest_attach", now, tx).map_err(AttachChildError::StorageCanisterError)?;
+
+    trace(&format!(
+        "Attached {}:{} to token {}",
+        child.collection, child.token_id, req.parent_id
+    ));
+    Ok(Nat::from(index))
+}
+
+#[update]
+pub async fn detach_child(req: nest::detach_child::Args) -> nest::detach_child::Response {
+    use nest::detach_child::DetachChildError;
+
+    let caller = Account {
+        owner: ic_cdk::api::msg_caller(),
+        subaccount: req.from_subaccount,
+    };
+    let to = req.to.unwrap_or(caller);
+    let child = req.child;
+
+    if is_collection_paused() {
+        return Err(DetachChildError::CollectionPaused);
+    }
+
+    check_parent(&req.parent_id, &caller).map_err(|e| match e {
+        ParentError::TokenDoesNotExist => DetachChildError::TokenDoesNotExist,
+        ParentError::NotTokenOwner => DetachChildError::NotTokenOwner,
+        ParentError::MaxDepthExceeded => DetachChildError::MaxDepthExceeded,
+    })?;
+
+    if to.owner == Principal::anonymous() {
+        return Err(DetachChildError::InvalidRecipient);
+    }
+
+    let now = ic_cdk::api::time();
+    let is_local = child.collection == ic_cdk::api::canister_self();
+
+    // Removed before any await so the child cannot be detached twice.
+    if !mutate_state(|state| state.data.remove_child(&req.parent_id, &child)) {
+        return Err(DetachChildError::ChildNotFound);
+    }
+
+    if is_local {
+        let nft = read_state(|state| state.data.get_token_by_id(&child.token_id))
+            .ok_or(DetachChildError::ChildNotFound)?;
+        if let Err(e) = commit_transfer(nft, to, None, now, now) {
+            mutate_state(|state| state.data.add_child(&req.parent_id, child.clone()));
+            return Err(DetachChildError::TransferFailed(format!("{:?}", e)));
+        }
+    } else {
+        let transfer = ledger::icrc7_transfer(
+            child.collection,
+            vec![icrc7::TransferArg {
+                to,
+                token_id: child.token_id.clone(),
+                memo: None,
+                from_subaccount: Some(serde_bytes::ByteBuf::from(
+                    token_subaccount(&req.parent_id).to_vec(),
+                )),
+                created_at_time: None,
+            }],
+        )
+        .await;
+
+        let result = match transfer {
+            Ok(results) => match results.into_iter().next().flatten() {
+                Some(Ok(_)) => Ok(()),
+                Some(Err(e)) => Err(DetachChildError::TransferFailed(format!("{:?}", e))),
+                None => Err(DetachChildError::TransferFailed(
+                    "Empty transfer response".to_string(),
+                )),
+            },
+            Err(e) => Err(DetachChildError::CollectionCallFailed(e)),
+        };
+
+        if let Err(e) = result {
+            mutate_state(|state| state.data.add_child(&req.parent_id, child.clone()));
+            return Err(e);
+        }
+    }
+
+    let mut tx = nest_block(&req.parent_id, &child);
+    tx.insert("from".to_string(), account_to_value(&caller));
+    tx.insert("to".to_string(), account_to_value(&to));
+
+    let index =
+        log_nest_block("nest_detach", now, tx).map_err(DetachChildError::StorageCanisterError)?;
+
+    trace(&format!(
+        "Detached {}:{} from token {}",
+        child.collection, child.token_id, req.parent_id
+    ));
+    Ok(Nat::from(index))
+}
+
+#[query]
+pub fn get_children(token_id: nest::get_children::Args) -> nest::get_children::Response {
+    read_state(|state| state.data.children_of(&token_id))
+}
+
+#[query]
+pub fn get_parent(token_id: nest::get_parent::Args) -> nest::get_parent::Response {
+    read_state(|state| state.data.parent_of(&token_id))
+}
+
+#[query]
+pub fn icrc7_root_owner_of(
+    token_ids: nest::icrc7_root_owner_of::Args,
+) -> nest::icrc7_root_owner_of::Response {
+    let icrc7_max_query_batch_size = read_state(|state| state.data.max_query_batch_size.clone());
+    let max_query_batch_size =
+        icrc7_max_query_batch_size.unwrap_or(Nat::from(icrc7::DEFAULT_MAX_QUERY_BATCH_SIZE));
+
+    if token_ids.len()
+        > usize::try_from(max_query_batch_size.0.clone())
+            .unwrap_or(icrc7::DEFAULT_MAX_QUERY_BATCH_SIZE as usize)
+    {
+        ic_cdk::trap(format!(
+            "max_query_batch_size exceeded. Limit is {}. Retry with a smaller batch size.",
+            max_query_batch_size.0
+        ));
+    }
+
+    read_state(|state| {
+        token_ids
+            .iter()
+            .map(|token_id| match state.data.root_owner_of(token_id) {
+                Ok(owner) => Some(owner),
+                Err(NestingError::TokenDoesNotExist) => None,
+                Err(NestingError::MaxDepthExceeded) => ic_cdk::trap(format!(
+                    "Token {} is nested deeper than {} levels.",
+                    token_id, MAX_NESTING_DEPTH
+                )),
+            })
+            .collect()
+    })
+}
+
+fn nest_block(parent_id: &Nat, child: &ChildToken) -> BTreeMap<String, ICRC3Value> {
+    let mut tx = BTreeMap::new();
+    tx.insert("tid".to_string(), ICRC3Value::Nat(parent_id.clone()));
+    tx.insert(
+        "child_coll".to_string(),
+        ICRC3Value::Text(child.collection.to_string()),
+    );
+    tx.insert(
+        "child_tid".to_string(),
+        ICRC3Value::Nat(child.token_id.clone()),
+    );
+    tx
+}
+
+// The child has already moved when this is called, so a failure to log is traced rather than
+// rolled back.
+fn log_nest_block(
+    btype: &str,
+    timestamp: u64,
+    tx: BTreeMap<String, ICRC3Value>,
+) -> Result<u64, String> {
+    icrc3_add_transaction(ExtensionTransaction::new(btype, timestamp, tx)).map_err(|e| {
+        trace(&format!("Failed to log {} block: {}", btype, e));
+        e.to_string()
+    })
+}
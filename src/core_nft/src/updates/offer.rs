@@ -0,0 +1,300 @@
+use crate::ledger;
+use crate::state::{icrc3_add_transaction, mutate_state, read_state};
+use crate::types::icrc3::ExtensionTransaction;
+use crate::types::offer;
+use crate::types::offer::{offer_escrow_subaccount, Offer};
+use crate::types::royalties::account_to_value;
+use crate::updates::icrc7::commit_transfer;
+use crate::updates::marketplace::{is_sale_ledger_allowed, pay_out, pay_sale_proceeds};
+use crate::utils::{is_collection_paused, is_token_transferable, trace};
+
+use candid::{Nat, Principal};
+use ic_cdk_macros::{query, update};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromArgs;
+use std::collections::BTreeMap;
+
+#[update]
+pub async fn make_offer(req: offer::make_offer::Args) -> offer::make_offer::Response {
+    use offer::make_offer::MakeOfferError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let now = ic_cdk::api::time();
+
+    if caller == Principal::anonymous() {
+        return Err(MakeOfferError::AnonymousCaller);
+    }
+
+    let config = read_state(|state| state.data.marketplace.clone())
+        .ok_or(MakeOfferError::MarketplaceClosed)?;
+
+    if is_collection_paused() {
+        return Err(MakeOfferError::CollectionPaused);
+    }
+
+    let buyer = Account {
+        owner: caller,
+        subaccount: req.from_subaccount,
+    };
+    let owner = read_state(|state| state.data.owner_of(&req.token_id))
+        .ok_or(MakeOfferError::TokenDoesNotExist)?;
+
+    if owner == buyer {
+        return Err(MakeOfferError::AlreadyTokenOwner);
+    }
+
+    if !is_token_transferable(&req.token_id) {
+        return Err(MakeOfferError::NonTransferable);
+    }
+
+    if !config.ledgers.contains(&req.ledger) || !is_sale_ledger_allowed(&req.token_id, req.ledger) {
+        return Err(MakeOfferError::UnsupportedLedger);
+    }
+
+    if req.amount == 0u64 {
+        return Err(MakeOfferError::InvalidAmount);
+    }
+
+    if req.expires_at <= now {
+        return Err(MakeOfferError::InvalidExpiry);
+    }
+
+    // The id is taken before the payment so the escrow subaccount is known up front.
+    let offer_id = mutate_state(|state| {
+        let offer_id = state.data.next_offer_id.clone();
+        state.data.next_offer_id += Nat::from(1u64);
+        offer_id
+    });
+
+    let payment = ledger::icrc2_transfer_from(
+        req.ledger,
+        TransferFromArgs {
+            spender_subaccount: None,
+            from: buyer,
+            to: Account {
+                owner: ic_cdk::api::canister_self(),
+                subaccount: Some(offer_escrow_subaccount(&offer_id)),
+            },
+            amount: req.amount.clone(),
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        },
+    )
+    .await;
+
+    match payment {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => return Err(MakeOfferError::PaymentFailed(e)),
+        Err(e) => return Err(MakeOfferError::LedgerCallFailed(e)),
+    }
+
+    let offer = Offer {
+        offer_id: offer_id.clone(),
+        token_id: req.token_id,
+        buyer,
+        amount: req.amount,
+        ledger: req.ledger,
+        expires_at: req.expires_at,
+        created_at: now,
+    };
+
+    // The token may have been burned, bought by the bidder or made soulbound while the payment
+    // was in flight; the escrowed funds go back in that case.
+    let recheck = match read_state(|state| state.data.owner_of(&offer.token_id)) {
+        None => Err(MakeOfferError::TokenDoesNotExist),
+        Some(owner) if owner == buyer => Err(MakeOfferError::AlreadyTokenOwner),
+        Some(_) if !is_token_transferable(&offer.token_id) => Err(MakeOfferError::NonTransferable),
+        Some(_) => Ok(()),
+    };
+    if let Err(e) = recheck {
+        refund_offer(offer).await;
+        return Err(e);
+    }
+
+    let mut tx = offer_block(&offer);
+    tx.insert("from".to_string(), account_to_value(&buyer));
+    tx.insert(
+        "ledger".to_string(),
+        ICRC3Value::Text(offer.ledger.to_string()),
+    );
+    tx.insert(
+        "exp".to_string(),
+        ICRC3Value::Nat(Nat::from(offer.expires_at)),
+    );
+    log_offer_block("offer", tx);
+
+    mutate_state(|state| state.data.offers.insert(offer_id.clone(), offer));
+    Ok(offer_id)
+}
+
+#[update]
+pub async fn cancel_offer(offer_id: offer::cancel_offer::Args) -> offer::cancel_offer::Response {
+    use offer::cancel_offer::CancelOfferError;
+
+    let caller = ic_cdk::api::msg_caller();
+
+    let offer = mutate_state(|state| {
+        let offer = state
+            .data
+            .offers
+            .get(&offer_id)
+            .ok_or(CancelOfferError::OfferNotFound)?;
+
+        if offer.buyer.owner != caller {
+            return Err(CancelOfferError::NotBuyer);
+        }
+
+        Ok(state.data.offers.remove(&offer_id).unwrap())
+    })?;
+
+    let mut tx = offer_block(&offer);
+    tx.insert("from".to_string(), account_to_value(&offer.buyer));
+    log_offer_block("offer_cancel", tx);
+
+    Ok(refund_offer(offer).await)
+}
+
+#[update]
+pub async fn accept_offer(req: offer::accept_offer::Args) -> offer::accept_offer::Response {
+    use offer::accept_offer::AcceptOfferError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let now = ic_cdk::api::time();
+
+    if is_collection_paused() {
+        return Err(AcceptOfferError::CollectionPaused);
+    }
+
+    let offer = read_state(|state| state.data.offers.get(&req.offer_id).cloned())
+        .ok_or(AcceptOfferError::OfferNotFound)?;
+
+    if offer.is_expired(now) {
+        return Err(AcceptOfferError::OfferExpired);
+    }
+
+    let nft = read_state(|state| state.data.get_token_by_id(&offer.token_id))
+        .ok_or(AcceptOfferError::TokenDoesNotExist)?;
+
+    let seller = Account {
+        owner: caller,
+        subaccount: req.from_subaccount,
+    };
+    if nft.token_owner != seller {
+        return Err(AcceptOfferError::NotTokenOwner);
+    }
+
+    if !is_token_transferable(&offer.token_id) {
+        return Err(AcceptOfferError::NonTransferable);
+    }
+
+    // Nothing is awaited before the token moves and the offer is removed, so it cannot be
+    // accepted twice.
+    let transfer_block = commit_transfer(nft, offer.buyer, None, now, now)
+        .map_err(|e| AcceptOfferError::TransferFailed(format!("{:?}", e)))?;
+    mutate_state(|state| state.data.offers.remove(&offer.offer_id));
+
+    let mut tx = offer_block(&offer);
+    tx.insert("from".to_string(), account_to_value(&seller));
+    tx.insert("to".to_string(), account_to_value(&offer.buyer));
+    log_offer_block("offer_accept", tx);
+
+    trace(&format!(
+        "Accepted offer {} on token {} for {}",
+        offer.offer_id, offer.token_id, offer.amount
+    ));
+
+    pay_sale_proceeds(
+        offer.ledger,
+        Some(offer.escrow_subaccount()),
+        &offer.token_id,
+        seller,
+        offer.amount,
+    )
+    .await;
+
+    Ok(transfer_block)
+}
+
+#[query]
+pub fn get_offer(offer_id: offer::get_offer::Args) -> offer::get_offer::Response {
+    read_state(|state| state.data.offers.get(&offer_id).cloned())
+}
+
+#[query]
+pub fn get_token_offers(
+    token_id: offer::get_token_offers::Args,
+) -> offer::get_token_offers::Response {
+    let now = ic_cdk::api::time();
+    read_state(|state| {
+        state
+            .data
+            .offers
+            .values()
+            .filter(|offer| offer.token_id == token_id && !offer.is_expired(now))
+            .cloned()
+            .collect()
+    })
+}
+
+// Removes expired offers and offers on burned tokens, refunding their escrow.
+pub(crate) async fn expire_offers() {
+    let now = ic_cdk::api::time();
+    let expired = mutate_state(|state| {
+        let expired_ids: Vec<Nat> = state
+            .data
+            .offers
+            .values()
+            .filter(|offer| {
+                offer.is_expired(now) || state.data.get_token_by_id(&offer.token_id).is_none()
+            })
+            .map(|offer| offer.offer_id.clone())
+            .collect();
+
+        expired_ids
+            .iter()
+            .filter_map(|offer_id| state.data.offers.remove(offer_id))
+            .collect::<Vec<Offer>>()
+    });
+
+    for offer in &expired {
+        let mut tx = offer_block(offer);
+        tx.insert("from".to_string(), account_to_value(&offer.buyer));
+        log_offer_block("offer_expire", tx);
+    }
+
+    for offer in expired {
+        refund_offer(offer).await;
+    }
+}
+
+async fn refund_offer(offer: Offer) -> Option<Nat> {
+    pay_out(
+        offer.ledger,
+        Some(offer.escrow_subaccount()),
+        offer.buyer,
+        offer.amount,
+    )
+    .await
+}
+
+fn offer_block(offer: &Offer) -> BTreeMap<String, ICRC3Value> {
+    let mut tx = BTreeMap::new();
+    tx.insert("tid".to_string(), ICRC3Value::Nat(offer.token_id.clone()));
+    tx.insert(
+        "offer_id".to_string(),
+        ICRC3Value::Nat(offer.offer_id.clone()),
+    );
+    tx.insert("amt".to_string(), ICRC3Value::Nat(offer.amount.clone()));
+    tx
+}
+
+// The escrowed funds have already moved at this point, so a failure to log is traced rather than
+// rolled back.
+fn log_offer_block(btype: &str, tx: BTreeMap<String, ICRC3Value>) {
+    let timestamp = ic_cdk::api::time();
+    if let Err(e) = icrc3_add_transaction(ExtensionTransaction::new(btype, timestamp, tx)) {
+        trace(&format!("Failed to log {} block: {}", btype, e));
+    }
+}
@@ -0,0 +1,505 @@
+use crate::guards::{
+    caller_has_minting_permission, caller_has_update_collection_metadata_permission,
+    GuardManagement,
+};
+use crate::ledger;
+use crate::state::{mutate_state, read_state};
+use crate::types::metadata::is_reserved_metadata_key;
+use crate::types::public_mint::{
+    verify_allowlist_proof, MintPhase, MintPhaseKind, PublicMintConfig,
+};
+use crate::types::{icrc7, management, public_mint};
+use crate::updates::management::mint_tokens;
+use crate::utils::{is_collection_paused, trace};
+
+use candid::{Nat, Principal};
+use ic_cdk_macros::{query, update};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::TransferArg;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromArgs;
+use serde_bytes::ByteBuf;
+
+#[update(guard = "caller_has_minting_permission")]
+pub fn set_public_mint_config(
+    config: public_mint::set_public_mint_config::Args,
+) -> public_mint::set_public_mint_config::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller).map_err(|_| {
+        public_mint::set_public_mint_config::SetPublicMintConfigError::ConcurrentManagementCall
+    })?;
+
+    if let Some(config) = &config {
+        if let Some((key, _)) = config
+            .metadata
+            .iter()
+            .find(|(key, _)| is_reserved_metadata_key(key))
+        {
+            return Err(
+                public_mint::set_public_mint_config::SetPublicMintConfigError::ReservedMetadataKey(
+                    key.clone(),
+                ),
+            );
+        }
+        read_state(|state| state.data.metadata_validation.validate(&config.metadata)).map_err(
+            public_mint::set_public_mint_config::SetPublicMintConfigError::InvalidMetadata,
+        )?;
+        config.validate_phases().map_err(
+            public_mint::set_public_mint_config::SetPublicMintConfigError::InvalidPhases,
+        )?;
+    }
+
+    mutate_state(|state| state.data.public_mint = config);
+    Ok(())
+}
+
+#[query]
+pub fn get_public_mint_config() -> public_mint::get_public_mint_config::Response {
+    read_state(|state| state.data.public_mint.clone())
+}
+
+#[query]
+pub fn get_public_mint_purchases(
+    buyer: public_mint::get_public_mint_purchases::Args,
+) -> public_mint::get_public_mint_purchases::Response {
+    read_state(|state| {
+        state
+            .data
+            .public_mint_purchases
+            .get(&buyer)
+            .cloned()
+            .unwrap_or_default()
+    })
+}
+
+#[update(guard = "caller_has_update_collection_metadata_permission")]
+pub fn set_allowlist_root(
+    root: public_mint::set_allowlist_root::Args,
+) -> public_mint::set_allowlist_root::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller).map_err(|_| {
+        public_mint::set_allowlist_root::SetAllowlistRootError::ConcurrentManagementCall
+    })?;
+
+    let root = match root {
+        Some(root) => Some(
+            <[u8; 32]>::try_from(root.as_slice())
+                .map_err(|_| public_mint::set_allowlist_root::SetAllowlistRootError::InvalidRoot)?,
+        ),
+        None => None,
+    };
+
+    mutate_state(|state| state.data.allowlist_root = root);
+    Ok(())
+}
+
+#[query]
+pub fn get_allowlist_root() -> public_mint::get_allowlist_root::Response {
+    read_state(|state| {
+        state
+            .data
+            .allowlist_root
+            .map(|root| ByteBuf::from(root.to_vec()))
+    })
+}
+
+#[update]
+pub async fn public_mint(
+    req: public_mint::public_mint::Args,
+) -> public_mint::public_mint::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let quantity = req.quantity;
+
+    let config = read_state(|state| state.data.public_mint.clone())
+        .ok_or(public_mint::public_mint::PublicMintError::PublicMintClosed)?;
+
+    if is_collection_paused() {
+        return Err(public_mint::public_mint::PublicMintError::CollectionPaused);
+    }
+
+    if caller == Principal::anonymous() {
+        return Err(public_mint::public_mint::PublicMintError::AnonymousCaller);
+    }
+
+    let max_batch_size = read_state(|state| {
+        state
+            .data
+            .max_update_batch_size
+            .clone()
+            .unwrap_or(Nat::from(100u64))
+    });
+
+    if quantity == 0u64 || quantity > max_batch_size {
+        return Err(public_mint::public_mint::PublicMintError::InvalidQuantity);
+    }
+
+    let phase = match config.phases.is_empty() {
+        true => None,
+        false => Some(
+            config
+                .active_phase(ic_cdk::api::time())
+                .cloned()
+                .ok_or(public_mint::public_mint::PublicMintError::PublicMintClosed)?,
+        ),
+    };
+
+    if let Some(MintPhase {
+        kind: MintPhaseKind::Allowlist,
+        ..
+    }) = &phase
+    {
+        let allowlisted = read_state(|state| state.data.allowlist_root)
+            .zip(req.proof.as_ref())
+            .is_some_and(|(root, proof)| verify_allowlist_proof(&root, &caller, proof));
+
+        if !allowlisted {
+            return Err(public_mint::public_mint::PublicMintError::NotAllowlisted);
+        }
+    }
+
+    reserve_public_mint(caller, &quantity, &config, phase.as_ref())?;
+
+    let price = phase
+        .as_ref()
+        .map_or(config.price.clone(), |phase| phase.price.clone());
+    let amount = price * quantity.clone();
+    let canister_account = Account {
+        owner: ic_cdk::api::canister_self(),
+        subaccount: None,
+    };
+
+    if amount > 0u64 {
+        let payment = ledger::icrc2_transfer_from(
+            config.ledger,
+            TransferFromArgs {
+                spender_subaccount: None,
+                from: Account {
+                    owner: caller,
+                    subaccount: None,
+                },
+                to: canister_account,
+                amount: amount.clone(),
+                fee: None,
+                memo: None,
+                created_at_time: None,
+            },
+        )
+        .await;
+
+        let payment_error = match payment {
+            Ok(Ok(_)) => None,
+            Ok(Err(e)) => Some(public_mint::public_mint::PublicMintError::PaymentFailed(e)),
+            Err(e) => Some(public_mint::public_mint::PublicMintError::LedgerCallFailed(
+                e,
+            )),
+        };
+
+        if let Some(error) = payment_error {
+            release_public_mint(caller, &quantity, phase.as_ref());
+            return Err(error);
+        }
+    }
+
+    let timestamp = ic_cdk::api::time();
+    let quantity_u64 = u64::try_from(quantity.0.clone()).unwrap_or_default();
+    let mint_requests: Vec<management::mint::MintRequest> = (0..quantity_u64)
+        .map(|_| management::mint::MintRequest {
+            token_owner: Account {
+                owner: caller,
+                subaccount: None,
+            },
+            memo: None,
+            metadata: config.metadata.clone(),
+            token_id: None,
+            created_at_time: None,
+        })
+        .collect();
+
+    let token_ids = match mint_tokens(&mint_requests, timestamp) {
+        Ok(token_ids) => {
+            mutate_state(|state| state.data.public_mint_reserved -= quantity.clone());
+            token_ids
+        }
+        Err(e) => {
+            release_public_mint(caller, &quantity, phase.as_ref());
+
+            let reason = format!("{:?}", e);
+            let refund_block = if amount > 0u64 {
+                refund_public_mint(config.ledger, caller, amount).await
+            } else {
+                None
+            };
+
+            return Err(public_mint::public_mint::PublicMintError::MintFailed {
+                reason,
+                refund_block,
+            });
+        }
+    };
+
+    trace(&format!(
+        "Public mint of {} NFTs for {}",
+        token_ids.len(),
+        caller
+    ));
+
+    if amount > 0u64 && config.treasury != canister_account {
+        forward_public_mint_proceeds(&config, amount).await;
+    }
+
+    Ok(token_ids)
+}
+
+#[update(guard = "caller_has_minting_permission")]
+pub async fn settle_public_mint() -> public_mint::settle_public_mint::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller).map_err(|_| {
+        public_mint::settle_public_mint::SettlePublicMintError::ConcurrentManagementCall
+    })?;
+
+    let config = read_state(|state| state.data.public_mint.clone())
+        .ok_or(public_mint::settle_public_mint::SettlePublicMintError::PublicMintNotConfigured)?;
+
+    let fee = ledger::icrc1_fee(config.ledger)
+        .await
+        .map_err(public_mint::settle_public_mint::SettlePublicMintError::LedgerCallFailed)?;
+
+    let mut result = public_mint::settle_public_mint::SettlePublicMintResult::default();
+    let refunds: Vec<(Principal, Nat)> =
+        mutate_state(|state| state.data.public_mint_pending_refunds.drain().collect());
+
+    let mut refunds = refunds.into_iter();
+    while let Some((buyer, amount)) = refunds.next() {
+        if amount <= fee {
+            trace(&format!(
+                "Dropping refund of {} below the ledger fee",
+                amount
+            ));
+            continue;
+        }
+
+        let refund = transfer_out(
+            config.ledger,
+            Account {
+                owner: buyer,
+                subaccount: None,
+            },
+            amount.clone() - fee.clone(),
+        )
+        .await;
+
+        match refund {
+            Ok(_) => result.refunded += amount - fee.clone(),
+            Err(e) => {
+                mutate_state(|state| {
+                    let pending = &mut state.data.public_mint_pending_refunds;
+                    *pending.entry(buyer).or_default() += amount;
+                    for (buyer, amount) in refunds {
+                        *pending.entry(buyer).or_default() += amount;
+                    }
+                });
+                return Err(e);
+            }
+        }
+    }
+
+    let canister_account = Account {
+        owner: ic_cdk::api::canister_self(),
+        subaccount: None,
+    };
+    let unsettled = mutate_state(|state| std::mem::take(&mut state.data.public_mint_unsettled));
+
+    // Proceeds already sit in the treasury when the canister is its own treasury.
+    if config.treasury == canister_account || unsettled <= fee {
+        if config.treasury != canister_account {
+            mutate_state(|state| state.data.public_mint_unsettled += unsettled);
+        }
+        return Ok(result);
+    }
+
+    match transfer_out(
+        config.ledger,
+        config.treasury,
+        unsettled.clone() - fee.clone(),
+    )
+    .await
+    {
+        Ok(_) => {
+            result.forwarded = unsettled - fee;
+            Ok(result)
+        }
+        Err(e) => {
+            mutate_state(|state| state.data.public_mint_unsettled += unsettled);
+            Err(e)
+        }
+    }
+}
+
+fn reserve_public_mint(
+    caller: Principal,
+    quantity: &Nat,
+    config: &PublicMintConfig,
+    phase: Option<&MintPhase>,
+) -> Result<(), public_mint::public_mint::PublicMintError> {
+    mutate_state(|state| {
+        let supply_cap = state
+            .data
+            .supply_cap
+            .clone()
+            .unwrap_or(Nat::from(icrc7::DEFAULT_MAX_SUPPLY_CAP));
+
+        if state.data.total_supply() + state.data.public_mint_reserved.clone() + quantity.clone()
+            > supply_cap
+        {
+            return Err(public_mint::public_mint::PublicMintError::ExceedMaxAllowedSupplyCap);
+        }
+
+        let purchased = state
+            .data
+            .public_mint_purchases
+            .get(&caller)
+            .cloned()
+            .unwrap_or_default();
+        let mut remaining = remaining_purchases(config.max_per_account.as_ref(), &purchased);
+
+        if let Some(phase) = phase {
+            let phase_purchased = state
+                .data
+                .public_mint_phase_purchases
+                .get(&phase.name)
+                .and_then(|purchases| purchases.get(&caller))
+                .cloned()
+                .unwrap_or_default();
+            let phase_remaining =
+                remaining_purchases(phase.max_per_account.as_ref(), &phase_purchased);
+            remaining = match (remaining, phase_remaining) {
+                (Some(remaining), Some(phase_remaining)) => Some(remaining.min(phase_remaining)),
+                (remaining, phase_remaining) => remaining.or(phase_remaining),
+            };
+        }
+
+        if let Some(remaining) = remaining {
+            if *quantity > remaining {
+                return Err(
+                    public_mint::public_mint::PublicMintError::ExceedAccountLimit { remaining },
+                );
+            }
+        }
+
+        state.data.public_mint_reserved += quantity.clone();
+        *state.data.public_mint_purchases.entry(caller).or_default() += quantity.clone();
+        if let Some(phase) = phase {
+            *state
+                .data
+                .public_mint_phase_purchases
+                .entry(phase.name.clone())
+                .or_default()
+                .entry(caller)
+                .or_default() += quantity.clone();
+        }
+        Ok(())
+    })
+}
+
+fn remaining_purchases(max_per_account: Option<&Nat>, purchased: &Nat) -> Option<Nat> {
+    max_per_account.map(|max_per_account| {
+        if max_per_account > purchased {
+            max_per_account.clone() - purchased.clone()
+        } else {
+            Nat::from(0u64)
+        }
+    })
+}
+
+fn release_public_mint(caller: Principal, quantity: &Nat, phase: Option<&MintPhase>) {
+    mutate_state(|state| {
+        state.data.public_mint_reserved -= quantity.clone();
+        if let Some(purchased) = state.data.public_mint_purchases.get_mut(&caller) {
+            *purchased -= quantity.clone();
+        }
+        if let Some(purchased) = phase.and_then(|phase| {
+            state
+                .data
+                .public_mint_phase_purchases
+                .get_mut(&phase.name)
+                .and_then(|purchases| purchases.get_mut(&caller))
+        }) {
+            *purchased -= quantity.clone();
+        }
+    });
+}
+
+// Returns the payment minus the ledger fee; failed refunds are kept for settle_public_mint.
+async fn refund_public_mint(ledger: Principal, buyer: Principal, amount: Nat) -> Option<Nat> {
+    let refund = match ledger::icrc1_fee(ledger).await {
+        Ok(fee) if amount > fee => {
+            transfer_out(
+                ledger,
+                Account {
+                    owner: buyer,
+                    subaccount: None,
+                },
+                amount.clone() - fee,
+            )
+            .await
+        }
+        Ok(_) => return None,
+        Err(e) => Err(public_mint::settle_public_mint::SettlePublicMintError::LedgerCallFailed(e)),
+    };
+
+    match refund {
+        Ok(block) => Some(block),
+        Err(e) => {
+            trace(&format!("Public mint refund to {} failed: {:?}", buyer, e));
+            mutate_state(|state| {
+                *state
+                    .data
+                    .public_mint_pending_refunds
+                    .entry(buyer)
+                    .or_default() += amount;
+            });
+            None
+        }
+    }
+}
+
+// Proceeds that cannot be forwarded now are kept for settle_public_mint.
+async fn forward_public_mint_proceeds(config: &PublicMintConfig, amount: Nat) {
+    let forwarded = match ledger::icrc1_fee(config.ledger).await {
+        Ok(fee) if amount > fee => {
+            transfer_out(config.ledger, config.treasury, amount.clone() - fee)
+                .await
+                .is_ok()
+        }
+        _ => false,
+    };
+
+    if !forwarded {
+        mutate_state(|state| state.data.public_mint_unsettled += amount);
+    }
+}
+
+async fn transfer_out(
+    ledger: Principal,
+    to: Account,
+    amount: Nat,
+) -> Result<Nat, public_mint::settle_public_mint::SettlePublicMintError> {
+    match ledger::icrc1_transfer(
+        ledger,
+        TransferArg {
+            from_subaccount: None,
+            to,
+            fee: None,
+            created_at_time: None,
+            memo: None,
+            amount,
+        },
+    )
+    .await
+    {
+        Ok(Ok(block)) => Ok(block),
+        Ok(Err(e)) => {
+            Err(public_mint::settle_public_mint::SettlePublicMintError::TransferFailed(e))
+        }
+        Err(e) => Err(public_mint::settle_public_mint::SettlePublicMintError::LedgerCallFailed(e)),
+    }
+}
@@ -0,0 +1,186 @@
+use crate::guards::{caller_has_update_metadata_permission, GuardManagement};
+use crate::state::{icrc3_add_transaction, mutate_state, read_state};
+use crate::types::metadata::{is_reserved_metadata_key, __METADATA};
+use crate::types::reveal;
+use crate::types::reveal::{reveal_commitment, RevealCommitment};
+use crate::updates::management::can_replace_metadata;
+use crate::utils::{is_timelock_enabled, trace, update_token_transaction};
+
+use candid::Nat;
+use ic_cdk_macros::{query, update};
+use std::collections::HashSet;
+
+#[update(guard = "caller_has_update_metadata_permission")]
+pub fn commit_reveal(commitment: reveal::commit_reveal::Args) -> reveal::commit_reveal::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller)
+        .map_err(|_| reveal::commit_reveal::CommitRevealError::ConcurrentManagementCall)?;
+
+    if commitment.len() != 32 {
+        return Err(reveal::commit_reveal::CommitRevealError::InvalidCommitment);
+    }
+
+    // A new drop can only be committed once the previous one has been revealed.
+    if read_state(|state| {
+        state
+            .data
+            .reveal
+            .as_ref()
+            .is_some_and(|reveal| reveal.revealed_at.is_none())
+    }) {
+        return Err(reveal::commit_reveal::CommitRevealError::AlreadyCommitted);
+    }
+
+    let timestamp = ic_cdk::api::time();
+    let previous_metadata = read_state(|state| {
+        state
+            .data
+            .reveal
+            .as_ref()
+            .map(RevealCommitment::metadata)
+            .unwrap_or_default()
+    });
+    let new_commitment = RevealCommitment {
+        commitment,
+        committed_at: timestamp,
+        salt: None,
+        revealed_at: None,
+    };
+
+    icrc3_add_transaction(update_token_transaction(
+        Nat::from(0u64),
+        caller,
+        previous_metadata,
+        new_commitment.metadata(),
+        timestamp,
+    ))
+    .map_err(|e| reveal::commit_reveal::CommitRevealError::StorageCanisterError(e.to_string()))?;
+
+    mutate_state(|state| state.data.reveal = Some(new_commitment));
+    Ok(())
+}
+
+#[update(guard = "caller_has_update_metadata_permission")]
+pub fn reveal(req: reveal::reveal::Args) -> reveal::reveal::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller)
+        .map_err(|_| reveal::reveal::RevealError::ConcurrentManagementCall)?;
+
+    if is_timelock_enabled() {
+        return Err(reveal::reveal::RevealError::TimelockRequired);
+    }
+
+    let commitment = read_state(|state| state.data.reveal.clone())
+        .ok_or(reveal::reveal::RevealError::NotCommitted)?;
+
+    if commitment.revealed_at.is_some() {
+        return Err(reveal::reveal::RevealError::AlreadyRevealed);
+    }
+
+    let mut seen = HashSet::new();
+    for entry in &req.tokens {
+        if !seen.insert(entry.token_id.clone()) {
+            return Err(reveal::reveal::RevealError::DuplicateToken(
+                entry.token_id.clone(),
+            ));
+        }
+    }
+
+    if reveal_commitment(&req.salt, &req.tokens).as_slice() != commitment.commitment.as_slice() {
+        return Err(reveal::reveal::RevealError::CommitmentMismatch);
+    }
+
+    // Everything is checked and logged before the first swap, so the metadata of the tokens is
+    // either revealed in full or left untouched.
+    let mut updates = Vec::new();
+    for entry in req.tokens {
+        let Some(token) = read_state(|state| state.data.get_token_by_id(&entry.token_id)) else {
+            // Burned tokens are part of the commitment but have nothing to reveal.
+            continue;
+        };
+
+        if let Some((key, _)) = entry
+            .metadata
+            .iter()
+            .find(|(key, _)| is_reserved_metadata_key(key))
+        {
+            return Err(reveal::reveal::RevealError::ReservedMetadataKey(
+                key.clone(),
+            ));
+        }
+
+        read_state(|state| state.data.metadata_validation.validate(&entry.metadata))
+            .map_err(reveal::reveal::RevealError::InvalidMetadata)?;
+
+        let previous_metadata = __METADATA
+            .with_borrow(|m| m.get_all_data(Some(entry.token_id.clone())))
+            .unwrap_or_default();
+
+        // Royalties and the other reserved keys are kept, as in `update_nft_metadata`.
+        let mut metadata = entry.metadata;
+        metadata.extend(
+            previous_metadata
+                .iter()
+                .filter(|(key, _)| is_reserved_metadata_key(key))
+                .map(|(key, value)| (key.clone(), value.0.clone())),
+        );
+
+        if !read_state(|state| {
+            can_replace_metadata(
+                &state.data.frozen_metadata,
+                &entry.token_id,
+                Some(&previous_metadata),
+                &metadata,
+            )
+        }) {
+            return Err(reveal::reveal::RevealError::MetadataFrozen(entry.token_id));
+        }
+
+        updates.push((token, metadata, previous_metadata));
+    }
+
+    let timestamp = ic_cdk::api::time();
+    for (token, metadata, previous_metadata) in &updates {
+        icrc3_add_transaction(update_token_transaction(
+            token.token_id.clone(),
+            caller,
+            previous_metadata
+                .iter()
+                .map(|(key, value)| (key.clone(), value.0.clone()))
+                .collect(),
+            metadata.iter().cloned().collect(),
+            timestamp,
+        ))
+        .map_err(|e| reveal::reveal::RevealError::StorageCanisterError(e.to_string()))?;
+    }
+
+    let revealed_count = updates.len();
+    for (mut token, metadata, _) in updates {
+        __METADATA.with_borrow_mut(|m| token.replace_metadata(m, metadata));
+    }
+
+    let revealed = RevealCommitment {
+        salt: Some(req.salt),
+        revealed_at: Some(timestamp),
+        ..commitment.clone()
+    };
+
+    icrc3_add_transaction(update_token_transaction(
+        Nat::from(0u64),
+        caller,
+        commitment.metadata(),
+        revealed.metadata(),
+        timestamp,
+    ))
+    .map_err(|e| reveal::reveal::RevealError::StorageCanisterError(e.to_string()))?;
+
+    mutate_state(|state| state.data.reveal = Some(revealed));
+
+    trace(&format!("Revealed metadata for {} tokens", revealed_count));
+    Ok(())
+}
+
+#[query]
+pub fn get_reveal_commitment() -> reveal::get_reveal_commitment::Response {
+    read_state(|state| state.data.reveal.clone())
+}
@@ -0,0 +1,265 @@
+use crate::guards::GuardManagement;
+use crate::state::{icrc3_add_transaction, mutate_state, read_state};
+use crate::types::icrc3::ExtensionTransaction;
+use crate::types::icrc7;
+use crate::types::management::update_collection_metadata;
+use crate::types::metadata::is_reserved_metadata_key;
+use crate::types::royalties::account_to_value;
+use crate::types::timelock::{self, PendingOperation, TimelockedOperation};
+use crate::updates::management::{apply_collection_metadata_update, replace_token_metadata};
+use crate::utils::{is_multisig_enabled, trace};
+
+use candid::{Nat, Principal};
+use ic_cdk_macros::{query, update};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+#[query]
+pub fn get_timelock_config() -> timelock::get_timelock_config::Response {
+    read_state(|state| state.data.timelock.clone())
+}
+
+#[update]
+pub fn schedule_operation(
+    operation: timelock::schedule_operation::Args,
+) -> timelock::schedule_operation::Response {
+    use timelock::schedule_operation::ScheduleOperationError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller)
+        .map_err(|_| ScheduleOperationError::ConcurrentManagementCall)?;
+
+    let permission = operation.permission();
+    if !read_state(|state| {
+        state
+            .data
+            .permissions
+            .has_permission(&caller, &permission, ic_cdk::api::time())
+    }) {
+        return Err(ScheduleOperationError::Unauthorized);
+    }
+    if operation.requires_multisig() && is_multisig_enabled() {
+        return Err(ScheduleOperationError::MultisigRequired);
+    }
+
+    schedule_operation_impl(caller, operation)
+}
+
+// Shared by `schedule_operation` and approved multisig proposals.
+pub(crate) fn schedule_operation_impl(
+    caller: Principal,
+    operation: TimelockedOperation,
+) -> timelock::schedule_operation::Response {
+    use timelock::schedule_operation::ScheduleOperationError;
+
+    match &operation {
+        TimelockedOperation::SetTimelockConfig(Some(config)) if !config.is_valid() => {
+            return Err(ScheduleOperationError::InvalidOperation(
+                "Invalid timelock config".to_string(),
+            ));
+        }
+        TimelockedOperation::ReplaceTokenMetadata(args) => {
+            if let Some((key, _)) = args
+                .metadata
+                .iter()
+                .find(|(key, _)| is_reserved_metadata_key(key))
+            {
+                return Err(ScheduleOperationError::InvalidOperation(format!(
+                    "Reserved metadata key: {}",
+                    key
+                )));
+            }
+            if read_state(|state| state.data.get_token_by_id(&args.token_id)).is_none() {
+                return Err(ScheduleOperationError::InvalidOperation(
+                    "Token does not exist".to_string(),
+                ));
+            }
+            read_state(|state| state.data.metadata_validation.validate(&args.metadata))
+                .map_err(|e| ScheduleOperationError::InvalidOperation(format!("{:?}", e)))?;
+        }
+        _ => {}
+    }
+
+    let now = ic_cdk::api::time();
+    let delay = read_state(|state| state.data.timelock.as_ref().map(|config| config.delay))
+        .unwrap_or_default();
+    let pending = PendingOperation {
+        operation_id: read_state(|state| state.data.next_operation_id.clone()),
+        operation,
+        scheduled_by: caller,
+        scheduled_at: now,
+        executes_at: now.saturating_add(delay),
+    };
+
+    let mut tx = operation_block(&pending);
+    tx.insert("from".to_string(), account_to_value(&Account::from(caller)));
+    tx.insert(
+        "exe".to_string(),
+        ICRC3Value::Nat(Nat::from(pending.executes_at)),
+    );
+    icrc3_add_transaction(ExtensionTransaction::new("op_schedule", now, tx))
+        .map_err(|e| ScheduleOperationError::StorageCanisterError(e.to_string()))?;
+
+    let operation_id = pending.operation_id.clone();
+    mutate_state(|state| {
+        state.data.next_operation_id += Nat::from(1u64);
+        state
+            .data
+            .pending_operations
+            .insert(operation_id.clone(), pending);
+    });
+
+    if delay == 0 {
+        execute_due_operations();
+    }
+
+    Ok(operation_id)
+}
+
+#[update]
+pub fn cancel_operation(
+    operation_id: timelock::cancel_operation::Args,
+) -> timelock::cancel_operation::Response {
+    use timelock::cancel_operation::CancelOperationError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal =
+        GuardManagement::new(caller).map_err(|_| CancelOperationError::ConcurrentManagementCall)?;
+
+    if !read_state(|state| {
+        state
+            .data
+            .timelock
+            .as_ref()
+            .is_some_and(|config| config.is_guardian(&caller))
+    }) {
+        return Err(CancelOperationError::NotGuardian);
+    }
+
+    let pending = read_state(|state| state.data.pending_operations.get(&operation_id).cloned())
+        .ok_or(CancelOperationError::OperationNotFound)?;
+
+    let mut tx = operation_block(&pending);
+    tx.insert("from".to_string(), account_to_value(&Account::from(caller)));
+    icrc3_add_transaction(ExtensionTransaction::new(
+        "op_cancel",
+        ic_cdk::api::time(),
+        tx,
+    ))
+    .map_err(|e| CancelOperationError::StorageCanisterError(e.to_string()))?;
+
+    mutate_state(|state| state.data.pending_operations.remove(&operation_id));
+    Ok(())
+}
+
+#[query]
+pub fn pending_operations(
+    args: timelock::pending_operations::Args,
+) -> timelock::pending_operations::Response {
+    let max_take_value = read_state(|state| state.data.max_take_value.clone())
+        .unwrap_or(Nat::from(icrc7::DEFAULT_MAX_TAKE_VALUE));
+    if let Some(take) = &args.take {
+        if take.0 > max_take_value.0 {
+            ic_cdk::trap(format!(
+                "max_take_value exceeded. Limit is {}. Retry with a smaller take value.",
+                max_take_value.0
+            ));
+        }
+    }
+
+    read_state(|state| {
+        let take = usize::try_from(
+            args.take
+                .unwrap_or_else(|| {
+                    state
+                        .data
+                        .default_take_value
+                        .clone()
+                        .unwrap_or(Nat::from(icrc7::DEFAULT_TAKE_VALUE))
+                })
+                .0,
+        )
+        .unwrap_or(icrc7::DEFAULT_TAKE_VALUE);
+
+        let start = match args.prev {
+            Some(prev) => Bound::Excluded(prev),
+            None => Bound::Unbounded,
+        };
+        state
+            .data
+            .pending_operations
+            .range((start, Bound::Unbounded))
+            .take(take)
+            .map(|(_, pending)| pending.clone())
+            .collect()
+    })
+}
+
+// Runs every operation whose delay has passed, in the order they were scheduled.
+pub(crate) fn execute_due_operations() {
+    let now = ic_cdk::api::time();
+    let due: Vec<PendingOperation> = mutate_state(|state| {
+        let ids: Vec<Nat> = state
+            .data
+            .pending_operations
+            .values()
+            .filter(|pending| pending.is_due(now))
+            .map(|pending| pending.operation_id.clone())
+            .collect();
+        ids.iter()
+            .filter_map(|id| state.data.pending_operations.remove(id))
+            .collect()
+    });
+
+    for pending in due {
+        let mut tx = operation_block(&pending);
+        if let Err(e) = execute_operation(pending) {
+            tx.insert("err".to_string(), ICRC3Value::Text(e));
+        }
+        log_operation_block("op_execute", tx);
+    }
+}
+
+// The principal that scheduled the operation stands in for the caller of the underlying call.
+fn execute_operation(pending: PendingOperation) -> Result<(), String> {
+    let caller = pending.scheduled_by;
+    match pending.operation {
+        TimelockedOperation::SetSupplyCap(supply_cap) => apply_collection_metadata_update(
+            caller,
+            update_collection_metadata::Args {
+                supply_cap: Some(supply_cap),
+                ..Default::default()
+            },
+        )
+        .map_err(|e| format!("{:?}", e)),
+        TimelockedOperation::ReplaceTokenMetadata(args) => replace_token_metadata(caller, args)
+            .map(|_| ())
+            .map_err(|e| format!("{:?}", e)),
+        TimelockedOperation::SetTimelockConfig(config) => {
+            mutate_state(|state| state.data.timelock = config);
+            Ok(())
+        }
+    }
+}
+
+fn operation_block(pending: &PendingOperation) -> BTreeMap<String, ICRC3Value> {
+    let mut tx = BTreeMap::new();
+    tx.insert(
+        "oid".to_string(),
+        ICRC3Value::Nat(pending.operation_id.clone()),
+    );
+    tx.insert(
+        "op".to_string(),
+        ICRC3Value::Text(pending.operation.name().to_string()),
+    );
+    tx
+}
+
+fn log_operation_block(btype: &str, tx: BTreeMap<String, ICRC3Value>) {
+    let timestamp = ic_cdk::api::time();
+    if let Err(e) = icrc3_add_transaction(ExtensionTransaction::new(btype, timestamp, tx)) {
+        trace(&format!("Failed to log {} block: {}", btype, e));
+    }
+}
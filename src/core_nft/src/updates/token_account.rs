@@ -0,0 +1,91 @@
+use crate::guards::{caller_has_update_collection_metadata_permission, GuardManagement};
+use crate::ledger;
+use crate::state::{mutate_state, read_state};
+use crate::types::nest::NestingError;
+use crate::types::nft::token_subaccount;
+use crate::types::token_account;
+use crate::utils::{is_collection_paused, token_account, trace};
+
+use ic_cdk_macros::{query, update};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::TransferArg;
+
+#[update(guard = "caller_has_update_collection_metadata_permission")]
+pub fn set_token_account_ledgers(
+    ledgers: token_account::set_token_account_ledgers::Args,
+) -> token_account::set_token_account_ledgers::Response {
+    let caller = ic_cdk::api::msg_caller();
+    let _guard_principal = GuardManagement::new(caller).map_err(|_| {
+        token_account::set_token_account_ledgers::SetTokenAccountLedgersError::ConcurrentManagementCall
+    })?;
+
+    mutate_state(|state| state.data.token_account_ledgers = ledgers);
+    Ok(())
+}
+
+#[query]
+pub fn get_token_account_ledgers() -> token_account::get_token_account_ledgers::Response {
+    read_state(|state| state.data.token_account_ledgers.clone())
+}
+
+#[query]
+pub fn get_token_account(
+    token_id: token_account::get_token_account::Args,
+) -> token_account::get_token_account::Response {
+    read_state(|state| state.data.token_exists(&token_id)).then(|| token_account(&token_id))
+}
+
+#[update]
+pub async fn token_account_transfer(
+    req: token_account::token_account_transfer::Args,
+) -> token_account::token_account_transfer::Response {
+    use token_account::token_account_transfer::TokenAccountTransferError;
+
+    let caller = Account {
+        owner: ic_cdk::api::msg_caller(),
+        subaccount: req.from_subaccount,
+    };
+
+    if is_collection_paused() {
+        return Err(TokenAccountTransferError::CollectionPaused);
+    }
+
+    // A nested token's account is controlled by the owner of the root of its tree.
+    let owner =
+        read_state(|state| state.data.root_owner_of(&req.token_id)).map_err(|e| match e {
+            NestingError::TokenDoesNotExist => TokenAccountTransferError::TokenDoesNotExist,
+            NestingError::MaxDepthExceeded => TokenAccountTransferError::MaxDepthExceeded,
+        })?;
+    if owner != caller {
+        return Err(TokenAccountTransferError::NotTokenOwner);
+    }
+
+    if !read_state(|state| state.data.token_account_ledgers.contains(&req.ledger)) {
+        return Err(TokenAccountTransferError::UnsupportedLedger);
+    }
+
+    let transfer = ledger::icrc1_transfer(
+        req.ledger,
+        TransferArg {
+            from_subaccount: Some(token_subaccount(&req.token_id)),
+            to: req.to,
+            fee: None,
+            created_at_time: req.created_at_time,
+            memo: req.memo,
+            amount: req.amount.clone(),
+        },
+    )
+    .await;
+
+    match transfer {
+        Ok(Ok(block_index)) => {
+            trace(&format!(
+                "Token {} account sent {} on {}",
+                req.token_id, req.amount, req.ledger
+            ));
+            Ok(block_index)
+        }
+        Ok(Err(e)) => Err(TokenAccountTransferError::TransferFailed(e)),
+        Err(e) => Err(TokenAccountTransferError::LedgerCallFailed(e)),
+    }
+}
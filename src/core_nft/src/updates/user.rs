@@ -0,0 +1,105 @@
+use crate::state::{icrc3_add_transaction, mutate_state, read_state};
+use crate::types::icrc3::ExtensionTransaction;
+use crate::types::icrc7;
+use crate::types::nft::TokenUser;
+use crate::types::royalties::account_to_value;
+use crate::types::user;
+use crate::types::wrapped_types::WrappedAccount;
+use crate::updates::icrc37::is_approved_spender;
+use crate::utils::{is_collection_paused, trace};
+
+use candid::{Nat, Principal};
+use ic_cdk_macros::{query, update};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use std::collections::BTreeMap;
+
+#[update]
+pub fn set_user(req: user::set_user::Args) -> user::set_user::Response {
+    use user::set_user::SetUserError;
+
+    let caller = ic_cdk::api::msg_caller();
+    let now = ic_cdk::api::time();
+
+    if is_collection_paused() {
+        return Err(SetUserError::CollectionPaused);
+    }
+
+    let owner = read_state(|state| state.data.owner_of(&req.token_id))
+        .ok_or(SetUserError::TokenDoesNotExist)?;
+
+    let spender = Account {
+        owner: caller,
+        subaccount: req.spender_subaccount,
+    };
+    if spender != owner && !is_approved_spender(&req.token_id, &owner, &spender, now) {
+        return Err(SetUserError::Unauthorized);
+    }
+
+    if let Some(user) = req.user {
+        if user.owner == Principal::anonymous() || user == owner {
+            return Err(SetUserError::InvalidUser);
+        }
+        if req.expires_at <= now {
+            return Err(SetUserError::InvalidExpiry);
+        }
+    }
+
+    let mut tx = BTreeMap::new();
+    tx.insert("tid".to_string(), ICRC3Value::Nat(req.token_id.clone()));
+    tx.insert("from".to_string(), account_to_value(&spender));
+    if let Some(user) = req.user {
+        tx.insert("to".to_string(), account_to_value(&user));
+        tx.insert(
+            "exp".to_string(),
+            ICRC3Value::Nat(Nat::from(req.expires_at)),
+        );
+    }
+
+    let index = icrc3_add_transaction(ExtensionTransaction::new("set_user", now, tx))
+        .map_err(|e| SetUserError::StorageCanisterError(e.to_string()))?;
+
+    mutate_state(|state| match req.user {
+        Some(user) => state.data.set_token_user(
+            &req.token_id,
+            TokenUser {
+                user: WrappedAccount(user),
+                expires_at: req.expires_at,
+            },
+        ),
+        None => {
+            state.data.clear_token_user(&req.token_id);
+        }
+    });
+
+    trace(&format!(
+        "User of token {} set to {:?}",
+        req.token_id, req.user
+    ));
+    Ok(Nat::from(index))
+}
+
+#[query]
+pub fn icrc7_user_of(token_ids: user::icrc7_user_of::Args) -> user::icrc7_user_of::Response {
+    let icrc7_max_query_batch_size = read_state(|state| state.data.max_query_batch_size.clone());
+    let max_query_batch_size =
+        icrc7_max_query_batch_size.unwrap_or(Nat::from(icrc7::DEFAULT_MAX_QUERY_BATCH_SIZE));
+
+    if token_ids.len()
+        > usize::try_from(max_query_batch_size.0.clone())
+            .unwrap_or(icrc7::DEFAULT_MAX_QUERY_BATCH_SIZE as usize)
+    {
+        ic_cdk::trap(format!(
+            "max_query_batch_size exceeded. Limit is {}. Retry with a smaller batch size.",
+            max_query_batch_size.0
+        ));
+    }
+
+    let now = ic_cdk::api::time();
+    read_state(|state| {
+        token_ids
+            .iter()
+            .map(|token_id| state.data.user_of(token_id, now).map(Into::into))
+            .collect()
+    })
+}
@@ -1,6 +1,17 @@
-use crate::state::read_state;
+use crate::state::{icrc3_add_transaction, read_state};
 use crate::types::icrc7;
-use candid::Nat;
+use crate::types::metadata::{
+    is_reserved_metadata_key, MetadataValidationError, __METADATA, TRANSFERABLE_METADATA_KEY,
+};
+use crate::types::nft::token_subaccount;
+use crate::types::royalties::{Royalties, ROYALTIES_METADATA_KEY};
+use crate::types::value_custom::CustomValue;
+
+use bity_ic_icrc3::transaction::{ICRC7Transaction, ICRC7TransactionData};
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use std::collections::{BTreeMap, HashMap};
 
 pub fn check_memo(memo: Option<serde_bytes::ByteBuf>) -> Result<(), String> {
     if let Some(ref memo) = memo {
@@ -27,6 +38,229 @@ pub fn check_memo(memo: Option<serde_bytes::ByteBuf>) -> Result<(), String> {
     Ok(())
 }
 
+pub enum CreatedAtTimeError {
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+}
+
+pub fn check_created_at_time(
+    created_at_time: u64,
+    current_time: u64,
+) -> Result<(), CreatedAtTimeError> {
+    let (permitted_drift, tx_window) = read_state(|state| {
+        (
+            state.data.permitted_drift.clone(),
+            state.data.tx_window.clone(),
+        )
+    });
+
+    let drift = permitted_drift
+        .map(|d| u64::try_from(d.0).unwrap_or(icrc7::DEFAULT_PERMITTED_DRIFT))
+        .unwrap_or(icrc7::DEFAULT_PERMITTED_DRIFT);
+
+    if created_at_time > current_time + drift {
+        return Err(CreatedAtTimeError::CreatedInFuture {
+            ledger_time: current_time,
+        });
+    }
+
+    let tx_window = tx_window
+        .map(|d| u64::try_from(d.0).unwrap_or(icrc7::DEFAULT_TX_WINDOW))
+        .unwrap_or(icrc7::DEFAULT_TX_WINDOW);
+
+    if created_at_time < current_time.saturating_sub(tx_window + drift) {
+        return Err(CreatedAtTimeError::TooOld);
+    }
+
+    Ok(())
+}
+
+// Only the changed keys are logged; a key missing from the new metadata was unset.
+pub fn update_token_transaction(
+    token_id: Nat,
+    caller: Principal,
+    previous_metadata: BTreeMap<String, ICRC3Value>,
+    new_metadata: BTreeMap<String, ICRC3Value>,
+    timestamp: u64,
+) -> ICRC7Transaction {
+    let mut metadata_map = BTreeMap::new();
+    metadata_map.insert(
+        "icrc7:previous_metadata".to_string(),
+        ICRC3Value::Map(previous_metadata),
+    );
+    metadata_map.insert(
+        "icrc7:new_metadata".to_string(),
+        ICRC3Value::Map(new_metadata),
+    );
+
+    ICRC7Transaction::new(
+        "7update_token".to_string(),
+        timestamp,
+        ICRC7TransactionData {
+            op: "7update_token".to_string(),
+            tid: Some(token_id),
+            from: Some(Account {
+                owner: caller,
+                subaccount: None,
+            }),
+            to: None,
+            meta: Some(ICRC3Value::Map(metadata_map)),
+            memo: None,
+            created_at_time: Some(Nat::from(timestamp)),
+        },
+    )
+}
+
+#[derive(Debug)]
+pub enum CollectionMetadataError {
+    InvalidMetadata(MetadataValidationError),
+    ReservedKey(String),
+    MetadataFrozen,
+    StorageCanisterError(String),
+}
+
+// Collection level keys live under the reserved token 0 of __METADATA.
+pub fn set_collection_metadata(
+    caller: Principal,
+    collection_metadata: HashMap<String, CustomValue>,
+) -> Result<(), CollectionMetadataError> {
+    if let Some(key) = collection_metadata
+        .keys()
+        .find(|key| is_reserved_metadata_key(key))
+    {
+        return Err(CollectionMetadataError::ReservedKey(key.clone()));
+    }
+
+    let entries: Vec<(String, ICRC3Value)> = collection_metadata
+        .iter()
+        .map(|(key, value)| (key.clone(), value.0.clone()))
+        .collect();
+    read_state(|state| state.data.metadata_validation.validate(&entries))
+        .map_err(CollectionMetadataError::InvalidMetadata)?;
+
+    let current_metadata = __METADATA
+        .with_borrow(|m| m.get_all_data(Some(Nat::from(0u64))))
+        .unwrap_or_default();
+
+    let mut previous_values = BTreeMap::new();
+    let mut new_values = BTreeMap::new();
+
+    for (key, value) in collection_metadata.iter() {
+        match current_metadata.get(key) {
+            Some(previous_value) if previous_value == value => continue,
+            Some(previous_value) => {
+                previous_values.insert(key.clone(), previous_value.0.clone());
+            }
+            None => {}
+        }
+        new_values.insert(key.clone(), value.0.clone());
+    }
+
+    if new_values.is_empty() {
+        return Ok(());
+    }
+
+    if read_state(|state| {
+        new_values.keys().any(|key| {
+            state
+                .data
+                .frozen_metadata
+                .is_key_frozen(&Nat::from(0u64), key)
+        })
+    }) {
+        return Err(CollectionMetadataError::MetadataFrozen);
+    }
+
+    let transaction = update_token_transaction(
+        Nat::from(0u64),
+        caller,
+        previous_values,
+        new_values,
+        ic_cdk::api::time(),
+    );
+
+    icrc3_add_transaction(transaction)
+        .map_err(|e| CollectionMetadataError::StorageCanisterError(e.to_string()))?;
+
+    __METADATA.with_borrow_mut(|m| {
+        for (key, value) in collection_metadata {
+            m.insert_data(None, key, value);
+        }
+    });
+
+    Ok(())
+}
+
+pub fn get_collection_metadata() -> BTreeMap<String, ICRC3Value> {
+    __METADATA
+        .with_borrow(|m| m.get_all_data(Some(Nat::from(0u64))))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(k, v)| (k, v.0))
+        .collect()
+}
+
+// Tokens are transferable unless "icrc7:transferable" is "false" on the token, or on the
+// collection (token 0) when the token does not set it.
+pub fn is_token_transferable(token_id: &Nat) -> bool {
+    __METADATA.with_borrow(|m| {
+        [token_id.clone(), Nat::from(0u64)]
+            .into_iter()
+            .find_map(|id| {
+                m.get_data(Some(id), TRANSFERABLE_METADATA_KEY.to_string())
+                    .ok()
+            })
+            .is_none_or(|value| value.0 != ICRC3Value::Text("false".to_string()))
+    })
+}
+
+// Token level royalties take precedence over the collection defaults stored under token 0.
+pub fn get_royalties(token_id: &Nat) -> Option<Royalties> {
+    __METADATA.with_borrow(|m| {
+        [token_id.clone(), Nat::from(0u64)]
+            .into_iter()
+            .find_map(|id| {
+                m.get_data(Some(id), ROYALTIES_METADATA_KEY.to_string())
+                    .ok()
+                    .and_then(|value| Royalties::from_value(&value.0))
+            })
+    })
+}
+
+// Account of the collection canister that belongs to token_id; see nft::token_subaccount.
+pub fn token_account(token_id: &Nat) -> Account {
+    Account {
+        owner: ic_cdk::api::canister_self(),
+        subaccount: Some(token_subaccount(token_id)),
+    }
+}
+
+pub fn non_transferable_message(token_id: &Nat) -> String {
+    format!(
+        "Token {} is soulbound and cannot be transferred or approved",
+        token_id
+    )
+}
+
+pub const COLLECTION_PAUSED_MESSAGE: &str = "Collection is paused";
+
+pub const ROYALTY_ENFORCEMENT_MESSAGE: &str =
+    "Royalty enforcement is enabled, sales must go through sale_transfer_from";
+
+pub fn is_collection_paused() -> bool {
+    read_state(|state| state.data.paused)
+}
+
+// While a multisig config is set, sensitive management calls only run through proposals.
+pub fn is_multisig_enabled() -> bool {
+    read_state(|state| state.data.multisig.is_some())
+}
+
+// While a timelock config is set, timelocked operations only run through `schedule_operation`.
+pub fn is_timelock_enabled() -> bool {
+    read_state(|state| state.data.timelock.is_some())
+}
+
 pub fn trace(msg: &str) {
     unsafe {
         ic0::debug_print(msg.as_ptr() as usize, msg.len() as usize);
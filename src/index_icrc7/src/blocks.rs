@@ -22,6 +22,24 @@ pub enum BlockType {
     CollectionApprove(CollectionApproveBlock),
     Revoke(RevokeBlock),
     RevokeCollection(RevokeCollectionBlock),
+    Offer(OfferBlock),
+    OfferAccept(OfferBlock),
+    OfferCancel(OfferBlock),
+    OfferExpire(OfferBlock),
+    SetUser(SetUserBlock),
+    NestAttach(NestBlock),
+    NestDetach(NestBlock),
+    PermissionGrant(AdminBlock),
+    PermissionRevoke(AdminBlock),
+    RoleUpdate(AdminBlock),
+    CollectionUpdate(AdminBlock),
+    ProposalCreate(AdminBlock),
+    ProposalApprove(AdminBlock),
+    ProposalExecute(AdminBlock),
+    ProposalExpire(AdminBlock),
+    OperationSchedule(AdminBlock),
+    OperationCancel(AdminBlock),
+    OperationExecute(AdminBlock),
 }
 
 #[derive(CandidType, Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
@@ -51,6 +69,21 @@ pub struct RevokeBlock;
 #[derive(CandidType, Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RevokeCollectionBlock;
 
+// Shared by the offer, offer_accept, offer_cancel and offer_expire blocks.
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OfferBlock;
+
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SetUserBlock;
+
+// Shared by the nest_attach and nest_detach blocks.
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NestBlock;
+
+// Shared by the perm_grant, perm_revoke, role_update, coll_update, proposal_* and op_* blocks.
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AdminBlock;
+
 impl FromStr for BlockType {
     type Err = String;
 
@@ -65,6 +98,24 @@ impl FromStr for BlockType {
             "37revoke" => Ok(BlockType::Revoke(RevokeBlock)),
             "37revoke_coll" => Ok(BlockType::RevokeCollection(RevokeCollectionBlock)),
             "7update_token" => Ok(BlockType::UpdateTokenMetadata(UpdateTokenMetadataBlock)),
+            "offer" => Ok(BlockType::Offer(OfferBlock)),
+            "offer_accept" => Ok(BlockType::OfferAccept(OfferBlock)),
+            "offer_cancel" => Ok(BlockType::OfferCancel(OfferBlock)),
+            "offer_expire" => Ok(BlockType::OfferExpire(OfferBlock)),
+            "set_user" => Ok(BlockType::SetUser(SetUserBlock)),
+            "nest_attach" => Ok(BlockType::NestAttach(NestBlock)),
+            "nest_detach" => Ok(BlockType::NestDetach(NestBlock)),
+            "perm_grant" => Ok(BlockType::PermissionGrant(AdminBlock)),
+            "perm_revoke" => Ok(BlockType::PermissionRevoke(AdminBlock)),
+            "role_update" => Ok(BlockType::RoleUpdate(AdminBlock)),
+            "coll_update" => Ok(BlockType::CollectionUpdate(AdminBlock)),
+            "proposal_create" => Ok(BlockType::ProposalCreate(AdminBlock)),
+            "proposal_approve" => Ok(BlockType::ProposalApprove(AdminBlock)),
+            "proposal_execute" => Ok(BlockType::ProposalExecute(AdminBlock)),
+            "proposal_expire" => Ok(BlockType::ProposalExpire(AdminBlock)),
+            "op_schedule" => Ok(BlockType::OperationSchedule(AdminBlock)),
+            "op_cancel" => Ok(BlockType::OperationCancel(AdminBlock)),
+            "op_execute" => Ok(BlockType::OperationExecute(AdminBlock)),
             _ => Err(format!("Unknown block type: {}", s)),
         }
     }
@@ -82,6 +133,24 @@ impl ToString for BlockType {
             BlockType::Revoke(_) => "37revoke".to_string(),
             BlockType::RevokeCollection(_) => "37revoke_coll".to_string(),
             BlockType::UpdateTokenMetadata(_) => "7update_token".to_string(),
+            BlockType::Offer(_) => "offer".to_string(),
+            BlockType::OfferAccept(_) => "offer_accept".to_string(),
+            BlockType::OfferCancel(_) => "offer_cancel".to_string(),
+            BlockType::OfferExpire(_) => "offer_expire".to_string(),
+            BlockType::SetUser(_) => "set_user".to_string(),
+            BlockType::NestAttach(_) => "nest_attach".to_string(),
+            BlockType::NestDetach(_) => "nest_detach".to_string(),
+            BlockType::PermissionGrant(_) => "perm_grant".to_string(),
+            BlockType::PermissionRevoke(_) => "perm_revoke".to_string(),
+            BlockType::RoleUpdate(_) => "role_update".to_string(),
+            BlockType::CollectionUpdate(_) => "coll_update".to_string(),
+            BlockType::ProposalCreate(_) => "proposal_create".to_string(),
+            BlockType::ProposalApprove(_) => "proposal_approve".to_string(),
+            BlockType::ProposalExecute(_) => "proposal_execute".to_string(),
+            BlockType::ProposalExpire(_) => "proposal_expire".to_string(),
+            BlockType::OperationSchedule(_) => "op_schedule".to_string(),
+            BlockType::OperationCancel(_) => "op_cancel".to_string(),
+            BlockType::OperationExecute(_) => "op_execute".to_string(),
         }
     }
 }
@@ -97,6 +166,23 @@ pub fn get_block_instance(block_type: &BlockType) -> Box<dyn TransactionDataExtr
         BlockType::Revoke(_) => Box::new(RevokeBlock),
         BlockType::RevokeCollection(_) => Box::new(RevokeCollectionBlock),
         BlockType::UpdateTokenMetadata(_) => Box::new(UpdateTokenMetadataBlock),
+        BlockType::Offer(_)
+        | BlockType::OfferAccept(_)
+        | BlockType::OfferCancel(_)
+        | BlockType::OfferExpire(_) => Box::new(OfferBlock),
+        BlockType::SetUser(_) => Box::new(SetUserBlock),
+        BlockType::NestAttach(_) | BlockType::NestDetach(_) => Box::new(NestBlock),
+        BlockType::PermissionGrant(_)
+        | BlockType::PermissionRevoke(_)
+        | BlockType::RoleUpdate(_)
+        | BlockType::CollectionUpdate(_)
+        | BlockType::ProposalCreate(_)
+        | BlockType::ProposalApprove(_)
+        | BlockType::ProposalExecute(_)
+        | BlockType::ProposalExpire(_)
+        | BlockType::OperationSchedule(_)
+        | BlockType::OperationCancel(_)
+        | BlockType::OperationExecute(_) => Box::new(AdminBlock),
     }
 }
 
@@ -154,6 +240,24 @@ impl BlockIndexer for BlockType {
             BlockType::Revoke(_) => true,
             BlockType::RevokeCollection(_) => false,
             BlockType::UpdateTokenMetadata(_) => true,
+            BlockType::Offer(_) => true,
+            BlockType::OfferAccept(_) => true,
+            BlockType::OfferCancel(_) => true,
+            BlockType::OfferExpire(_) => true,
+            BlockType::SetUser(_) => true,
+            BlockType::NestAttach(_) => true,
+            BlockType::NestDetach(_) => true,
+            BlockType::PermissionGrant(_) => true,
+            BlockType::PermissionRevoke(_) => true,
+            BlockType::RoleUpdate(_) => true,
+            BlockType::CollectionUpdate(_) => true,
+            BlockType::ProposalCreate(_) => true,
+            BlockType::ProposalApprove(_) => true,
+            BlockType::ProposalExecute(_) => true,
+            BlockType::ProposalExpire(_) => false,
+            BlockType::OperationSchedule(_) => true,
+            BlockType::OperationCancel(_) => true,
+            BlockType::OperationExecute(_) => false,
         }
     }
 
@@ -486,6 +590,76 @@ impl TransactionDataExtractor for RevokeCollectionBlock {
     }
 }
 
+impl TransactionDataExtractor for OfferBlock {
+    fn extract_accounts(&self, data: &ICRC3Value) -> Result<Vec<WrappedAccount>, String> {
+        match data {
+            ICRC3Value::Map(map) => {
+                let mut accounts = Vec::new();
+                if let Some(ICRC3Value::Map(tx)) = map.get("tx") {
+                    accounts.extend(
+                        ["from", "to"]
+                            .iter()
+                            .filter_map(|key| tx.get(*key))
+                            .filter_map(WrappedAccount::from_value),
+                    );
+                }
+                Ok(accounts)
+            }
+            _ => Err("Offer transaction data must be a map".to_string()),
+        }
+    }
+
+    fn extract_token_id(&self, data: &ICRC3Value) -> Result<Option<WrappedNat>, String> {
+        match data {
+            ICRC3Value::Map(map) => {
+                if let Some(ICRC3Value::Map(tx)) = map.get("tx") {
+                    if let Some(ICRC3Value::Nat(token_id)) = tx.get("tid") {
+                        Ok(Some(WrappedNat(token_id.clone())))
+                    } else {
+                        Err("Missing or invalid token ID field".to_string())
+                    }
+                } else {
+                    Err("Invalid transaction data".to_string())
+                }
+            }
+            _ => Err("Offer transaction data must be a map".to_string()),
+        }
+    }
+}
+
+// set_user and nesting blocks carry the same from/to/tid fields as offer blocks.
+impl TransactionDataExtractor for SetUserBlock {
+    fn extract_accounts(&self, data: &ICRC3Value) -> Result<Vec<WrappedAccount>, String> {
+        OfferBlock.extract_accounts(data)
+    }
+
+    fn extract_token_id(&self, data: &ICRC3Value) -> Result<Option<WrappedNat>, String> {
+        OfferBlock.extract_token_id(data)
+    }
+}
+
+impl TransactionDataExtractor for NestBlock {
+    fn extract_accounts(&self, data: &ICRC3Value) -> Result<Vec<WrappedAccount>, String> {
+        OfferBlock.extract_accounts(data)
+    }
+
+    fn extract_token_id(&self, data: &ICRC3Value) -> Result<Option<WrappedNat>, String> {
+        OfferBlock.extract_token_id(data)
+    }
+}
+
+// Administration blocks index the acting principal (`from`) and the grantee (`to`), and are not
+// tied to a token. Proposal and timelock blocks only carry `from`.
+impl TransactionDataExtractor for AdminBlock {
+    fn extract_accounts(&self, data: &ICRC3Value) -> Result<Vec<WrappedAccount>, String> {
+        OfferBlock.extract_accounts(data)
+    }
+
+    fn extract_token_id(&self, _data: &ICRC3Value) -> Result<Option<WrappedNat>, String> {
+        Ok(None)
+    }
+}
+
 pub async fn get_all_blocks(
     block_ids: Vec<u64>,
     sort_by: Option<SortBy>,
@@ -89,6 +89,29 @@ impl FromStr for WrappedAccount {
     }
 }
 
+impl WrappedAccount {
+    // Accounts logged as [owner blob, optional subaccount blob]. Older blocks log the owner
+    // principal as text.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Array(parts) => {
+                let owner = match parts.first()? {
+                    Value::Blob(owner) => Principal::try_from_slice(owner).ok()?,
+                    _ => return None,
+                };
+                let subaccount = match parts.get(1) {
+                    Some(Value::Blob(subaccount)) => Some(subaccount.as_slice().try_into().ok()?),
+                    Some(_) => return None,
+                    None => None,
+                };
+                Some(WrappedAccount(Account { owner, subaccount }))
+            }
+            Value::Text(text) => WrappedAccount::from_str(text).ok(),
+            _ => None,
+        }
+    }
+}
+
 impl Storable for WrappedAccount {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
         let mut buffer = Vec::new();